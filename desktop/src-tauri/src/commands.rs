@@ -1,15 +1,24 @@
 use serde::{Deserialize, Serialize};
 use shard::accounts::{Account, Accounts, load_accounts, remove_account, save_accounts, set_active};
 use shard::auth::{DeviceCode, request_device_code};
+use shard::backup::{BackupInfo, PruneReport, backup_after_session, create_backup, list_backups, prune_backups, restore_backup};
 use shard::config::{Config, load_config, save_config};
 use shard::content_store::{ContentStore, ContentType, Platform, SearchOptions, ContentItem, ContentVersion};
+use shard::diff::{DiffSide, ProfileDiff, diff_sides};
+use shard::drift::{CaptureReport, DriftItem, capture_drift, instance_drift};
+use shard::fetch::{FetchReport, fetch_profile_content};
+use shard::inherit::resolve_profile;
 use shard::java::{JavaInstallation, JavaValidation, AdoptiumRelease, detect_installations, validate_java_path, get_required_java_version, is_java_compatible, fetch_adoptium_release, download_and_install_java, find_compatible_java, get_managed_java, list_managed_runtimes};
 use shard::library::{Library, LibraryItem, LibraryFilter, LibraryItemInput, LibraryContentType, LibraryStats, Tag, ImportResult, UnusedItemsSummary, PurgeResult};
-use shard::logs::{LogEntry, LogFile, LogWatcher, list_log_files, list_crash_reports, read_log_file, read_log_tail};
-use shard::minecraft::{LaunchPlan, prepare};
+use shard::logs::{LogEntry, LogFile, LogWatcher, SessionLog, list_log_files, list_crash_reports, log_file_path, read_log_file, read_log_tail};
+use shard::instance::{IncompleteInstance, LinkStrategy, MaterializeSummary};
+use shard::minecraft::{LaunchOptions, LaunchPlan, QuickPlay, prepare};
 use shard::ops::{finish_device_code_flow, parse_loader, resolve_input, resolve_launch_account, ensure_fresh_account};
 use shard::paths::Paths;
-use shard::profile::{ContentRef, Loader, Profile, Runtime, clone_profile, create_profile, delete_profile, diff_profiles, list_profiles, load_profile, remove_mod, remove_resourcepack, remove_shaderpack, rename_profile, save_profile, upsert_mod, upsert_resourcepack, upsert_shaderpack};
+use shard::profile::{ContentRef, Loader, Profile, ResourcePackPolicy, Runtime, Server, clone_profile, create_profile, delete_profile, is_valid_world_name, list_profiles, load_profile, move_resourcepack, remove_datapack, remove_mod, remove_resourcepack, remove_server, remove_shaderpack, rename_profile, save_profile, upsert_datapack, upsert_mod, upsert_resourcepack, upsert_server, upsert_shaderpack};
+use shard::running::{RunningInstance, claim_launch, kill_instances, list_running, register as register_running, unregister as unregister_running};
+use shard::servers::{ServerEntry, list_instance_servers, store_server_icon};
+use shard::snapshot::{SnapshotInfo, list_snapshots, rollback_profile};
 use shard::skin::{
     MinecraftProfile,
     get_profile as get_mc_profile,
@@ -31,20 +40,15 @@ use shard::skin::{
 use shard::store::{ContentKind, store_content};
 use shard::template::{Template, list_templates, load_template, init_builtin_templates};
 use shard::updates::{StorageStats, UpdateCheckResult, get_storage_stats, check_all_updates, check_profile_updates, set_content_pinned, set_content_enabled, apply_update};
+use shard::worlds::{WorldInfo, copy_world, export_world_zip, import_world_zip, list_worlds};
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
 use tauri::{AppHandle, Emitter};
 
-#[derive(Serialize)]
-pub struct DiffResult {
-    pub only_a: Vec<String>,
-    pub only_b: Vec<String>,
-    pub both: Vec<String>,
-}
-
 #[derive(Serialize)]
 pub struct LaunchPlanDto {
     pub instance_dir: String,
+    pub materialized: MaterializeSummary,
     pub java_exec: String,
     pub jvm_args: Vec<String>,
     pub classpath: String,
@@ -160,6 +164,7 @@ pub fn create_profile_cmd(input: CreateProfileInput) -> Result<Profile, String>
         java: input.java.filter(|v| !v.trim().is_empty()),
         memory: input.memory.filter(|v| !v.trim().is_empty()),
         args,
+        ..Runtime::default()
     };
 
     create_profile(&paths, &input.id, &input.mc_version, loader, runtime)
@@ -211,12 +216,73 @@ pub fn update_profile_version_cmd(
 }
 
 #[tauri::command]
-pub fn diff_profiles_cmd(a: String, b: String) -> Result<DiffResult, String> {
+pub fn list_snapshots_cmd(profile_id: String) -> Result<Vec<SnapshotInfo>, String> {
+    let paths = load_paths()?;
+    list_snapshots(&paths, &profile_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rollback_profile_cmd(profile_id: String, snapshot_id: String) -> Result<Profile, String> {
+    let paths = load_paths()?;
+    rollback_profile(&paths, &profile_id, &snapshot_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_worlds_cmd(profile_id: String) -> Result<Vec<WorldInfo>, String> {
+    let paths = load_paths()?;
+    list_worlds(&paths, &profile_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn copy_world_cmd(from_profile: String, world: String, to_profile: String, name: Option<String>) -> Result<String, String> {
+    let paths = load_paths()?;
+    copy_world(&paths, &from_profile, &world, &to_profile, name.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_world_cmd(profile_id: String, path: String, name: Option<String>) -> Result<String, String> {
     let paths = load_paths()?;
-    let profile_a = load_profile(&paths, &a).map_err(|e| e.to_string())?;
-    let profile_b = load_profile(&paths, &b).map_err(|e| e.to_string())?;
-    let (only_a, only_b, both) = diff_profiles(&profile_a, &profile_b);
-    Ok(DiffResult { only_a, only_b, both })
+    import_world_zip(&paths, &profile_id, &PathBuf::from(path), name.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_world_cmd(profile_id: String, world: String, path: String) -> Result<(), String> {
+    let paths = load_paths()?;
+    export_world_zip(&paths, &profile_id, &world, &PathBuf::from(path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_backup_cmd(profile_id: String, world: String) -> Result<Option<BackupInfo>, String> {
+    let paths = load_paths()?;
+    let config = load_config(&paths).map_err(|e| e.to_string())?;
+    create_backup(&paths, &profile_id, &world, &config.backup_retention).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_backups_cmd(profile_id: String, world: Option<String>) -> Result<Vec<BackupInfo>, String> {
+    let paths = load_paths()?;
+    list_backups(&paths, &profile_id, world.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_backup_cmd(profile_id: String, world: String, backup_id: String, target: Option<String>, force: bool) -> Result<String, String> {
+    let paths = load_paths()?;
+    restore_backup(&paths, &profile_id, &world, &backup_id, target.as_deref(), force).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn prune_backups_cmd(profile_id: String, world: Option<String>) -> Result<PruneReport, String> {
+    let paths = load_paths()?;
+    let config = load_config(&paths).map_err(|e| e.to_string())?;
+    prune_backups(&paths, &profile_id, world.as_deref(), &config.backup_retention).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn diff_profiles_cmd(a: String, b: String) -> Result<ProfileDiff, String> {
+    let paths = load_paths()?;
+    let side_a = DiffSide::load(&paths, &a).map_err(|e| e.to_string())?;
+    let side_b = DiffSide::load(&paths, &b).map_err(|e| e.to_string())?;
+    Ok(diff_sides(&side_a, &side_b))
 }
 
 fn add_content(
@@ -237,6 +303,7 @@ fn add_content(
             ContentKind::Mod => "mod",
             ContentKind::ResourcePack => "resourcepack",
             ContentKind::ShaderPack => "shaderpack",
+            ContentKind::Datapack => "datapack",
             ContentKind::Skin => "skin",
         };
         let hash = stored.hash.strip_prefix("sha256:").unwrap_or(&stored.hash);
@@ -278,6 +345,7 @@ fn add_content(
         ContentKind::Mod => upsert_mod(&mut profile_data, content_ref),
         ContentKind::ResourcePack => upsert_resourcepack(&mut profile_data, content_ref),
         ContentKind::ShaderPack => upsert_shaderpack(&mut profile_data, content_ref),
+        ContentKind::Datapack => upsert_datapack(&mut profile_data, content_ref),
         ContentKind::Skin => false, // Skins are not added to profiles
     };
     save_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
@@ -291,6 +359,7 @@ fn remove_content(profile_id: &str, target: &str, kind: ContentKind) -> Result<b
         ContentKind::Mod => remove_mod(&mut profile_data, target),
         ContentKind::ResourcePack => remove_resourcepack(&mut profile_data, target),
         ContentKind::ShaderPack => remove_shaderpack(&mut profile_data, target),
+        ContentKind::Datapack => remove_datapack(&mut profile_data, target),
         ContentKind::Skin => false, // Skins are not removed from profiles
     };
     if changed {
@@ -314,6 +383,11 @@ pub fn add_shaderpack_cmd(profile_id: String, input: String, name: Option<String
     add_content(&profile_id, &input, name, version, ContentKind::ShaderPack)
 }
 
+#[tauri::command]
+pub fn add_datapack_cmd(profile_id: String, input: String, name: Option<String>, version: Option<String>) -> Result<bool, String> {
+    add_content(&profile_id, &input, name, version, ContentKind::Datapack)
+}
+
 #[tauri::command]
 pub fn remove_mod_cmd(profile_id: String, target: String) -> Result<bool, String> {
     remove_content(&profile_id, &target, ContentKind::Mod)
@@ -329,6 +403,107 @@ pub fn remove_shaderpack_cmd(profile_id: String, target: String) -> Result<bool,
     remove_content(&profile_id, &target, ContentKind::ShaderPack)
 }
 
+#[tauri::command]
+pub fn remove_datapack_cmd(profile_id: String, target: String) -> Result<bool, String> {
+    remove_content(&profile_id, &target, ContentKind::Datapack)
+}
+
+/// Install the profile's datapacks into these worlds; an empty list uses the
+/// datapack mod's global folder instead.
+#[tauri::command]
+pub fn set_datapack_worlds_cmd(profile_id: String, worlds: Vec<String>) -> Result<Profile, String> {
+    if let Some(world) = worlds.iter().find(|w| !is_valid_world_name(w)) {
+        return Err(format!("invalid world name: {world}"));
+    }
+    let paths = load_paths()?;
+    let mut profile_data = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    profile_data.datapack_worlds = worlds;
+    save_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
+    Ok(profile_data)
+}
+
+/// Set the game window size (both or neither), fullscreen and demo mode.
+#[tauri::command]
+pub fn set_window_cmd(profile_id: String, width: Option<u32>, height: Option<u32>, fullscreen: bool, demo: bool) -> Result<Profile, String> {
+    if width.is_some() != height.is_some() {
+        return Err("set both width and height, or neither".to_string());
+    }
+    if width == Some(0) || height == Some(0) {
+        return Err("width and height must be greater than 0".to_string());
+    }
+    let paths = load_paths()?;
+    let mut profile_data = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    profile_data.runtime.width = width;
+    profile_data.runtime.height = height;
+    // An explicit `false` is only needed to turn off what a base profile enables
+    let inherits = profile_data.extends.is_some();
+    let explicit = |value: bool| (value || inherits).then_some(value);
+    profile_data.runtime.fullscreen = explicit(fullscreen);
+    profile_data.runtime.demo = explicit(demo);
+    save_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
+    Ok(profile_data)
+}
+
+/// The profile's servers (resolved) and the entries of the instance's servers.dat
+#[tauri::command]
+pub fn list_servers_cmd(profile_id: String) -> Result<Vec<ServerEntry>, String> {
+    let paths = load_paths()?;
+    let profile_data = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    let resolved = resolve_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
+    let mut entries = list_instance_servers(&resolved, &paths.instance_dir(&profile_id)).map_err(|e| e.to_string())?;
+    for server in resolved.servers.iter().rev() {
+        if !entries.iter().any(|entry| entry.address.eq_ignore_ascii_case(&server.address)) {
+            entries.insert(0, ServerEntry { name: server.name.clone(), address: server.address.clone(), managed: true });
+        }
+    }
+    Ok(entries)
+}
+
+#[tauri::command]
+pub fn add_server_cmd(
+    profile_id: String,
+    name: String,
+    address: String,
+    icon_path: Option<String>,
+    resource_pack: Option<ResourcePackPolicy>,
+) -> Result<Profile, String> {
+    if address.trim().is_empty() {
+        return Err("server address is empty".to_string());
+    }
+    let paths = load_paths()?;
+    let mut profile_data = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    let icon = icon_path
+        .map(|path| store_server_icon(&paths, &profile_id, &address, &PathBuf::from(path)))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    upsert_server(&mut profile_data, Server { name, address, icon, resource_pack: resource_pack.unwrap_or_default() });
+    save_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
+    Ok(profile_data)
+}
+
+#[tauri::command]
+pub fn remove_server_cmd(profile_id: String, target: String) -> Result<Profile, String> {
+    let paths = load_paths()?;
+    let mut profile_data = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    if !remove_server(&mut profile_data, &target) {
+        return Err(format!("server not found in profile {profile_id}: {target}"));
+    }
+    save_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
+    Ok(profile_data)
+}
+
+/// Move a resource pack to `index` in the load order (0 is the top).
+#[tauri::command]
+pub fn move_resourcepack_cmd(profile_id: String, target: String, index: usize) -> Result<Profile, String> {
+    let paths = load_paths()?;
+    let mut profile_data = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    if !move_resourcepack(&mut profile_data, &target, index) {
+        return Err(format!("resource pack not found in profile {profile_id}"));
+    }
+    save_profile(&paths, &profile_data).map_err(|e| e.to_string())?;
+    Ok(profile_data)
+}
+
 #[tauri::command]
 pub fn list_accounts_cmd() -> Result<Accounts, String> {
     let paths = load_paths()?;
@@ -394,16 +569,17 @@ pub fn finish_device_code_flow_cmd(
 }
 
 #[tauri::command]
-pub fn prepare_profile_cmd(profile_id: String, account_id: Option<String>) -> Result<LaunchPlanDto, String> {
+pub fn prepare_profile_cmd(profile_id: String, account_id: Option<String>, frozen: Option<bool>, quick_play: Option<QuickPlay>, force: Option<bool>) -> Result<LaunchPlanDto, String> {
     let paths = load_paths()?;
     let profile = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
     let account = resolve_launch_account(&paths, account_id).map_err(|e| e.to_string())?;
-    let plan = prepare(&paths, &profile, &account).map_err(|e| e.to_string())?;
+    let options = LaunchOptions { frozen: frozen.unwrap_or(false), quick_play, force: force.unwrap_or(false), ..LaunchOptions::default() };
+    let plan = prepare(&paths, &profile, &account, &options).map_err(|e| e.to_string())?;
     Ok(LaunchPlanDto::from(plan))
 }
 
 #[tauri::command]
-pub fn launch_profile_cmd(app: AppHandle, profile_id: String, account_id: Option<String>) -> Result<(), String> {
+pub fn launch_profile_cmd(app: AppHandle, profile_id: String, account_id: Option<String>, frozen: Option<bool>, quick_play: Option<QuickPlay>, force: Option<bool>) -> Result<(), String> {
     let app_handle = app.clone();
 
     // Emit initial status immediately before spawning thread
@@ -414,7 +590,8 @@ pub fn launch_profile_cmd(app: AppHandle, profile_id: String, account_id: Option
 
     // Use spawn_blocking for blocking I/O operations (HTTP requests, file I/O)
     tauri::async_runtime::spawn_blocking(move || {
-        match run_launch(app_handle.clone(), profile_id.clone(), account_id) {
+        let options = LaunchOptions { frozen: frozen.unwrap_or(false), quick_play, force: force.unwrap_or(false), ..LaunchOptions::default() };
+        match run_launch(app_handle.clone(), profile_id.clone(), account_id, options) {
             Ok(()) => {}
             Err(err) => {
                 let _ = app_handle.emit("launch-status", LaunchEvent {
@@ -427,13 +604,52 @@ pub fn launch_profile_cmd(app: AppHandle, profile_id: String, account_id: Option
     Ok(())
 }
 
+#[tauri::command]
+pub fn list_running_cmd() -> Result<Vec<RunningInstance>, String> {
+    let paths = load_paths()?;
+    list_running(&paths).map_err(|e| e.to_string())
+}
+
+/// Stop a profile's running game; `force` kills it instead of asking it to close.
+#[tauri::command]
+pub fn kill_instance_cmd(profile_id: String, force: Option<bool>) -> Result<Vec<RunningInstance>, String> {
+    let paths = load_paths()?;
+    kill_instances(&paths, &profile_id, force.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn fetch_profile_content_cmd(profile_id: String) -> Result<FetchReport, String> {
+    let paths = load_paths()?;
+    let config = load_config(&paths).map_err(|e| e.to_string())?;
+    let profile = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    fetch_profile_content(&paths, &profile, config.curseforge_api_key.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn instance_drift_cmd(profile_id: String) -> Result<Vec<DriftItem>, String> {
+    let paths = load_paths()?;
+    let profile = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    instance_drift(&paths, &profile).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn capture_drift_cmd(profile_id: String, paths_to_capture: Option<Vec<String>>) -> Result<CaptureReport, String> {
+    let paths = load_paths()?;
+    let mut profile = load_profile(&paths, &profile_id).map_err(|e| e.to_string())?;
+    let mut drift = instance_drift(&paths, &profile).map_err(|e| e.to_string())?;
+    if let Some(selected) = paths_to_capture {
+        drift.retain(|item| selected.contains(&item.path));
+    }
+    capture_drift(&paths, &mut profile, &drift).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn instance_path_cmd(profile_id: String) -> Result<String, String> {
     let paths = load_paths()?;
     Ok(paths.instance_dir(&profile_id).to_string_lossy().to_string())
 }
 
-fn run_launch(app: AppHandle, profile_id: String, account_id: Option<String>) -> Result<(), String> {
+fn run_launch(app: AppHandle, profile_id: String, account_id: Option<String>, options: LaunchOptions) -> Result<(), String> {
     let _ = app.emit("launch-status", LaunchEvent {
         stage: "preparing".to_string(),
         message: Some("Downloading game files...".to_string()),
@@ -442,29 +658,81 @@ fn run_launch(app: AppHandle, profile_id: String, account_id: Option<String>) ->
     let paths = load_paths()?;
     let profile = load_profile(&paths, &profile_id).map_err(|e| format!("Failed to load profile: {}", e))?;
     let account = resolve_launch_account(&paths, account_id).map_err(|e| format!("Failed to resolve account: {}", e))?;
-    let plan = prepare(&paths, &profile, &account).map_err(|e| format!("Failed to prepare launch: {}", e))?;
+    // Held until the game is registered so a second launch sees it running
+    let launch_lock = if options.force {
+        None
+    } else {
+        Some(claim_launch(&paths, &profile.id).map_err(|e| e.to_string())?)
+    };
+    let plan = prepare(&paths, &profile, &account, &options).map_err(|e| {
+        // Frozen launches report every missing input so the UI can list them
+        if let Some(incomplete) = e.downcast_ref::<IncompleteInstance>() {
+            let _ = app.emit("launch-problems", &incomplete.problems);
+        }
+        format!("Failed to prepare launch: {}", e)
+    })?;
 
     let _ = app.emit("launch-status", LaunchEvent {
         stage: "launching".to_string(),
         message: Some("Starting Minecraft...".to_string()),
     });
 
-    let mut child = Command::new(&plan.java_exec)
+    let started = std::time::SystemTime::now();
+    // Losing the session log is no reason not to play
+    let mut session = SessionLog::start(&paths, &profile.id, plan.redacted_command(&account.access_token))
+        .inspect_err(|e| eprintln!("failed to create session log, output will not be kept: {e:#}"))
+        .ok();
+    let output = || if session.is_some() { Stdio::piped() } else { Stdio::inherit() };
+    let spawned = Command::new(&plan.java_exec)
         .args(&plan.jvm_args)
         .arg("-cp")
         .arg(&plan.classpath)
         .arg(&plan.main_class)
         .args(&plan.game_args)
         .current_dir(&plan.instance_dir)
-        .spawn()
-        .map_err(|e| format!("Failed to start Java: {}", e))?;
+        .stdout(output())
+        .stderr(output())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            if let Some(session) = session {
+                let _ = session.finish(None);
+            }
+            return Err(format!("Failed to start Java: {}", e));
+        }
+    };
+    if let Some(session) = session.as_mut() {
+        session.capture(&mut child, false);
+    }
+    let running = RunningInstance::new(&profile.id, &account.username, child.id(), &plan.java_exec, &plan.main_class);
+    if let Err(e) = register_running(&paths, &running) {
+        eprintln!("failed to record running instance: {e:#}");
+    }
+    drop(launch_lock);
 
     let _ = app.emit("launch-status", LaunchEvent {
         stage: "running".to_string(),
         message: Some("Minecraft is running".to_string()),
     });
 
-    let status = child.wait().map_err(|e| format!("Failed to wait for process: {}", e))?;
+    let status = child.wait().map_err(|e| format!("Failed to wait for process: {}", e));
+    if let Err(e) = unregister_running(&paths, running.pid) {
+        eprintln!("failed to clear running instance: {e:#}");
+    }
+    // A failed wait still ends the session and backs up what was played
+    let exit_code = status.as_ref().ok().and_then(|status| status.code());
+    if let Some(session) = session
+        && let Err(e) = session.finish(exit_code)
+    {
+        eprintln!("failed to record session: {e:#}");
+    }
+
+    if let Err(e) = backup_after_session(&paths, &profile.id, started) {
+        eprintln!("world backup failed: {e:#}");
+    }
+
+    let status = status?;
 
     if !status.success() {
         return Err(format!("Minecraft exited with status {}", status));
@@ -482,6 +750,7 @@ impl From<LaunchPlan> for LaunchPlanDto {
     fn from(plan: LaunchPlan) -> Self {
         Self {
             instance_dir: plan.instance_dir.to_string_lossy().to_string(),
+            materialized: plan.materialized,
             java_exec: plan.java_exec,
             jvm_args: plan.jvm_args,
             classpath: plan.classpath,
@@ -711,6 +980,7 @@ pub fn create_profile_from_template_cmd(input: CreateProfileInput) -> Result<Pro
             } else {
                 template.runtime.args
             },
+            ..Runtime::default()
         };
 
         let mut profile = create_profile(&paths, &input.id, &template.mc_version, loader.clone(), runtime)
@@ -785,6 +1055,7 @@ pub fn create_profile_from_template_cmd(input: CreateProfileInput) -> Result<Pro
             java: input.java.filter(|v| !v.trim().is_empty()),
             memory: input.memory.filter(|v| !v.trim().is_empty()),
             args,
+            ..Runtime::default()
         };
 
         create_profile(&paths, &input.id, &input.mc_version, loader, runtime)
@@ -807,6 +1078,7 @@ fn parse_content_type(s: &str) -> Result<ContentType, String> {
         "mod" => Ok(ContentType::Mod),
         "resourcepack" => Ok(ContentType::ResourcePack),
         "shader" | "shaderpack" => Ok(ContentType::ShaderPack),
+        "datapack" => Ok(ContentType::Datapack),
         "modpack" => Ok(ContentType::ModPack),
         _ => Err(format!("invalid content type: {}", s)),
     }
@@ -885,6 +1157,7 @@ pub fn store_get_versions_cmd(
             }
         }
         ContentType::ResourcePack => None, // Resourcepacks use "minecraft" loader, no filter needed
+        ContentType::Datapack => Some("datapack".to_string()),
     };
 
     store.get_versions(platform, &project_id, game_version.as_deref(), effective_loader.as_deref())
@@ -915,6 +1188,7 @@ pub fn store_install_cmd(input: StoreInstallInput) -> Result<Profile, String> {
             profile.primary_shader_loader().map(|sl| sl.modrinth_name().to_string())
         }
         ContentType::ResourcePack => None, // Resourcepacks use "minecraft" loader, no filter needed
+        ContentType::Datapack => Some("datapack".to_string()),
     };
 
     let version = if let Some(v_id) = input.version_id.clone() {
@@ -943,6 +1217,7 @@ pub fn store_install_cmd(input: StoreInstallInput) -> Result<Profile, String> {
             ContentType::Mod | ContentType::ModPack => "mod",
             ContentType::ResourcePack => "resourcepack",
             ContentType::ShaderPack => "shaderpack",
+            ContentType::Datapack => "datapack",
         };
         let hash = content_ref.hash.strip_prefix("sha256:").unwrap_or(&content_ref.hash);
         let lib_input = LibraryItemInput {
@@ -964,6 +1239,7 @@ pub fn store_install_cmd(input: StoreInstallInput) -> Result<Profile, String> {
         ContentType::Mod | ContentType::ModPack => upsert_mod(&mut profile, content_ref),
         ContentType::ResourcePack => upsert_resourcepack(&mut profile, content_ref),
         ContentType::ShaderPack => upsert_shaderpack(&mut profile, content_ref),
+        ContentType::Datapack => upsert_datapack(&mut profile, content_ref),
     };
 
     save_profile(&paths, &profile).map_err(|e| e.to_string())?;
@@ -982,7 +1258,7 @@ pub fn list_log_files_cmd(profile_id: String) -> Result<Vec<LogFile>, String> {
 pub fn read_logs_cmd(profile_id: String, file: Option<String>, lines: Option<usize>) -> Result<Vec<LogEntry>, String> {
     let paths = load_paths()?;
     let log_path = if let Some(filename) = file {
-        paths.instance_logs_dir(&profile_id).join(filename)
+        log_file_path(&paths, &profile_id, &filename).map_err(|e| e.to_string())?
     } else {
         paths.instance_latest_log(&profile_id)
     };
@@ -1482,6 +1758,7 @@ pub fn library_delete_item_cmd(id: i64, delete_file: bool) -> Result<bool, Strin
                 LibraryContentType::Mod => paths.store_mod_path(&item.hash),
                 LibraryContentType::ResourcePack => paths.store_resourcepack_path(&item.hash),
                 LibraryContentType::ShaderPack => paths.store_shaderpack_path(&item.hash),
+                LibraryContentType::Datapack => paths.store_datapack_path(&item.hash),
                 LibraryContentType::Skin => paths.store_skin_path(&item.hash),
             };
             if store_path.exists() {
@@ -1503,6 +1780,7 @@ pub fn library_get_item_path_cmd(id: i64) -> Result<Option<String>, String> {
             LibraryContentType::Mod => paths.store_mod_path(&item.hash),
             LibraryContentType::ResourcePack => paths.store_resourcepack_path(&item.hash),
             LibraryContentType::ShaderPack => paths.store_shaderpack_path(&item.hash),
+            LibraryContentType::Datapack => paths.store_datapack_path(&item.hash),
             LibraryContentType::Skin => paths.store_skin_path(&item.hash),
         };
         if store_path.exists() {
@@ -1605,6 +1883,21 @@ fn enrich_library_from_profiles(paths: &Paths, library: &Library) -> Result<usiz
                     enriched += 1;
                 }
             }
+
+            // Enrich from datapacks
+            for content in &profile.datapacks {
+                if library.enrich_item_from_content_ref(
+                    &content.hash,
+                    &content.name,
+                    content.file_name.as_deref(),
+                    content.source.as_deref(),
+                    content.platform.as_deref(),
+                    content.project_id.as_deref(),
+                    content.version.as_deref(),
+                ).is_ok() {
+                    enriched += 1;
+                }
+            }
         }
     }
 
@@ -1672,6 +1965,7 @@ pub fn library_add_to_profile_cmd(profile_id: String, item_id: i64) -> Result<Pr
         LibraryContentType::Mod => { upsert_mod(&mut profile, content_ref); }
         LibraryContentType::ResourcePack => { upsert_resourcepack(&mut profile, content_ref); }
         LibraryContentType::ShaderPack => { upsert_shaderpack(&mut profile, content_ref); }
+        LibraryContentType::Datapack => { upsert_datapack(&mut profile, content_ref); }
         LibraryContentType::Skin => return Err("skins cannot be added to profiles".to_string()),
     };
 
@@ -1739,6 +2033,15 @@ pub fn set_auto_update_enabled_cmd(enabled: bool) -> Result<Config, String> {
     Ok(config)
 }
 
+#[tauri::command]
+pub fn set_link_strategy_cmd(strategy: LinkStrategy) -> Result<Config, String> {
+    let paths = load_paths()?;
+    let mut config = load_config(&paths).map_err(|e| e.to_string())?;
+    config.link_strategy = strategy;
+    save_config(&paths, &config).map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
 // ============================================================================
 // Update Checking Commands
 // ============================================================================
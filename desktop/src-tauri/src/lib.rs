@@ -21,14 +21,37 @@ pub fn run() {
             commands::rename_profile_cmd,
             commands::update_profile_version_cmd,
             commands::diff_profiles_cmd,
+            commands::list_snapshots_cmd,
+            commands::rollback_profile_cmd,
+            commands::list_worlds_cmd,
+            commands::copy_world_cmd,
+            commands::import_world_cmd,
+            commands::export_world_cmd,
+            commands::create_backup_cmd,
+            commands::list_backups_cmd,
+            commands::restore_backup_cmd,
+            commands::prune_backups_cmd,
             commands::add_mod_cmd,
             commands::add_resourcepack_cmd,
             commands::add_shaderpack_cmd,
+            commands::add_datapack_cmd,
             commands::remove_mod_cmd,
             commands::remove_resourcepack_cmd,
             commands::remove_shaderpack_cmd,
+            commands::remove_datapack_cmd,
+            commands::set_datapack_worlds_cmd,
+            commands::set_window_cmd,
+            commands::list_running_cmd,
+            commands::kill_instance_cmd,
+            commands::list_servers_cmd,
+            commands::add_server_cmd,
+            commands::remove_server_cmd,
+            commands::move_resourcepack_cmd,
             commands::prepare_profile_cmd,
             commands::launch_profile_cmd,
+            commands::fetch_profile_content_cmd,
+            commands::instance_drift_cmd,
+            commands::capture_drift_cmd,
             commands::instance_path_cmd,
             // Account commands
             commands::list_accounts_cmd,
@@ -105,6 +128,7 @@ pub fn run() {
             commands::purge_unused_items_cmd,
             commands::get_auto_update_enabled_cmd,
             commands::set_auto_update_enabled_cmd,
+            commands::set_link_strategy_cmd,
             // Update checking commands
             commands::check_all_updates_cmd,
             commands::check_profile_updates_cmd,
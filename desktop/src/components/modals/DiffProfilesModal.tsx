@@ -4,7 +4,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { Modal } from "../Modal";
 import { Field } from "../Field";
 import { useAppStore } from "../../store";
-import type { DiffResult } from "../../types";
+import type { ContentChange, ContentState, ProfileDiff } from "../../types";
 
 interface DiffProfilesModalProps {
   open: boolean;
@@ -16,7 +16,7 @@ export function DiffProfilesModal({ open, onClose }: DiffProfilesModalProps) {
 
   const [form, setForm] = useState({ a: "", b: "" });
   const [errors, setErrors] = useState<Record<string, string>>({});
-  const [result, setResult] = useState<DiffResult | null>(null);
+  const [result, setResult] = useState<ProfileDiff | null>(null);
 
   useEffect(() => {
     if (open) {
@@ -35,7 +35,7 @@ export function DiffProfilesModal({ open, onClose }: DiffProfilesModalProps) {
     if (Object.keys(newErrors).length > 0) return;
 
     await runAction(async () => {
-      const diffResult = await invoke<DiffResult>("diff_profiles_cmd", { a: form.a, b: form.b });
+      const diffResult = await invoke<ProfileDiff>("diff_profiles_cmd", { a: form.a, b: form.b });
       setResult(diffResult);
     });
   };
@@ -59,22 +59,67 @@ export function DiffProfilesModal({ open, onClose }: DiffProfilesModalProps) {
         </div>
         <button className="btn btn-primary" onClick={handleCompare}>Compare</button>
         {result && (
-          <div style={{ display: "grid", gridTemplateColumns: "1fr 1fr 1fr", gap: 16, marginTop: 8 }}>
-            <div>
-              <div className="field-label">Only in A</div>
-              {result.only_a.length === 0 ? <div style={{ color: "rgba(255,255,255,0.4)", fontSize: 13 }}>—</div> : result.only_a.map((n) => <div key={n} style={{ fontSize: 13 }}>{n}</div>)}
-            </div>
-            <div>
-              <div className="field-label">Only in B</div>
-              {result.only_b.length === 0 ? <div style={{ color: "rgba(255,255,255,0.4)", fontSize: 13 }}>—</div> : result.only_b.map((n) => <div key={n} style={{ fontSize: 13 }}>{n}</div>)}
-            </div>
-            <div>
-              <div className="field-label">In both</div>
-              {result.both.length === 0 ? <div style={{ color: "rgba(255,255,255,0.4)", fontSize: 13 }}>—</div> : result.both.map((n) => <div key={n} style={{ fontSize: 13 }}>{n}</div>)}
-            </div>
+          <div style={{ display: "flex", flexDirection: "column", gap: 16, marginTop: 8 }}>
+            {isEmpty(result) && <div style={{ color: "rgba(255,255,255,0.4)", fontSize: 13 }}>No differences</div>}
+            {result.fields.length > 0 && (
+              <div>
+                <div className="field-label">Settings</div>
+                {result.fields.map((f) => (
+                  <div key={f.field} style={{ fontSize: 13 }}>{f.field}: {f.old ?? "—"} → {f.new ?? "—"}</div>
+                ))}
+              </div>
+            )}
+            <ContentSection label="Mods" changes={result.mods} />
+            <ContentSection label="Resource packs" changes={result.resourcepacks} />
+            <ContentSection label="Shader packs" changes={result.shaderpacks} />
+            <ContentSection label="Datapacks" changes={result.datapacks} />
+            {result.overrides.length > 0 && (
+              <div>
+                <div className="field-label">Overrides</div>
+                {result.overrides.map((c) => (
+                  <div key={c.path} style={{ fontSize: 13 }}>{SYMBOLS[c.change]} {c.path}</div>
+                ))}
+              </div>
+            )}
           </div>
         )}
       </div>
     </Modal>
   );
 }
+
+const SYMBOLS = { added: "+", removed: "−", changed: "~" } as const;
+
+function isEmpty(diff: ProfileDiff) {
+  return (
+    diff.fields.length === 0 &&
+    diff.mods.length === 0 &&
+    diff.resourcepacks.length === 0 &&
+    diff.shaderpacks.length === 0 &&
+    diff.datapacks.length === 0 &&
+    diff.overrides.length === 0
+  );
+}
+
+function describe(state?: ContentState) {
+  if (!state) return "";
+  const label = state.version ?? state.hash.replace("sha256:", "").slice(0, 12);
+  return state.enabled ? label : `${label} (disabled)`;
+}
+
+function ContentSection({ label, changes }: { label: string; changes: ContentChange[] }) {
+  if (changes.length === 0) return null;
+  return (
+    <div>
+      <div className="field-label">{label}</div>
+      {changes.map((c) => (
+        <div key={`${c.change}-${c.name}`} style={{ fontSize: 13 }}>
+          {SYMBOLS[c.change]} {c.name}{" "}
+          <span style={{ color: "rgba(255,255,255,0.5)" }}>
+            {c.old && c.new ? `${describe(c.old)} → ${describe(c.new)}` : describe(c.old ?? c.new)}
+          </span>
+        </div>
+      ))}
+    </div>
+  );
+}
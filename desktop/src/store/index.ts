@@ -11,7 +11,7 @@ import type {
   Toast,
   LaunchEvent,
   DeviceCode,
-  DiffResult,
+  ProfileDiff,
   ManifestVersion,
   ProfileFolder,
   ProfileOrganization,
@@ -53,7 +53,7 @@ interface AppState {
   // Modal-specific state
   deviceCode: DeviceCode | null;
   devicePending: boolean;
-  diffResult: DiffResult | null;
+  diffResult: ProfileDiff | null;
 
   // Minecraft versions
   mcVersions: ManifestVersion[];
@@ -80,7 +80,7 @@ interface AppState {
   setDebugDrag: (debug: boolean) => void;
   setDeviceCode: (code: DeviceCode | null) => void;
   setDevicePending: (pending: boolean) => void;
-  setDiffResult: (result: DiffResult | null) => void;
+  setDiffResult: (result: ProfileDiff | null) => void;
   setMcVersions: (versions: ManifestVersion[]) => void;
   setMcVersionLoading: (loading: boolean) => void;
   setLoaderVersions: (versions: string[]) => void;
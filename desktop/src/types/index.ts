@@ -20,18 +20,57 @@ export type Runtime = {
   java?: string | null;
   memory?: string | null;
   args: string[];
+  width?: number | null;
+  height?: number | null;
+  fullscreen?: boolean;
+  demo?: boolean;
 };
 
 export type Profile = {
+  schemaVersion?: number;
   id: string;
+  extends?: string | null;
   mcVersion: string;
   loader?: Loader | null;
   mods: ContentRef[];
   resourcepacks: ContentRef[];
   shaderpacks: ContentRef[];
+  datapacks?: ContentRef[];
+  datapackWorlds?: string[];
   runtime: Runtime;
+  gameOptions?: Record<string, string>;
+  activeShader?: string | null;
+  servers?: Server[];
+  linkStrategy?: LinkStrategy | null;
+  remove?: Removals;
 };
 
+export type Removals = {
+  mods?: string[];
+  resourcepacks?: string[];
+  shaderpacks?: string[];
+  datapacks?: string[];
+  servers?: string[];
+  overrides?: string[];
+};
+
+export type ResourcePackPolicy = "prompt" | "enabled" | "disabled";
+
+export type Server = {
+  name: string;
+  address: string;
+  icon?: string | null;
+  resourcePack?: ResourcePackPolicy;
+};
+
+export type ServerEntry = {
+  name: string;
+  address: string;
+  managed: boolean;
+};
+
+export type LinkStrategy = "symlink" | "hardlink" | "reflink" | "copy";
+
 export type Account = {
   uuid: string;
   username: string;
@@ -47,6 +86,28 @@ export type Config = {
   msa_client_id?: string | null;
   msa_client_secret?: string | null;
   auto_update_enabled?: boolean;
+  link_strategy?: LinkStrategy;
+  local?: LocalOverlay;
+  strict_schema?: boolean;
+  backup_on_exit?: boolean;
+  backup_retention?: RetentionPolicy;
+  keep_override_edits?: boolean;
+  session_log_limit?: number | null;
+};
+
+export type RetentionPolicy = {
+  keep_last: number;
+  keep_daily: number;
+  keep_weekly: number;
+};
+
+export type LocalOverlay = {
+  java?: string | null;
+  memory?: string | null;
+  args?: string[];
+  link_strategy?: LinkStrategy | null;
+  width?: number | null;
+  height?: number | null;
 };
 
 export type DeviceCode = {
@@ -58,8 +119,21 @@ export type DeviceCode = {
   interval: number;
 };
 
+export type MaterializeSummary = {
+  added: string[];
+  replaced: string[];
+  removed: string[];
+  unchanged: number;
+  overrides_copied: string[];
+  overrides_kept: string[];
+  options_set: string[];
+  shader_files: string[];
+  servers_set: string[];
+};
+
 export type LaunchPlan = {
   instance_dir: string;
+  materialized: MaterializeSummary;
   java_exec: string;
   jvm_args: string[];
   classpath: string;
@@ -67,10 +141,40 @@ export type LaunchPlan = {
   game_args: string[];
 };
 
-export type DiffResult = {
-  only_a: string[];
-  only_b: string[];
-  both: string[];
+export type RunningInstance = {
+  profile: string;
+  account: string;
+  pid: number;
+  started: number;
+  java: string;
+  main_class: string;
+};
+
+/** Passed as `quickPlay` to launch_profile_cmd / prepare_profile_cmd */
+export type QuickPlay = { server: string } | { world: string } | { realm: string };
+
+export type ChangeKind = "added" | "removed" | "changed";
+
+export type ContentState = {
+  version?: string;
+  hash: string;
+  enabled: boolean;
+};
+
+export type ContentChange = {
+  change: ChangeKind;
+  name: string;
+  old?: ContentState;
+  new?: ContentState;
+};
+
+export type ProfileDiff = {
+  fields: { field: string; old?: string | null; new?: string | null }[];
+  mods: ContentChange[];
+  resourcepacks: ContentChange[];
+  shaderpacks: ContentChange[];
+  datapacks: ContentChange[];
+  overrides: { change: ChangeKind; path: string }[];
 };
 
 export type LaunchEvent = {
@@ -216,7 +320,7 @@ export type StoreProject = {
   body?: string | null;
   icon_url?: string | null;
   platform: "modrinth" | "curseforge";
-  content_type: "mod" | "resourcepack" | "shaderpack" | "modpack";
+  content_type: "mod" | "resourcepack" | "shaderpack" | "datapack" | "modpack";
   downloads: number;
   updated: string;
   categories: string[];
@@ -259,6 +363,18 @@ export type LogFile = {
   size: number;
   modified: number;
   is_current: boolean;
+  /** Set for launcher session logs (`session-<id>.log`) */
+  session?: GameSession | null;
+};
+
+export type GameSession = {
+  profile_id: string;
+  started: number;
+  ended?: number | null;
+  exit_code?: number | null;
+  pid?: number | null;
+  /** Java command line with the access token redacted */
+  command: string[];
 };
 
 export type ConfirmState = {
@@ -275,7 +391,7 @@ export type Toast = {
 };
 
 // Library types
-export type LibraryContentType = "mod" | "resourcepack" | "shaderpack" | "skin";
+export type LibraryContentType = "mod" | "resourcepack" | "shaderpack" | "datapack" | "skin";
 
 export type LibraryTag = {
   id: number;
@@ -314,6 +430,7 @@ export type LibraryStats = {
   mods_count: number;
   resourcepacks_count: number;
   shaderpacks_count: number;
+  datapacks_count: number;
   skins_count: number;
   total_size: number;
   tags_count: number;
@@ -331,6 +448,7 @@ export type StorageStats = {
   mods_bytes: number;
   resourcepacks_bytes: number;
   shaderpacks_bytes: number;
+  datapacks_bytes: number;
   skins_bytes: number;
   minecraft_bytes: number;
   database_bytes: number;
@@ -352,6 +470,7 @@ export type UnusedItemsSummary = {
   mods: UnusedItem[];
   resourcepacks: UnusedItem[];
   shaderpacks: UnusedItem[];
+  datapacks: UnusedItem[];
   skins: UnusedItem[];
   total_count: number;
   total_bytes: number;
@@ -381,3 +500,40 @@ export type UpdateCheckResult = {
   skipped: number;
   errors: string[];
 };
+
+export type SnapshotInfo = {
+  id: string;
+  created: number;
+  mcVersion: string;
+  loader?: string | null;
+  mods: number;
+  resourcepacks: number;
+  shaderpacks: number;
+  overrides: number;
+};
+
+export type WorldInfo = {
+  folder: string;
+  name: string;
+  game_mode: "survival" | "creative" | "adventure" | "spectator";
+  hardcore: boolean;
+  version?: string | null;
+  last_played?: number | null;
+  seed?: number | null;
+  size: number;
+};
+
+export type BackupInfo = {
+  id: string;
+  profile: string;
+  world: string;
+  created: number;
+  files: number;
+  size: number;
+  stored: number;
+};
+
+export type PruneReport = {
+  removed: BackupInfo[];
+  freed: number;
+};
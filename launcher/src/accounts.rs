@@ -1,3 +1,4 @@
+use crate::migrate::{ACCOUNTS_SCHEMA_VERSION, DocumentKind, load_document};
 use crate::paths::Paths;
 use crate::util::now_epoch_secs;
 use anyhow::{Context, Result};
@@ -8,6 +9,9 @@ use std::path::Path;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[derive(Default)]
 pub struct Accounts {
+    /// On-disk format version (see `migrate`)
+    #[serde(rename = "schemaVersion", default)]
+    pub schema_version: u32,
     #[serde(default)]
     pub active: Option<String>,
     #[serde(default)]
@@ -52,17 +56,12 @@ impl MinecraftTokens {
 
 pub fn load_accounts(paths: &Paths) -> Result<Accounts> {
     if !paths.accounts.exists() {
-        return Ok(Accounts::default());
+        return Ok(Accounts {
+            schema_version: ACCOUNTS_SCHEMA_VERSION,
+            ..Accounts::default()
+        });
     }
-    let data = fs::read_to_string(&paths.accounts)
-        .with_context(|| format!("failed to read accounts file: {}", paths.accounts.display()))?;
-    let accounts: Accounts = serde_json::from_str(&data).with_context(|| {
-        format!(
-            "failed to parse accounts JSON: {}",
-            paths.accounts.display()
-        )
-    })?;
-    Ok(accounts)
+    load_document(paths, DocumentKind::Accounts, &paths.accounts, false)
 }
 
 pub fn save_accounts(paths: &Paths, accounts: &Accounts) -> Result<()> {
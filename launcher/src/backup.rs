@@ -0,0 +1,612 @@
+//! Deduplicated world backups.
+//!
+//! A backup is a manifest of the files in `saves/<world>`, stored as
+//! `backups/worlds/<profile>/<world>/<backup>.json`. File contents are kept
+//! once per SHA-256 in `backups/objects/sha256/`, so a new backup only stores
+//! the region files that changed since the previous one. Files whose size and
+//! modification time match the previous backup are not read again.
+
+use crate::config::load_config;
+use crate::paths::Paths;
+use crate::profile::is_valid_world_name;
+use crate::running::running_instances;
+use crate::store::{hash_file, normalize_hash};
+use crate::util::now_epoch_secs;
+use crate::worlds::{LEVEL_DAT, SESSION_LOCK, is_world_open, world_dir};
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Which backups of a world `prune` keeps. The newest backup is always kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RetentionPolicy {
+    /// Most recent backups to keep
+    #[serde(default)]
+    pub keep_last: usize,
+    /// Days to keep the newest backup of
+    #[serde(default)]
+    pub keep_daily: usize,
+    /// Weeks to keep the newest backup of
+    #[serde(default)]
+    pub keep_weekly: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: 10,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupManifest {
+    created: u64,
+    /// Path relative to the world folder -> file
+    files: BTreeMap<String, BackupFile>,
+    /// Bytes of new objects this backup added to the store
+    #[serde(default)]
+    stored: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct BackupFile {
+    /// "sha256:<hex>"
+    hash: String,
+    size: u64,
+    /// Milliseconds since the Unix epoch
+    modified: u64,
+}
+
+/// A saved state of a world
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub profile: String,
+    pub world: String,
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    pub files: usize,
+    /// Total size of the world when it was backed up
+    pub size: u64,
+    /// Bytes this backup added to the store
+    pub stored: u64,
+}
+
+/// What `prune_backups` removed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    pub removed: Vec<BackupInfo>,
+    /// Bytes of objects no backup refers to any more
+    pub freed: u64,
+}
+
+/// Back up a world, then prune its backups with `policy`. Returns `None` if
+/// the world is unchanged since its latest backup.
+pub fn create_backup(
+    paths: &Paths,
+    profile_id: &str,
+    world: &str,
+    policy: &RetentionPolicy,
+) -> Result<Option<BackupInfo>> {
+    let created = backup_world(paths, profile_id, world)?.0;
+    if created.is_some() {
+        prune_world(paths, profile_id, world, policy)?;
+        collect_garbage(paths)?;
+    }
+    Ok(created)
+}
+
+/// Back up every world whose `level.dat` was written at or after `since`, for
+/// use after a game session.
+pub fn backup_played_worlds(
+    paths: &Paths,
+    profile_id: &str,
+    since: SystemTime,
+    policy: &RetentionPolicy,
+) -> Result<Vec<BackupInfo>> {
+    let saves = paths.instance_saves(profile_id);
+    let mut backups = Vec::new();
+    if !saves.is_dir() {
+        return Ok(backups);
+    }
+    for entry in
+        fs::read_dir(&saves).with_context(|| format!("failed to read dir: {}", saves.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let played = fs::metadata(entry.path().join(LEVEL_DAT))
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified >= since);
+        if !played {
+            continue;
+        }
+        let world = entry.file_name().to_string_lossy().to_string();
+        if let Some(backup) = create_backup(paths, profile_id, &world, policy)? {
+            backups.push(backup);
+        }
+    }
+    Ok(backups)
+}
+
+/// Back up the worlds played in a game session that started at `started`, if
+/// `backupOnExit` is enabled in the config.
+pub fn backup_after_session(
+    paths: &Paths,
+    profile_id: &str,
+    started: SystemTime,
+) -> Result<Vec<BackupInfo>> {
+    let config = load_config(paths)?;
+    if !config.backup_on_exit {
+        return Ok(Vec::new());
+    }
+    backup_played_worlds(paths, profile_id, started, &config.backup_retention)
+}
+
+/// Write a backup of the world as it is now. Returns the new backup, or `None`
+/// if it matches the latest one, along with the world's files.
+fn backup_world(
+    paths: &Paths,
+    profile_id: &str,
+    world: &str,
+) -> Result<(Option<BackupInfo>, BTreeMap<String, BackupFile>)> {
+    let dir = world_dir(paths, profile_id, world)?;
+    let backups_dir = paths.world_backups(profile_id, world);
+    let previous = match backup_ids(&backups_dir)?.last() {
+        Some(id) => Some(read_manifest(&backups_dir, id)?),
+        None => None,
+    };
+
+    let mut found = Vec::new();
+    scan_files(&dir, &dir, &mut found)?;
+    let mut files = BTreeMap::new();
+    let mut stored = 0;
+    for (rel, path) in found {
+        let meta =
+            fs::metadata(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let size = meta.len();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or(0);
+        let unchanged = previous
+            .as_ref()
+            .and_then(|manifest| manifest.files.get(&rel))
+            .filter(|file| file.size == size && file.modified == modified)
+            .filter(|file| paths.backup_object(normalize_hash(&file.hash)).exists());
+        let hash = match unchanged {
+            Some(file) => file.hash.clone(),
+            None => {
+                let (hash, added) = store_object(paths, &path)?;
+                stored += added;
+                hash
+            }
+        };
+        files.insert(
+            rel,
+            BackupFile {
+                hash,
+                size,
+                modified,
+            },
+        );
+    }
+
+    let same = previous.as_ref().is_some_and(|manifest| {
+        manifest.files.len() == files.len()
+            && manifest
+                .files
+                .iter()
+                .all(|(rel, file)| files.get(rel).is_some_and(|f| f.hash == file.hash))
+    });
+    if same {
+        return Ok((None, files));
+    }
+
+    fs::create_dir_all(&backups_dir)
+        .with_context(|| format!("failed to create dir: {}", backups_dir.display()))?;
+    let created = now_epoch_secs();
+    let mut id = created.to_string();
+    let mut idx = 1;
+    while manifest_path(&backups_dir, &id).exists() {
+        id = format!("{created}-{idx}");
+        idx += 1;
+    }
+    let manifest = BackupManifest {
+        created,
+        files,
+        stored,
+    };
+    let data = serde_json::to_string_pretty(&manifest).context("failed to serialize backup")?;
+    let path = manifest_path(&backups_dir, &id);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, data).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("failed to write {}", path.display()))?;
+
+    let info = backup_info(profile_id, world, &id, &manifest);
+    Ok((Some(info), manifest.files))
+}
+
+/// Copy a file into the object store. The copy is hashed rather than the
+/// original, so a file the game is still writing cannot end up stored under
+/// the wrong hash. Returns the hash and the bytes added to the store.
+fn store_object(paths: &Paths, path: &Path) -> Result<(String, u64)> {
+    let objects = paths.backup_objects();
+    fs::create_dir_all(&objects)
+        .with_context(|| format!("failed to create dir: {}", objects.display()))?;
+    let tmp = objects.join(format!(
+        ".tmp-{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    let size =
+        fs::copy(path, &tmp).with_context(|| format!("failed to back up {}", path.display()))?;
+    let hex = hash_file(&tmp)?;
+    let object = paths.backup_object(&hex);
+    if object.exists() {
+        fs::remove_file(&tmp).with_context(|| format!("failed to remove {}", tmp.display()))?;
+        return Ok((format!("sha256:{hex}"), 0));
+    }
+    fs::rename(&tmp, &object).with_context(|| format!("failed to write {}", object.display()))?;
+    Ok((format!("sha256:{hex}"), size))
+}
+
+fn scan_files(root: &Path, dir: &Path, found: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let path = entry.path();
+        if entry
+            .file_type()
+            .context("failed to read entry type")?
+            .is_dir()
+        {
+            scan_files(root, &path, found)?;
+            continue;
+        }
+        if entry.file_name() == SESSION_LOCK {
+            continue;
+        }
+        let rel = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        found.push((rel, path));
+    }
+    Ok(())
+}
+
+/// List backups, oldest first: of one world, or of every world of the profile.
+pub fn list_backups(
+    paths: &Paths,
+    profile_id: &str,
+    world: Option<&str>,
+) -> Result<Vec<BackupInfo>> {
+    let mut backups = Vec::new();
+    for world in backed_up_worlds(paths, profile_id, world)? {
+        let dir = paths.world_backups(profile_id, &world);
+        for id in backup_ids(&dir)? {
+            let manifest = read_manifest(&dir, &id)?;
+            backups.push(backup_info(profile_id, &world, &id, &manifest));
+        }
+    }
+    Ok(backups)
+}
+
+/// Restore a world from a backup, into `target` (a world folder name) if
+/// given. If the destination world exists, it is backed up first and then
+/// brought to the backed-up state: changed files are replaced and files the
+/// backup does not have are removed. Returns the world folder restored into.
+/// Refuses while the profile is running or the world is open in a game,
+/// unless `force` is set.
+pub fn restore_backup(
+    paths: &Paths,
+    profile_id: &str,
+    world: &str,
+    backup_id: &str,
+    target: Option<&str>,
+    force: bool,
+) -> Result<String> {
+    let backups_dir = paths.world_backups(profile_id, world);
+    if !backup_ids(&backups_dir)?.iter().any(|id| id == backup_id) {
+        bail!("backup not found for world {world} in profile {profile_id}: {backup_id}");
+    }
+    let manifest = read_manifest(&backups_dir, backup_id)?;
+    let folder = target.unwrap_or(world);
+    if !is_valid_world_name(folder) {
+        bail!("invalid world name: {folder}");
+    }
+    let dir = paths.instance_saves(profile_id).join(folder);
+    if !force {
+        if let Some(instance) = running_instances(paths, profile_id)?.first() {
+            bail!(
+                "profile {profile_id} is running (pid {}); close the game first or restore with --force",
+                instance.pid
+            );
+        }
+        if is_world_open(&dir) {
+            bail!("{folder} is open in a game; close it first or restore with --force");
+        }
+    }
+
+    let current = if dir.join(LEVEL_DAT).is_file() {
+        let (safety, files) = backup_world(paths, profile_id, folder)?;
+        if let Some(safety) = safety {
+            eprintln!("backed up current {folder} as {}", safety.id);
+        }
+        files
+    } else if dir.exists() {
+        bail!("{} exists and is not a world", dir.display());
+    } else {
+        BTreeMap::new()
+    };
+
+    for rel in current
+        .keys()
+        .filter(|rel| !manifest.files.contains_key(*rel))
+    {
+        let path = dir.join(rel);
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    for (rel, file) in &manifest.files {
+        if current.get(rel).is_some_and(|f| f.hash == file.hash) {
+            continue;
+        }
+        let object = paths.backup_object(normalize_hash(&file.hash));
+        let path = dir.join(rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+        }
+        fs::copy(&object, &path).with_context(|| format!("failed to restore {rel}"))?;
+    }
+    Ok(folder.to_string())
+}
+
+/// Apply a retention policy to the backups of one world, or of every world of
+/// the profile, and delete objects that are no longer referenced.
+pub fn prune_backups(
+    paths: &Paths,
+    profile_id: &str,
+    world: Option<&str>,
+    policy: &RetentionPolicy,
+) -> Result<PruneReport> {
+    let mut report = PruneReport::default();
+    for world in backed_up_worlds(paths, profile_id, world)? {
+        report
+            .removed
+            .extend(prune_world(paths, profile_id, &world, policy)?);
+    }
+    report.freed = collect_garbage(paths)?;
+    Ok(report)
+}
+
+fn prune_world(
+    paths: &Paths,
+    profile_id: &str,
+    world: &str,
+    policy: &RetentionPolicy,
+) -> Result<Vec<BackupInfo>> {
+    let dir = paths.world_backups(profile_id, world);
+    let mut backups = Vec::new();
+    for id in backup_ids(&dir)? {
+        let manifest = read_manifest(&dir, &id)?;
+        backups.push(backup_info(profile_id, world, &id, &manifest));
+    }
+    let created: Vec<u64> = backups.iter().map(|b| b.created).collect();
+    let keep = retained(&created, policy);
+
+    let mut removed = Vec::new();
+    for (idx, backup) in backups.into_iter().enumerate() {
+        if keep.contains(&idx) {
+            continue;
+        }
+        let path = manifest_path(&dir, &backup.id);
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        removed.push(backup);
+    }
+    Ok(removed)
+}
+
+/// Indexes of the backups to keep, given their creation times oldest first.
+fn retained(created: &[u64], policy: &RetentionPolicy) -> HashSet<usize> {
+    const DAY: u64 = 86400;
+    let mut keep = HashSet::new();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (rank, (idx, created)) in created.iter().enumerate().rev().enumerate() {
+        if rank == 0 || rank < policy.keep_last {
+            keep.insert(idx);
+        }
+        let day = created / DAY;
+        if days.len() < policy.keep_daily && days.insert(day) {
+            keep.insert(idx);
+        }
+        let week = created / (DAY * 7);
+        if weeks.len() < policy.keep_weekly && weeks.insert(week) {
+            keep.insert(idx);
+        }
+    }
+    keep
+}
+
+/// Delete objects that no backup refers to. Returns the bytes freed. Objects
+/// still being written by another backup (`.tmp-*`) are left alone.
+fn collect_garbage(paths: &Paths) -> Result<u64> {
+    let objects = paths.backup_objects();
+    if !objects.exists() {
+        return Ok(0);
+    }
+    let mut referenced = HashSet::new();
+    let worlds_root = paths.backups.join("worlds");
+    for profile in read_dir_names(&worlds_root)? {
+        for world in read_dir_names(&worlds_root.join(&profile))? {
+            let dir = paths.world_backups(&profile, &world);
+            for id in backup_ids(&dir)? {
+                for file in read_manifest(&dir, &id)?.files.into_values() {
+                    referenced.insert(normalize_hash(&file.hash).to_string());
+                }
+            }
+        }
+    }
+
+    let mut freed = 0;
+    for name in read_dir_names(&objects)? {
+        if referenced.contains(&name) || name.starts_with(".tmp-") {
+            continue;
+        }
+        let path = objects.join(&name);
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        freed += size;
+    }
+    Ok(freed)
+}
+
+fn backed_up_worlds(paths: &Paths, profile_id: &str, world: Option<&str>) -> Result<Vec<String>> {
+    match world {
+        Some(world) => Ok(vec![world.to_string()]),
+        None => read_dir_names(&paths.backups.join("worlds").join(profile_id)),
+    }
+}
+
+fn read_dir_names(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    if !dir.exists() {
+        return Ok(names);
+    }
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        names.push(entry.file_name().to_string_lossy().to_string());
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn backup_ids(dir: &Path) -> Result<Vec<String>> {
+    let mut ids: Vec<String> = read_dir_names(dir)?
+        .into_iter()
+        .filter_map(|name| name.strip_suffix(".json").map(str::to_string))
+        .collect();
+    ids.sort_by_key(|id| sort_key(id));
+    Ok(ids)
+}
+
+/// Backup ids are "<epoch>" or "<epoch>-<n>" for several in one second.
+fn sort_key(id: &str) -> (u64, u64) {
+    let (secs, idx) = id.split_once('-').unwrap_or((id, "0"));
+    (secs.parse().unwrap_or(0), idx.parse().unwrap_or(0))
+}
+
+fn manifest_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.json"))
+}
+
+fn read_manifest(dir: &Path, id: &str) -> Result<BackupManifest> {
+    let path = manifest_path(dir, id);
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read backup: {}", path.display()))?;
+    serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse backup: {}", path.display()))
+}
+
+fn backup_info(profile_id: &str, world: &str, id: &str, manifest: &BackupManifest) -> BackupInfo {
+    BackupInfo {
+        id: id.to_string(),
+        profile: profile_id.to_string(),
+        world: world.to_string(),
+        created: manifest.created,
+        files: manifest.files.len(),
+        size: manifest.files.values().map(|f| f.size).sum(),
+        stored: manifest.stored,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retention_keeps_last_daily_and_weekly() {
+        const DAY: u64 = 86400;
+        // Two backups a day for 20 days, oldest first.
+        let created: Vec<u64> = (0..40).map(|i| i * DAY / 2).collect();
+        let policy = RetentionPolicy {
+            keep_last: 3,
+            keep_daily: 2,
+            keep_weekly: 2,
+        };
+        let mut keep: Vec<usize> = retained(&created, &policy).into_iter().collect();
+        keep.sort();
+        // Last three, the newest of days 19 and 18 (39, 37), the newest of the
+        // two latest weeks (39 and 27, the last backup of days 7..13).
+        assert_eq!(keep, [27, 37, 38, 39]);
+
+        let none = RetentionPolicy {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+        };
+        assert_eq!(retained(&created, &none), HashSet::from([39]));
+    }
+
+    #[test]
+    fn stores_changed_files_once_and_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let world = paths.instance_saves("a").join("World");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join(LEVEL_DAT), b"level").unwrap();
+        fs::write(world.join("region/r.0.0.mca"), b"spawn chunks").unwrap();
+        fs::write(world.join("region/r.1.0.mca"), b"far away").unwrap();
+        let policy = RetentionPolicy::default();
+
+        let first = create_backup(&paths, "a", "World", &policy)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.files, 3);
+        assert_eq!(first.stored, first.size);
+        assert!(
+            create_backup(&paths, "a", "World", &policy)
+                .unwrap()
+                .is_none()
+        );
+
+        fs::write(world.join("region/r.1.0.mca"), b"far away, now built on").unwrap();
+        let second = create_backup(&paths, "a", "World", &policy)
+            .unwrap()
+            .unwrap();
+        // Only the changed region file is stored again
+        assert_eq!(second.stored, "far away, now built on".len() as u64);
+        assert_eq!(read_dir_names(&paths.backup_objects()).unwrap().len(), 4);
+
+        fs::write(world.join("region/r.2.0.mca"), b"new").unwrap();
+        let folder = restore_backup(&paths, "a", "World", &first.id, None, false).unwrap();
+        assert_eq!(folder, "World");
+        assert_eq!(
+            fs::read(world.join("region/r.1.0.mca")).unwrap(),
+            b"far away"
+        );
+        assert_eq!(
+            fs::read(world.join("region/r.0.0.mca")).unwrap(),
+            b"spawn chunks"
+        );
+        assert!(!world.join("region/r.2.0.mca").exists());
+    }
+}
@@ -1,5 +1,10 @@
+use crate::backup::RetentionPolicy;
+use crate::instance::LinkStrategy;
+use crate::local::LocalOverlay;
+use crate::migrate::{CONFIG_SCHEMA_VERSION, DocumentKind, load_document};
 use crate::paths::Paths;
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
@@ -10,8 +15,11 @@ const BUILTIN_MS_CLIENT_ID: Option<&str> = option_env!("SHARD_MS_CLIENT_ID");
 /// CurseForge API key baked in at compile time (for release builds)
 const BUILTIN_CURSEFORGE_API_KEY: Option<&str> = option_env!("SHARD_CURSEFORGE_API_KEY");
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 pub struct Config {
+    /// On-disk format version (see `migrate`)
+    #[serde(rename = "schemaVersion", default)]
+    pub schema_version: u32,
     #[serde(default)]
     pub msa_client_id: Option<String>,
     #[serde(default)]
@@ -21,6 +29,28 @@ pub struct Config {
     /// Whether to automatically check for content updates on launcher start
     #[serde(default = "default_auto_update")]
     pub auto_update_enabled: bool,
+    /// How content is placed into instances unless a profile overrides it
+    #[serde(default)]
+    pub link_strategy: LinkStrategy,
+    /// Machine-local values applied over every profile's runtime
+    #[serde(default, skip_serializing_if = "LocalOverlay::is_empty")]
+    pub local: LocalOverlay,
+    /// Reject unknown keys in profile and template files instead of ignoring them
+    #[serde(default)]
+    pub strict_schema: bool,
+    /// Back up the worlds played in a session when the game exits
+    #[serde(default)]
+    pub backup_on_exit: bool,
+    /// Which world backups to keep (see `backup`)
+    #[serde(default)]
+    pub backup_retention: RetentionPolicy,
+    /// Keep override files changed in an instance instead of copying the
+    /// profile's overrides over them on launch
+    #[serde(default)]
+    pub keep_override_edits: bool,
+    /// Session logs kept per profile (see `logs`); 50 if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_log_limit: Option<usize>,
 }
 
 fn default_auto_update() -> bool {
@@ -29,12 +59,12 @@ fn default_auto_update() -> bool {
 
 pub fn load_config(paths: &Paths) -> Result<Config> {
     let mut config = if paths.config.exists() {
-        let data = fs::read_to_string(&paths.config)
-            .with_context(|| format!("failed to read config: {}", paths.config.display()))?;
-        serde_json::from_str(&data)
-            .with_context(|| format!("failed to parse config: {}", paths.config.display()))?
+        load_document(paths, DocumentKind::Config, &paths.config, false)?
     } else {
-        Config::default()
+        Config {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            ..Config::default()
+        }
     };
 
     // Priority for MS Client ID:
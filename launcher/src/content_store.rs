@@ -4,7 +4,7 @@
 //! content from multiple sources.
 
 use crate::curseforge::{self, CurseForgeClient, ModLoaderType};
-use crate::modrinth::{ModrinthClient, ProjectType, SearchFacets};
+use crate::modrinth::{ModrinthClient, ProjectType, SearchFacets, Version};
 use crate::paths::Paths;
 use crate::store::store_from_url;
 use anyhow::{Context, Result};
@@ -17,6 +17,7 @@ pub enum ContentType {
     Mod,
     ResourcePack,
     ShaderPack,
+    Datapack,
     ModPack,
 }
 
@@ -26,6 +27,7 @@ impl ContentType {
             ContentType::Mod => ProjectType::Mod,
             ContentType::ResourcePack => ProjectType::Resourcepack,
             ContentType::ShaderPack => ProjectType::Shader,
+            ContentType::Datapack => ProjectType::Datapack,
             ContentType::ModPack => ProjectType::Modpack,
         }
     }
@@ -35,6 +37,7 @@ impl ContentType {
             ContentType::Mod => curseforge::CLASS_MODS,
             ContentType::ResourcePack => curseforge::CLASS_RESOURCEPACKS,
             ContentType::ShaderPack => curseforge::CLASS_SHADERS,
+            ContentType::Datapack => curseforge::CLASS_DATAPACKS,
             ContentType::ModPack => curseforge::CLASS_MODPACKS,
         }
     }
@@ -44,6 +47,7 @@ impl ContentType {
             ContentType::Mod => crate::store::ContentKind::Mod,
             ContentType::ResourcePack => crate::store::ContentKind::ResourcePack,
             ContentType::ShaderPack => crate::store::ContentKind::ShaderPack,
+            ContentType::Datapack => crate::store::ContentKind::Datapack,
             ContentType::ModPack => crate::store::ContentKind::Mod, // Modpacks are stored as mods
         }
     }
@@ -57,6 +61,17 @@ pub enum Platform {
     CurseForge,
 }
 
+impl Platform {
+    /// Parse a platform name as stored in `ContentRef::platform`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "modrinth" => Some(Platform::Modrinth),
+            "curseforge" => Some(Platform::CurseForge),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for Platform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -238,6 +253,7 @@ impl ContentStore {
                     ProjectType::Modpack => ContentType::ModPack,
                     ProjectType::Resourcepack => ContentType::ResourcePack,
                     ProjectType::Shader => ContentType::ShaderPack,
+                    ProjectType::Datapack => ContentType::Datapack,
                 },
                 downloads: hit.downloads,
                 updated: hit.date_modified,
@@ -290,6 +306,7 @@ impl ContentStore {
                     Some(curseforge::CLASS_MODS) => ContentType::Mod,
                     Some(curseforge::CLASS_RESOURCEPACKS) => ContentType::ResourcePack,
                     Some(curseforge::CLASS_SHADERS) => ContentType::ShaderPack,
+                    Some(curseforge::CLASS_DATAPACKS) => ContentType::Datapack,
                     Some(curseforge::CLASS_MODPACKS) => ContentType::ModPack,
                     _ => ContentType::Mod,
                 };
@@ -335,6 +352,7 @@ impl ContentStore {
                         ProjectType::Modpack => ContentType::ModPack,
                         ProjectType::Resourcepack => ContentType::ResourcePack,
                         ProjectType::Shader => ContentType::ShaderPack,
+                        ProjectType::Datapack => ContentType::Datapack,
                     },
                     downloads: project.downloads,
                     updated: project.updated,
@@ -355,6 +373,7 @@ impl ContentStore {
                     Some(curseforge::CLASS_MODS) => ContentType::Mod,
                     Some(curseforge::CLASS_RESOURCEPACKS) => ContentType::ResourcePack,
                     Some(curseforge::CLASS_SHADERS) => ContentType::ShaderPack,
+                    Some(curseforge::CLASS_DATAPACKS) => ContentType::Datapack,
                     Some(curseforge::CLASS_MODPACKS) => ContentType::ModPack,
                     _ => ContentType::Mod,
                 };
@@ -398,34 +417,7 @@ impl ContentStore {
 
                 Ok(versions
                     .into_iter()
-                    .filter_map(|v| {
-                        let file = ModrinthClient::get_primary_file(&v)?.clone();
-                        Some(ContentVersion {
-                            id: v.id,
-                            project_id: v.project_id,
-                            name: v.name,
-                            version: v.version_number,
-                            download_url: file.url,
-                            filename: file.filename,
-                            size: file.size,
-                            sha256: None,
-                            sha1: Some(file.hashes.sha1),
-                            platform: Platform::Modrinth,
-                            game_versions: v.game_versions,
-                            loaders: v.loaders,
-                            release_type: v.version_type,
-                            dependencies: v
-                                .dependencies
-                                .into_iter()
-                                .filter_map(|d| {
-                                    Some(ContentDependency {
-                                        project_id: d.project_id?,
-                                        dependency_type: d.dependency_type,
-                                    })
-                                })
-                                .collect(),
-                        })
-                    })
+                    .filter_map(modrinth_content_version)
                     .collect())
             }
             Platform::CurseForge => {
@@ -441,48 +433,39 @@ impl ContentStore {
                 Ok(files
                     .data
                     .into_iter()
-                    .filter_map(|f| {
-                        let download_url = f.download_url.clone()?;
-                        let sha1 = curseforge::get_sha1_hash(&f).map(String::from);
-
-                        let release_type = match f.release_type {
-                            1 => "release",
-                            2 => "beta",
-                            3 => "alpha",
-                            _ => "unknown",
-                        }
-                        .to_string();
-
-                        Some(ContentVersion {
-                            id: f.id.to_string(),
-                            project_id: f.mod_id.to_string(),
-                            name: f.display_name,
-                            version: f.file_name.clone(),
-                            download_url,
-                            filename: f.file_name,
-                            size: f.file_length,
-                            sha256: None,
-                            sha1,
-                            platform: Platform::CurseForge,
-                            game_versions: f.game_versions,
-                            loaders: vec![],
-                            release_type,
-                            dependencies: f
-                                .dependencies
-                                .into_iter()
-                                .filter(|d| d.relation_type == 3) // Required only
-                                .map(|d| ContentDependency {
-                                    project_id: d.mod_id.to_string(),
-                                    dependency_type: "required".to_string(),
-                                })
-                                .collect(),
-                        })
-                    })
+                    .filter_map(curseforge_content_version)
                     .collect())
             }
         }
     }
 
+    /// Get a specific version of a project by its platform version ID
+    pub fn get_version(
+        &self,
+        platform: Platform,
+        project_id: &str,
+        version_id: &str,
+    ) -> Result<ContentVersion> {
+        match platform {
+            Platform::Modrinth => {
+                let version = self.modrinth.get_version(version_id)?;
+                modrinth_content_version(version)
+                    .with_context(|| format!("version {version_id} has no downloadable file"))
+            }
+            Platform::CurseForge => {
+                let cf = self
+                    .curseforge
+                    .as_ref()
+                    .context("CurseForge not configured")?;
+                let mod_id: u32 = project_id.parse().context("invalid CurseForge mod ID")?;
+                let file_id: u32 = version_id.parse().context("invalid CurseForge file ID")?;
+                let file = cf.get_file(mod_id, file_id)?;
+                curseforge_content_version(file)
+                    .with_context(|| format!("file {version_id} has no download URL"))
+            }
+        }
+    }
+
     /// Get the latest compatible version
     pub fn get_latest_version(
         &self,
@@ -536,6 +519,73 @@ impl ContentStore {
     }
 }
 
+fn modrinth_content_version(v: Version) -> Option<ContentVersion> {
+    let file = ModrinthClient::get_primary_file(&v)?.clone();
+    Some(ContentVersion {
+        id: v.id,
+        project_id: v.project_id,
+        name: v.name,
+        version: v.version_number,
+        download_url: file.url,
+        filename: file.filename,
+        size: file.size,
+        sha256: None,
+        sha1: Some(file.hashes.sha1),
+        platform: Platform::Modrinth,
+        game_versions: v.game_versions,
+        loaders: v.loaders,
+        release_type: v.version_type,
+        dependencies: v
+            .dependencies
+            .into_iter()
+            .filter_map(|d| {
+                Some(ContentDependency {
+                    project_id: d.project_id?,
+                    dependency_type: d.dependency_type,
+                })
+            })
+            .collect(),
+    })
+}
+
+fn curseforge_content_version(f: curseforge::File) -> Option<ContentVersion> {
+    let download_url = f.download_url.clone()?;
+    let sha1 = curseforge::get_sha1_hash(&f).map(String::from);
+
+    let release_type = match f.release_type {
+        1 => "release",
+        2 => "beta",
+        3 => "alpha",
+        _ => "unknown",
+    }
+    .to_string();
+
+    Some(ContentVersion {
+        id: f.id.to_string(),
+        project_id: f.mod_id.to_string(),
+        name: f.display_name,
+        version: f.file_name.clone(),
+        download_url,
+        filename: f.file_name,
+        size: f.file_length,
+        sha256: None,
+        sha1,
+        platform: Platform::CurseForge,
+        game_versions: f.game_versions,
+        loaders: vec![],
+        release_type,
+        dependencies: f
+            .dependencies
+            .into_iter()
+            .filter(|d| d.relation_type == 3) // Required only
+            .map(|d| ContentDependency {
+                project_id: d.mod_id.to_string(),
+                dependency_type: "required".to_string(),
+            })
+            .collect(),
+    })
+}
+
 /// Convenience functions for direct Modrinth access
 pub mod modrinth_helpers {
     use super::*;
@@ -12,6 +12,7 @@ pub const CLASS_MODS: u32 = 6;
 pub const CLASS_RESOURCEPACKS: u32 = 12;
 pub const CLASS_SHADERS: u32 = 6552;
 pub const CLASS_MODPACKS: u32 = 4471;
+pub const CLASS_DATAPACKS: u32 = 6945;
 
 /// CurseForge mod (project)
 #[derive(Debug, Clone, Deserialize)]
@@ -0,0 +1,572 @@
+//! Structured profile diffs.
+//!
+//! Compares everything a profile declares: Minecraft version, loader, runtime,
+//! every content list (by identity, with versions and hashes) and the files in
+//! `overrides/`. The diff serializes to JSON for tooling and renders as a short
+//! text summary for review. Either side can be a profile, a profile directory
+//! or a `profile.json` file, so revisions from git can be compared too.
+
+use crate::paths::Paths;
+use crate::profile::{ContentRef, Loader, Profile, Server, load_profile, read_profile_file};
+use crate::store::{hash_dir, normalize_hash};
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProfileDiff {
+    /// Changes to scalar settings (mcVersion, loader, runtime.*, ...)
+    pub fields: Vec<FieldChange>,
+    pub mods: Vec<ContentChange>,
+    pub resourcepacks: Vec<ContentChange>,
+    pub shaderpacks: Vec<ContentChange>,
+    pub datapacks: Vec<ContentChange>,
+    pub overrides: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    pub fn symbol(self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentChange {
+    pub change: ChangeKind,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<ContentState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<ContentState>,
+}
+
+/// The parts of a content item that a diff reports on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContentState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub hash: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    pub change: ChangeKind,
+    /// Path relative to overrides/
+    pub path: String,
+}
+
+impl ProfileDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+            && self.mods.is_empty()
+            && self.resourcepacks.is_empty()
+            && self.shaderpacks.is_empty()
+            && self.datapacks.is_empty()
+            && self.overrides.is_empty()
+    }
+}
+
+impl ContentState {
+    fn from_ref(item: &ContentRef) -> Self {
+        Self {
+            version: item.version.clone(),
+            hash: item.hash.clone(),
+            enabled: item.enabled,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let mut out = self
+            .version
+            .clone()
+            .unwrap_or_else(|| short_hash(&self.hash).to_string());
+        if !self.enabled {
+            out.push_str(" (disabled)");
+        }
+        out
+    }
+
+    /// Like `describe`, but always includes the hash so that a content change
+    /// without a version bump is visible.
+    fn describe_with_hash(&self) -> String {
+        match &self.version {
+            Some(version) => {
+                let mut out = format!("{version} ({})", short_hash(&self.hash));
+                if !self.enabled {
+                    out.push_str(" (disabled)");
+                }
+                out
+            }
+            None => self.describe(),
+        }
+    }
+}
+
+/// One side of a diff
+#[derive(Debug, Clone)]
+pub struct DiffSide {
+    pub profile: Profile,
+    /// Hashes of the files in `overrides/`, if the side has that folder
+    pub overrides: Option<BTreeMap<String, String>>,
+}
+
+impl DiffSide {
+    /// Load a profile ID, or a path to a profile directory or `profile.json`.
+    /// Arguments containing a path separator or ending in `.json` are paths.
+    /// A file's overrides are read from an `overrides/` folder next to it.
+    pub fn load(paths: &Paths, arg: &str) -> Result<Self> {
+        if !arg.contains(['/', '\\']) && !arg.ends_with(".json") {
+            return Ok(Self {
+                profile: load_profile(paths, arg)?,
+                overrides: Some(hash_dir(&paths.profile_overrides(arg))?),
+            });
+        }
+        let path = Path::new(arg);
+        let (file, dir) = if path.is_dir() {
+            (path.join("profile.json"), Some(path))
+        } else if path.is_file() {
+            (path.to_path_buf(), path.parent())
+        } else {
+            bail!("no profile at {arg}");
+        };
+        let overrides_dir = dir.map(|dir| dir.join("overrides"));
+        let overrides = match overrides_dir {
+            Some(dir) if dir.is_dir() || path.is_dir() => Some(hash_dir(&dir)?),
+            _ => None,
+        };
+        Ok(Self {
+            profile: read_profile_file(&file)?,
+            overrides,
+        })
+    }
+}
+
+/// Compare two sides, including overrides when both sides have them.
+pub fn diff_sides(a: &DiffSide, b: &DiffSide) -> ProfileDiff {
+    let mut diff = diff_profiles(&a.profile, &b.profile);
+    if let (Some(a), Some(b)) = (&a.overrides, &b.overrides) {
+        diff.overrides = diff_overrides(a, b);
+    }
+    diff
+}
+
+/// Key identifying the same content item across two versions of a profile:
+/// its platform project when known, otherwise its name.
+pub fn content_key(item: &ContentRef) -> String {
+    match (&item.platform, &item.project_id) {
+        (Some(platform), Some(project_id)) => format!("{platform}:{project_id}"),
+        _ => item.name.clone(),
+    }
+}
+
+/// Compare two profiles. Overrides are not part of `profile.json`; fill them
+/// in with `diff_overrides`.
+pub fn diff_profiles(a: &Profile, b: &Profile) -> ProfileDiff {
+    let mut fields = Vec::new();
+    push_field(&mut fields, "extends", a.extends.clone(), b.extends.clone());
+    push_field(
+        &mut fields,
+        "mcVersion",
+        Some(a.mc_version.clone()),
+        Some(b.mc_version.clone()),
+    );
+    push_field(
+        &mut fields,
+        "loader",
+        a.loader.as_ref().map(describe_loader),
+        b.loader.as_ref().map(describe_loader),
+    );
+    push_field(
+        &mut fields,
+        "runtime.java",
+        a.runtime.java.clone(),
+        b.runtime.java.clone(),
+    );
+    push_field(
+        &mut fields,
+        "runtime.memory",
+        a.runtime.memory.clone(),
+        b.runtime.memory.clone(),
+    );
+    push_field(
+        &mut fields,
+        "runtime.args",
+        join_args(&a.runtime.args),
+        join_args(&b.runtime.args),
+    );
+    push_field(
+        &mut fields,
+        "runtime.width",
+        a.runtime.width.map(|v| v.to_string()),
+        b.runtime.width.map(|v| v.to_string()),
+    );
+    push_field(
+        &mut fields,
+        "runtime.height",
+        a.runtime.height.map(|v| v.to_string()),
+        b.runtime.height.map(|v| v.to_string()),
+    );
+    push_field(
+        &mut fields,
+        "runtime.fullscreen",
+        a.runtime.fullscreen.map(|v| v.to_string()),
+        b.runtime.fullscreen.map(|v| v.to_string()),
+    );
+    push_field(
+        &mut fields,
+        "runtime.demo",
+        a.runtime.demo.map(|v| v.to_string()),
+        b.runtime.demo.map(|v| v.to_string()),
+    );
+    push_field(
+        &mut fields,
+        "datapackWorlds",
+        join_worlds(&a.datapack_worlds),
+        join_worlds(&b.datapack_worlds),
+    );
+    push_field(
+        &mut fields,
+        "activeShader",
+        a.active_shader.clone(),
+        b.active_shader.clone(),
+    );
+    push_field(
+        &mut fields,
+        "frozen",
+        a.frozen.map(|v| v.to_string()),
+        b.frozen.map(|v| v.to_string()),
+    );
+    push_field(
+        &mut fields,
+        "linkStrategy",
+        a.link_strategy.map(|s| s.to_string()),
+        b.link_strategy.map(|s| s.to_string()),
+    );
+
+    let keys: BTreeSet<&String> = a.game_options.keys().chain(b.game_options.keys()).collect();
+    for key in keys {
+        push_field(
+            &mut fields,
+            &format!("gameOptions.{key}"),
+            a.game_options.get(key).cloned(),
+            b.game_options.get(key).cloned(),
+        );
+    }
+
+    let addresses: BTreeSet<String> = a
+        .servers
+        .iter()
+        .chain(&b.servers)
+        .map(|server| server.address.to_lowercase())
+        .collect();
+    for address in addresses {
+        let find = |servers: &[Server]| {
+            servers
+                .iter()
+                .find(|server| server.address.to_lowercase() == address)
+                .map(describe_server)
+        };
+        push_field(
+            &mut fields,
+            &format!("servers.{address}"),
+            find(&a.servers),
+            find(&b.servers),
+        );
+    }
+
+    ProfileDiff {
+        fields,
+        mods: diff_content(&a.mods, &b.mods),
+        resourcepacks: diff_content(&a.resourcepacks, &b.resourcepacks),
+        shaderpacks: diff_content(&a.shaderpacks, &b.shaderpacks),
+        datapacks: diff_content(&a.datapacks, &b.datapacks),
+        overrides: Vec::new(),
+    }
+}
+
+/// Compare two override trees as returned by `store::hash_dir`.
+pub fn diff_overrides(
+    a: &BTreeMap<String, String>,
+    b: &BTreeMap<String, String>,
+) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    for (path, hash) in a {
+        let change = match b.get(path) {
+            None => ChangeKind::Removed,
+            Some(other) if other != hash => ChangeKind::Changed,
+            Some(_) => continue,
+        };
+        changes.push(FileChange {
+            change,
+            path: path.clone(),
+        });
+    }
+    for path in b.keys().filter(|path| !a.contains_key(*path)) {
+        changes.push(FileChange {
+            change: ChangeKind::Added,
+            path: path.clone(),
+        });
+    }
+    changes.sort_by(|x, y| x.path.cmp(&y.path));
+    changes
+}
+
+fn diff_content(a: &[ContentRef], b: &[ContentRef]) -> Vec<ContentChange> {
+    let mut changes = Vec::new();
+    for old in a {
+        let key = content_key(old);
+        match b.iter().find(|item| content_key(item) == key) {
+            None => changes.push(ContentChange {
+                change: ChangeKind::Removed,
+                name: old.name.clone(),
+                old: Some(ContentState::from_ref(old)),
+                new: None,
+            }),
+            Some(new) => {
+                let old_state = ContentState::from_ref(old);
+                let new_state = ContentState::from_ref(new);
+                if old_state != new_state {
+                    changes.push(ContentChange {
+                        change: ChangeKind::Changed,
+                        name: new.name.clone(),
+                        old: Some(old_state),
+                        new: Some(new_state),
+                    });
+                }
+            }
+        }
+    }
+    for new in b {
+        let key = content_key(new);
+        if !a.iter().any(|item| content_key(item) == key) {
+            changes.push(ContentChange {
+                change: ChangeKind::Added,
+                name: new.name.clone(),
+                old: None,
+                new: Some(ContentState::from_ref(new)),
+            });
+        }
+    }
+    changes
+}
+
+fn push_field(
+    fields: &mut Vec<FieldChange>,
+    field: &str,
+    old: Option<String>,
+    new: Option<String>,
+) {
+    if old != new {
+        fields.push(FieldChange {
+            field: field.to_string(),
+            old,
+            new,
+        });
+    }
+}
+
+fn describe_loader(loader: &Loader) -> String {
+    format!("{} {}", loader.loader_type, loader.version)
+}
+
+/// One-line summary of a server entry
+pub fn describe_server(server: &Server) -> String {
+    let mut text = server.name.clone();
+    if let Some(icon) = &server.icon {
+        text.push_str(&format!(", icon {icon}"));
+    }
+    if !server.resource_pack.is_prompt() {
+        text.push_str(&format!(", resource pack {}", server.resource_pack.label()));
+    }
+    text
+}
+
+/// World names can contain spaces, so they are listed with commas.
+fn join_worlds(worlds: &[String]) -> Option<String> {
+    (!worlds.is_empty()).then(|| worlds.join(", "))
+}
+
+fn join_args(args: &[String]) -> Option<String> {
+    if args.is_empty() {
+        None
+    } else {
+        Some(args.join(" "))
+    }
+}
+
+fn short_hash(hash: &str) -> &str {
+    let hex = normalize_hash(hash);
+    hex.get(..12).unwrap_or(hex)
+}
+
+impl std::fmt::Display for ProfileDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for field in &self.fields {
+            writeln!(
+                f,
+                "{}: {} -> {}",
+                field.field,
+                field.old.as_deref().unwrap_or("(none)"),
+                field.new.as_deref().unwrap_or("(none)")
+            )?;
+        }
+        let lists = [
+            ("mods", &self.mods),
+            ("resourcepacks", &self.resourcepacks),
+            ("shaderpacks", &self.shaderpacks),
+            ("datapacks", &self.datapacks),
+        ];
+        for (label, changes) in lists {
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "{label}:")?;
+            for change in changes {
+                let detail = match (&change.old, &change.new) {
+                    (Some(old), Some(new)) if old.version == new.version => format!(
+                        "{} -> {}",
+                        old.describe_with_hash(),
+                        new.describe_with_hash()
+                    ),
+                    (Some(old), Some(new)) => format!("{} -> {}", old.describe(), new.describe()),
+                    (Some(state), None) | (None, Some(state)) => state.describe(),
+                    (None, None) => String::new(),
+                };
+                writeln!(f, "  {} {} {}", change.change.symbol(), change.name, detail)?;
+            }
+        }
+        if !self.overrides.is_empty() {
+            writeln!(f, "overrides:")?;
+            for change in &self.overrides {
+                writeln!(f, "  {} {}", change.change.symbol(), change.path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn profile(value: serde_json::Value) -> Profile {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn keys_content_by_platform_project() {
+        let a = profile(json!({
+            "id": "a",
+            "mcVersion": "1.21.4",
+            "mods": [
+                { "name": "Sodium", "hash": "sha256:aa", "version": "0.6.0",
+                  "platform": "modrinth", "project_id": "AANobbMI" },
+                { "name": "lithium", "hash": "sha256:bb" }
+            ]
+        }));
+        let b = profile(json!({
+            "id": "a",
+            "mcVersion": "1.21.5",
+            "mods": [
+                { "name": "sodium-fabric", "hash": "sha256:cc", "version": "0.6.1",
+                  "platform": "modrinth", "project_id": "AANobbMI" },
+                { "name": "iris", "hash": "sha256:dd" }
+            ]
+        }));
+
+        let diff = diff_profiles(&a, &b);
+        let fields: Vec<_> = diff.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, ["mcVersion"]);
+        let mods: Vec<_> = diff
+            .mods
+            .iter()
+            .map(|c| (c.change, c.name.as_str()))
+            .collect();
+        assert_eq!(
+            mods,
+            [
+                (ChangeKind::Changed, "sodium-fabric"),
+                (ChangeKind::Removed, "lithium"),
+                (ChangeKind::Added, "iris")
+            ]
+        );
+        assert_eq!(
+            diff.mods[0].old.as_ref().unwrap().version.as_deref(),
+            Some("0.6.0")
+        );
+        assert!(diff_profiles(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn diffs_overrides_and_profile_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let old = dir.path().join("old");
+        fs::create_dir_all(old.join("overrides/config")).unwrap();
+        fs::write(
+            old.join("profile.json"),
+            r#"{"id":"a","mcVersion":"1.21.4"}"#,
+        )
+        .unwrap();
+        fs::write(old.join("overrides/options.txt"), "fov:70").unwrap();
+        fs::write(old.join("overrides/config/a.toml"), "a").unwrap();
+        let new = dir.path().join("new");
+        fs::create_dir_all(new.join("overrides/config")).unwrap();
+        fs::write(
+            new.join("profile.json"),
+            r#"{"id":"a","mcVersion":"1.21.4"}"#,
+        )
+        .unwrap();
+        fs::write(new.join("overrides/options.txt"), "fov:90").unwrap();
+        fs::write(new.join("overrides/config/b.toml"), "b").unwrap();
+
+        let a = DiffSide::load(&paths, old.to_str().unwrap()).unwrap();
+        let b = DiffSide::load(&paths, new.join("profile.json").to_str().unwrap()).unwrap();
+        let diff = diff_sides(&a, &b);
+        let overrides: Vec<_> = diff
+            .overrides
+            .iter()
+            .map(|c| (c.change, c.path.as_str()))
+            .collect();
+        assert_eq!(
+            overrides,
+            [
+                (ChangeKind::Removed, "config/a.toml"),
+                (ChangeKind::Added, "config/b.toml"),
+                (ChangeKind::Changed, "options.txt")
+            ]
+        );
+
+        // A lone file has no overrides to compare
+        let lone = dir.path().join("HEAD-profile.json");
+        fs::copy(old.join("profile.json"), &lone).unwrap();
+        let lone = DiffSide::load(&paths, lone.to_str().unwrap()).unwrap();
+        assert!(diff_sides(&lone, &b).is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+//! Detect and capture instance drift.
+//!
+//! Instances are derived from their profile, so anything a player changes in
+//! `instances/<id>` directly is lost the next time it is materialized. Drift is
+//! either a foreign file dropped into a managed content directory or a config
+//! file that no longer matches `overrides/`. Config files without an override
+//! (most of them generated by mods) only count once they change from the
+//! baseline recorded in `.shard/configs.json` when the instance was last
+//! materialized. Capturing drift stores foreign files as content of the
+//! profile and copies changed configs back into the profile's overrides.
+
+use crate::inherit::{hash_overrides, resolve_profile};
+use crate::instance::{content_dirs, datapack_dirs, plan_instance};
+use crate::paths::Paths;
+use crate::profile::{
+    ContentRef, Profile, save_profile, upsert_datapack, upsert_mod, upsert_resourcepack,
+    upsert_shaderpack,
+};
+use crate::shaders::settings_file_name;
+use crate::snapshot::snapshot_profile;
+use crate::store::{ContentKind, hash_dir, hash_file, store_content};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Instance paths whose files are treated as configuration even when the
+/// profile has no override for them yet
+pub const CONFIG_PATHS: [&str; 3] = ["config", "defaultconfigs", "options.txt"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftKind {
+    /// A file in a managed content directory that the profile does not declare
+    Foreign,
+    /// A config file that differs from its override
+    Modified,
+    /// A config file with no override that changed since it was materialized
+    Added,
+}
+
+impl DriftKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DriftKind::Foreign => "foreign",
+            DriftKind::Modified => "modified",
+            DriftKind::Added => "added",
+        }
+    }
+}
+
+/// A file in the instance that differs from what the profile describes
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftItem {
+    pub kind: DriftKind,
+    /// Path relative to the instance directory
+    pub path: String,
+}
+
+impl std::fmt::Display for DriftItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind.label(), self.path)
+    }
+}
+
+/// Outcome of capturing drift into a profile
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureReport {
+    /// Foreign files stored and added to the profile
+    pub content: Vec<String>,
+    /// Config files copied into overrides
+    pub overrides: Vec<String>,
+}
+
+/// List foreign files in the managed content directories and config files that
+/// differ from the profile's overrides.
+pub fn instance_drift(paths: &Paths, profile: &Profile) -> Result<Vec<DriftItem>> {
+    let instance_dir = paths.instance_dir(&profile.id);
+    let mut drift = Vec::new();
+    if !instance_dir.exists() {
+        return Ok(drift);
+    }
+
+    let resolved = resolve_profile(paths, profile)?;
+    let planned_files = plan_instance(paths, &resolved);
+    let mut planned: HashSet<String> = planned_files.iter().map(|file| file.rel_path()).collect();
+    // Settings the shader loader keeps next to a pack are not foreign content
+    planned.extend(
+        planned_files
+            .iter()
+            .filter(|file| file.dir == "shaderpacks")
+            .map(|file| format!("shaderpacks/{}", settings_file_name(&file.file_name))),
+    );
+    let datapack_dirs = datapack_dirs(paths, &resolved);
+    for dir in content_dirs(paths, &resolved) {
+        let target_dir = instance_dir.join(&dir);
+        if !target_dir.exists() {
+            continue;
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&target_dir)
+            .with_context(|| format!("failed to read directory: {}", target_dir.display()))?
+        {
+            let entry = entry.context("failed to read dir entry")?;
+            if !entry
+                .file_type()
+                .context("failed to read entry type")?
+                .is_file()
+            {
+                continue;
+            }
+            let path = format!("{dir}/{}", entry.file_name().to_string_lossy());
+            if !planned.contains(&path) {
+                names.push(path);
+            }
+        }
+        names.sort();
+        drift.extend(names.into_iter().map(|path| DriftItem {
+            kind: DriftKind::Foreign,
+            path,
+        }));
+    }
+
+    let overrides = hash_overrides(paths, profile)?;
+    for (rel, hash) in &overrides {
+        let target = instance_dir.join(rel);
+        if target.is_file() && &format!("sha256:{}", hash_file(&target)?) != hash {
+            drift.push(DriftItem {
+                kind: DriftKind::Modified,
+                path: rel.clone(),
+            });
+        }
+    }
+    let baseline = load_config_baseline(paths, &profile.id)?;
+    let configs = unmanaged_configs(&instance_dir, &datapack_dirs, |rel| {
+        overrides.contains_key(rel)
+    })?;
+    for (rel, hash) in configs {
+        if baseline.get(&rel).is_some_and(|recorded| *recorded != hash) {
+            drift.push(DriftItem {
+                kind: DriftKind::Added,
+                path: rel,
+            });
+        }
+    }
+
+    Ok(drift)
+}
+
+/// Record the hashes of config files that have no override. Files already in
+/// the baseline keep their recorded hash, so changes made in-game stay visible
+/// as drift, unless the launcher itself just `rewrote` them.
+pub fn record_config_baseline(
+    paths: &Paths,
+    profile: &Profile,
+    overrides: &BTreeMap<String, PathBuf>,
+    rewritten: &[String],
+) -> Result<()> {
+    let instance_dir = paths.instance_dir(&profile.id);
+    let mut baseline = load_config_baseline(paths, &profile.id)?;
+    let configs = unmanaged_configs(&instance_dir, &datapack_dirs(paths, profile), |rel| {
+        overrides.contains_key(rel)
+    })?;
+    baseline.retain(|rel, _| configs.contains_key(rel));
+    for (rel, hash) in configs {
+        if rewritten.contains(&rel) || !baseline.contains_key(&rel) {
+            baseline.insert(rel, hash);
+        }
+    }
+
+    let path = paths.instance_state_dir(&profile.id).join("configs.json");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+    }
+    let data =
+        serde_json::to_string_pretty(&baseline).context("failed to serialize config baseline")?;
+    fs::write(&path, data).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn load_config_baseline(paths: &Paths, profile_id: &str) -> Result<BTreeMap<String, String>> {
+    let path = paths.instance_state_dir(profile_id).join("configs.json");
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Hashes of the files under `CONFIG_PATHS` that have no override. A global
+/// datapack folder under config/ is content, not config.
+fn unmanaged_configs(
+    instance_dir: &Path,
+    datapack_dirs: &[String],
+    has_override: impl Fn(&str) -> bool,
+) -> Result<BTreeMap<String, String>> {
+    let mut configs = BTreeMap::new();
+    for config in CONFIG_PATHS {
+        let path = instance_dir.join(config);
+        if path.is_file() {
+            if !has_override(config) {
+                configs.insert(config.to_string(), format!("sha256:{}", hash_file(&path)?));
+            }
+            continue;
+        }
+        for (rel, hash) in hash_dir(&path)? {
+            let rel = format!("{config}/{rel}");
+            let in_datapack_dir = datapack_dirs
+                .iter()
+                .any(|dir| rel.starts_with(&format!("{dir}/")));
+            if !has_override(&rel) && !in_datapack_dir {
+                configs.insert(rel, hash);
+            }
+        }
+    }
+    Ok(configs)
+}
+
+/// Capture the selected drift into the profile: foreign files are stored and
+/// added as content, config files are copied into overrides. The profile is
+/// saved if any content was added.
+pub fn capture_drift(
+    paths: &Paths,
+    profile: &mut Profile,
+    items: &[DriftItem],
+) -> Result<CaptureReport> {
+    snapshot_profile(paths, &profile.id)?;
+    let instance_dir = paths.instance_dir(&profile.id);
+    let overrides_dir = paths.profile_overrides(&profile.id);
+    let datapack_dirs = datapack_dirs(paths, &resolve_profile(paths, profile)?);
+    let mut report = CaptureReport::default();
+
+    for item in items {
+        let source = instance_dir.join(&item.path);
+        match item.kind {
+            DriftKind::Foreign => {
+                let (dir, file_name) = item
+                    .path
+                    .rsplit_once('/')
+                    .with_context(|| format!("not a content path: {}", item.path))?;
+                let kind = content_kind_for_dir(dir, &datapack_dirs)
+                    .with_context(|| format!("not a managed content directory: {dir}"))?;
+                let stored =
+                    store_content(paths, kind, &source, None, Some(file_name.to_string()))?;
+                let content = ContentRef {
+                    name: stored.name,
+                    hash: stored.hash,
+                    version: None,
+                    source: None,
+                    file_name: Some(stored.file_name),
+                    platform: None,
+                    project_id: None,
+                    version_id: None,
+                    enabled: true,
+                    pinned: false,
+                };
+                let added = match kind {
+                    ContentKind::Mod => upsert_mod(profile, content),
+                    ContentKind::ResourcePack => upsert_resourcepack(profile, content),
+                    ContentKind::Datapack => upsert_datapack(profile, content),
+                    _ => upsert_shaderpack(profile, content),
+                };
+                if added {
+                    report.content.push(item.path.clone());
+                }
+            }
+            DriftKind::Modified | DriftKind::Added => {
+                let target = overrides_dir.join(&item.path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+                }
+                fs::copy(&source, &target)
+                    .with_context(|| format!("failed to copy {} to overrides", item.path))?;
+                report.overrides.push(item.path.clone());
+            }
+        }
+    }
+
+    if !report.content.is_empty() {
+        save_profile(paths, profile)?;
+    }
+    Ok(report)
+}
+
+fn content_kind_for_dir(dir: &str, datapack_dirs: &[String]) -> Option<ContentKind> {
+    match dir {
+        "mods" => Some(ContentKind::Mod),
+        "resourcepacks" => Some(ContentKind::ResourcePack),
+        "shaderpacks" => Some(ContentKind::ShaderPack),
+        _ if datapack_dirs.iter().any(|d| d == dir) => Some(ContentKind::Datapack),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inherit::resolve_overrides;
+    use serde_json::json;
+
+    fn write(path: &Path, data: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn reports_and_captures_only_real_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        paths.ensure().unwrap();
+        let mut profile: Profile =
+            serde_json::from_value(json!({ "id": "a", "mcVersion": "1.21.4" })).unwrap();
+        save_profile(&paths, &profile).unwrap();
+        write(
+            &paths.profile_overrides("a").join("options.txt"),
+            "fov:70\n",
+        );
+
+        // As left by the last materialize, plus a config the game generated
+        let instance = paths.instance_dir("a");
+        write(&instance.join("options.txt"), "fov:70\n");
+        write(&instance.join("config/sodium.json"), "{}");
+        let overrides = resolve_overrides(&paths, &profile).unwrap();
+        record_config_baseline(&paths, &profile, &overrides, &[]).unwrap();
+        write(&instance.join("config/generated.toml"), "a = 1");
+        assert!(instance_drift(&paths, &profile).unwrap().is_empty());
+
+        write(&instance.join("mods/dropped.jar"), "jar");
+        write(&instance.join("options.txt"), "fov:90\n");
+        write(&instance.join("config/sodium.json"), "{\"fog\":false}");
+        let drift = instance_drift(&paths, &profile).unwrap();
+        let listed: Vec<String> = drift.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            listed,
+            [
+                "foreign mods/dropped.jar",
+                "modified options.txt",
+                "added config/sodium.json"
+            ]
+        );
+
+        let report = capture_drift(&paths, &mut profile, &drift).unwrap();
+        assert_eq!(report.content, ["mods/dropped.jar"]);
+        assert_eq!(report.overrides, ["options.txt", "config/sodium.json"]);
+        assert_eq!(profile.mods.len(), 1);
+        let overrides_dir = paths.profile_overrides("a");
+        assert_eq!(
+            fs::read_to_string(overrides_dir.join("options.txt")).unwrap(),
+            "fov:90\n"
+        );
+        assert_eq!(
+            fs::read_to_string(overrides_dir.join("config/sodium.json")).unwrap(),
+            "{\"fog\":false}"
+        );
+        assert!(instance_drift(&paths, &profile).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! Download a profile's missing store content from its own metadata.
+//!
+//! A `profile.json` pulled from git references content by hash. When those
+//! blobs are not in the local store, each `ContentRef` is re-downloaded from
+//! its platform version (Modrinth/CurseForge) or its `source` URL and checked
+//! against the recorded SHA-256 before it is stored.
+
+use crate::content_store::{ContentStore, Platform};
+use crate::inherit::resolve_profile;
+use crate::paths::Paths;
+use crate::profile::{ContentRef, Profile};
+use crate::store::{ContentKind, content_store_path, hash_file, normalize_hash, store_content, store_from_url};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs;
+
+/// Outcome of fetching a profile's content
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FetchReport {
+    /// Items that were downloaded into the store
+    pub fetched: Vec<String>,
+    /// Number of items already present in the store
+    pub present: usize,
+    /// Items that could not be downloaded or failed verification
+    pub failed: Vec<FetchFailure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FetchFailure {
+    pub kind: String,
+    pub name: String,
+    pub reason: String,
+}
+
+/// Download every content item of the profile that is missing from the store.
+pub fn fetch_profile_content(
+    paths: &Paths,
+    profile: &Profile,
+    curseforge_api_key: Option<&str>,
+) -> Result<FetchReport> {
+    let profile = &resolve_profile(paths, profile)?;
+    let store = ContentStore::new(curseforge_api_key);
+    let mut report = FetchReport::default();
+
+    let lists = [
+        (ContentKind::Mod, &profile.mods),
+        (ContentKind::ResourcePack, &profile.resourcepacks),
+        (ContentKind::ShaderPack, &profile.shaderpacks),
+        (ContentKind::Datapack, &profile.datapacks),
+    ];
+    for (kind, items) in lists {
+        for item in items {
+            if content_store_path(paths, kind, &item.hash).exists() {
+                report.present += 1;
+                continue;
+            }
+            match fetch_item(paths, &store, kind, item) {
+                Ok(()) => report.fetched.push(item.name.clone()),
+                Err(err) => report.failed.push(FetchFailure {
+                    kind: kind.label().to_string(),
+                    name: item.name.clone(),
+                    reason: format!("{err:#}"),
+                }),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn fetch_item(paths: &Paths, store: &ContentStore, kind: ContentKind, item: &ContentRef) -> Result<()> {
+    let url = download_url(store, item)?;
+    let (download_path, file_name) = store_from_url(paths, &url)?;
+
+    let expected = normalize_hash(&item.hash).to_lowercase();
+    let actual = hash_file(&download_path)?;
+    if actual != expected {
+        let _ = fs::remove_file(&download_path);
+        bail!("sha256 mismatch: expected {expected}, downloaded {actual} from {url}");
+    }
+
+    let file_name = item.file_name.clone().unwrap_or(file_name);
+    store_content(paths, kind, &download_path, Some(url), Some(file_name))?;
+    let _ = fs::remove_file(&download_path);
+    Ok(())
+}
+
+/// Pick the URL to download an item from: its platform version if known,
+/// otherwise the recorded source URL.
+fn download_url(store: &ContentStore, item: &ContentRef) -> Result<String> {
+    let source_url = item
+        .source
+        .as_deref()
+        .filter(|s| s.starts_with("http://") || s.starts_with("https://"));
+
+    let platform = item.platform.as_deref().and_then(Platform::parse);
+    if let (Some(platform), Some(project_id), Some(version_id)) =
+        (platform, item.project_id.as_deref(), item.version_id.as_deref())
+    {
+        match store.get_version(platform, project_id, version_id) {
+            Ok(version) => return Ok(version.download_url),
+            Err(err) => match source_url {
+                Some(url) => {
+                    eprintln!(
+                        "warning: {platform} lookup failed for {} ({err}), using source URL",
+                        item.name
+                    );
+                    return Ok(url.to_string());
+                }
+                None => return Err(err),
+            },
+        }
+    }
+
+    source_url
+        .map(str::to_string)
+        .context("no platform version or source URL to download from")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn skips_present_blobs_and_reports_unresolvable_items() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        paths.ensure().unwrap();
+        let jar = dir.path().join("sodium.jar");
+        fs::write(&jar, b"sodium").unwrap();
+        let stored = store_content(&paths, ContentKind::Mod, &jar, None, None).unwrap();
+
+        let profile: Profile = serde_json::from_value(json!({
+            "id": "a",
+            "mcVersion": "1.21.4",
+            "mods": [
+                { "name": "sodium", "hash": stored.hash },
+                { "name": "local-only", "hash": "sha256:00", "source": "/home/me/local.jar" }
+            ],
+            "resourcepacks": [{ "name": "faithful", "hash": "sha256:11" }]
+        }))
+        .unwrap();
+
+        let report = fetch_profile_content(&paths, &profile, None).unwrap();
+        assert_eq!(report.present, 1);
+        assert!(report.fetched.is_empty());
+        let failed: Vec<_> = report
+            .failed
+            .iter()
+            .map(|f| (f.kind.as_str(), f.name.as_str()))
+            .collect();
+        assert_eq!(
+            failed,
+            [("mod", "local-only"), ("resourcepack", "faithful")]
+        );
+        assert!(
+            report.failed[0]
+                .reason
+                .contains("no platform version or source URL")
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Profile inheritance.
+//!
+//! A profile can name a base profile in `extends`. The base is resolved first
+//! (bases can extend other profiles), then the child is layered on top: its
+//! content replaces inherited items with the same identity, its runtime args
+//! are appended, its servers replace those with the same address, its
+//! overrides win over the base's, and anything listed in its `remove` section
+//! is dropped from what it inherits. Functions that take a profile from disk
+//! resolve it themselves, so callers pass the declared profile.
+
+use crate::diff::content_key;
+use crate::paths::Paths;
+use crate::profile::{ContentRef, Profile, Removals, Server, load_profile};
+use crate::store::hash_file;
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The profile and all of its bases, root first.
+pub fn profile_chain(paths: &Paths, profile: &Profile) -> Result<Vec<Profile>> {
+    let mut chain = vec![profile.clone()];
+    let mut next = profile.extends.clone();
+    while let Some(parent_id) = next {
+        if chain.iter().any(|p| p.id == parent_id) {
+            bail!(
+                "profile {} has an inheritance cycle through {parent_id}",
+                profile.id
+            );
+        }
+        let parent = load_profile(paths, &parent_id)
+            .with_context(|| format!("failed to load base profile: {parent_id}"))?;
+        next = parent.extends.clone();
+        chain.push(parent);
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+/// Flatten a profile and its bases into a single profile without `extends`.
+pub fn resolve_profile(paths: &Paths, profile: &Profile) -> Result<Profile> {
+    if profile.extends.is_none() {
+        return Ok(profile.clone());
+    }
+    let mut chain = profile_chain(paths, profile)?.into_iter();
+    let mut resolved = chain.next().context("empty profile chain")?;
+    for child in chain {
+        resolved = layer(resolved, child);
+    }
+    resolved.extends = None;
+    resolved.remove = Removals::default();
+    Ok(resolved)
+}
+
+fn layer(base: Profile, child: Profile) -> Profile {
+    let mut runtime = child.runtime.clone();
+    runtime.java = child.runtime.java.or(base.runtime.java);
+    runtime.memory = child.runtime.memory.or(base.runtime.memory);
+    runtime.width = child.runtime.width.or(base.runtime.width);
+    runtime.height = child.runtime.height.or(base.runtime.height);
+    runtime.fullscreen = child.runtime.fullscreen.or(base.runtime.fullscreen);
+    runtime.demo = child.runtime.demo.or(base.runtime.demo);
+    runtime.args = base.runtime.args;
+    runtime.args.extend(child.runtime.args);
+    let mut game_options = base.game_options;
+    game_options.extend(child.game_options);
+
+    Profile {
+        mods: layer_content(base.mods, child.mods, &child.remove.mods),
+        resourcepacks: layer_content(
+            base.resourcepacks,
+            child.resourcepacks,
+            &child.remove.resourcepacks,
+        ),
+        shaderpacks: layer_content(
+            base.shaderpacks,
+            child.shaderpacks,
+            &child.remove.shaderpacks,
+        ),
+        datapacks: layer_content(base.datapacks, child.datapacks, &child.remove.datapacks),
+        datapack_worlds: if child.datapack_worlds.is_empty() {
+            base.datapack_worlds
+        } else {
+            child.datapack_worlds
+        },
+        mc_version: if child.mc_version.is_empty() {
+            base.mc_version
+        } else {
+            child.mc_version
+        },
+        loader: child.loader.or(base.loader),
+        runtime,
+        game_options,
+        active_shader: child.active_shader.or(base.active_shader),
+        servers: layer_servers(base.servers, child.servers, &child.remove.servers),
+        frozen: child.frozen.or(base.frozen),
+        link_strategy: child.link_strategy.or(base.link_strategy),
+        ..child
+    }
+}
+
+fn layer_content(
+    mut base: Vec<ContentRef>,
+    child: Vec<ContentRef>,
+    removed: &[String],
+) -> Vec<ContentRef> {
+    base.retain(|item| {
+        !removed
+            .iter()
+            .any(|r| *r == item.name || *r == item.hash || *r == content_key(item))
+    });
+    for item in child {
+        let key = content_key(&item);
+        match base
+            .iter_mut()
+            .find(|existing| content_key(existing) == key)
+        {
+            Some(existing) => *existing = item,
+            None => base.push(item),
+        }
+    }
+    base
+}
+
+fn layer_servers(mut base: Vec<Server>, child: Vec<Server>, removed: &[String]) -> Vec<Server> {
+    base.retain(|server| !removed.iter().any(|r| server.matches(r)));
+    for server in child {
+        match base
+            .iter_mut()
+            .find(|existing| existing.address.eq_ignore_ascii_case(&server.address))
+        {
+            Some(existing) => *existing = server,
+            None => base.push(server),
+        }
+    }
+    base
+}
+
+/// Override files of the profile and its bases, keyed by path relative to
+/// `overrides/`. Files from a child replace those of its base.
+pub fn resolve_overrides(paths: &Paths, profile: &Profile) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    for layer in profile_chain(paths, profile)? {
+        let removed = &layer.remove.overrides;
+        files.retain(|rel: &String, _| {
+            !removed.iter().any(|r| {
+                let r = r.trim_end_matches('/');
+                rel == r || rel.starts_with(&format!("{r}/"))
+            })
+        });
+        let dir = paths.profile_overrides(&layer.id);
+        list_files(&dir, &dir, &mut files)?;
+    }
+    Ok(files)
+}
+
+/// Hashes ("sha256:<hex>") of the resolved override files.
+pub fn hash_overrides(paths: &Paths, profile: &Profile) -> Result<BTreeMap<String, String>> {
+    resolve_overrides(paths, profile)?
+        .into_iter()
+        .map(|(rel, path)| Ok((rel, format!("sha256:{}", hash_file(&path)?))))
+        .collect()
+}
+
+fn list_files(root: &Path, dir: &Path, files: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let path = entry.path();
+        if entry
+            .file_type()
+            .context("failed to read entry type")?
+            .is_dir()
+        {
+            list_files(root, &path, files)?;
+            continue;
+        }
+        let rel = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.insert(rel, path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn profile(value: serde_json::Value) -> Profile {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn layers_child_over_base() {
+        let base = profile(json!({
+            "id": "base",
+            "mcVersion": "1.21.4",
+            "mods": [
+                { "name": "sodium", "hash": "a" },
+                { "name": "lithium", "hash": "b" }
+            ],
+            "runtime": { "memory": "4G", "args": ["-XX:+UseG1GC"], "fullscreen": true },
+            "frozen": true
+        }));
+        let child = profile(json!({
+            "id": "child",
+            "extends": "base",
+            "mods": [
+                { "name": "sodium", "hash": "c" },
+                { "name": "iris", "hash": "d" }
+            ],
+            "runtime": { "args": ["-Dfoo=bar"] },
+            "frozen": false
+        }));
+
+        let resolved = layer(base, child);
+        assert_eq!(resolved.id, "child");
+        assert_eq!(resolved.mc_version, "1.21.4");
+        let mods: Vec<_> = resolved.mods.iter().map(|m| m.hash.as_str()).collect();
+        assert_eq!(mods, ["c", "b", "d"]);
+        assert_eq!(resolved.runtime.memory.as_deref(), Some("4G"));
+        assert_eq!(resolved.runtime.args, ["-XX:+UseG1GC", "-Dfoo=bar"]);
+        // The child's explicit `false` wins, an unset value is inherited
+        assert_eq!(resolved.frozen, Some(false));
+        assert_eq!(resolved.runtime.fullscreen, Some(true));
+    }
+
+    #[test]
+    fn applies_removals() {
+        let base = profile(json!({
+            "id": "base",
+            "mcVersion": "1.21.4",
+            "mods": [
+                { "name": "sodium", "hash": "a" },
+                { "name": "Lithium", "hash": "b", "platform": "modrinth", "project_id": "gvQqBUqZ" }
+            ]
+        }));
+        let child = profile(json!({
+            "id": "child",
+            "extends": "base",
+            "remove": { "mods": ["modrinth:gvQqBUqZ"] }
+        }));
+
+        let resolved = layer(base, child);
+        let mods: Vec<_> = resolved.mods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(mods, ["sodium"]);
+    }
+}
@@ -1,69 +1,471 @@
+use crate::drift::record_config_baseline;
+use crate::inherit::{hash_overrides, resolve_overrides, resolve_profile};
+use crate::lock::ProfileLock;
+use crate::options::{OPTIONS_FILE, resource_pack_options, update_options_file};
 use crate::paths::Paths;
-use crate::profile::{ContentRef, Profile};
-use crate::store::{ContentKind, content_store_path};
-use crate::util::{copy_dir_merge, sanitize_filename, unique_path};
+use crate::profile::{ContentRef, Profile, is_valid_world_name};
+use crate::servers::apply_servers;
+use crate::shaders::{apply_shaders, settings_file_name};
+use crate::store::{ContentKind, content_store_path, hash_file, normalize_hash};
+use crate::util::sanitize_filename;
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub fn materialize_instance(paths: &Paths, profile: &Profile) -> Result<std::path::PathBuf> {
+/// Options controlling how an instance is materialized
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterializeOptions {
+    /// How content files are placed from the store into the instance
+    pub link_strategy: LinkStrategy,
+    /// Leave override files that were changed in the instance alone instead
+    /// of copying the override over them
+    pub keep_override_edits: bool,
+}
+
+/// How content files are placed from the store into an instance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStrategy {
+    /// Symlink to the store blob, copying if symlinks are not available
+    #[default]
+    Symlink,
+    /// Hard link to the store blob, copying if the store and instances are on
+    /// different filesystems. The game must not modify content files in place.
+    Hardlink,
+    /// Copy-on-write clone (btrfs, xfs, APFS), copying where unsupported
+    Reflink,
+    /// Plain copy
+    Copy,
+}
+
+impl LinkStrategy {
+    pub fn label(self) -> &'static str {
+        match self {
+            LinkStrategy::Symlink => "symlink",
+            LinkStrategy::Hardlink => "hardlink",
+            LinkStrategy::Reflink => "reflink",
+            LinkStrategy::Copy => "copy",
+        }
+    }
+}
+
+impl std::fmt::Display for LinkStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl std::str::FromStr for LinkStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "symlink" => Ok(LinkStrategy::Symlink),
+            "hardlink" => Ok(LinkStrategy::Hardlink),
+            "reflink" => Ok(LinkStrategy::Reflink),
+            "copy" => Ok(LinkStrategy::Copy),
+            other => anyhow::bail!(
+                "unknown link strategy: {other} (expected symlink, hardlink, reflink or copy)"
+            ),
+        }
+    }
+}
+
+/// What part of an instance a problem refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemKind {
+    Content,
+    Override,
+    Library,
+    Lockfile,
+}
+
+impl ProblemKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ProblemKind::Content => "content",
+            ProblemKind::Override => "override",
+            ProblemKind::Library => "library",
+            ProblemKind::Lockfile => "lockfile",
+        }
+    }
+}
+
+/// A missing or mismatched input found when verifying a frozen instance
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceProblem {
+    pub kind: ProblemKind,
+    pub name: String,
+    pub detail: String,
+}
+
+impl std::fmt::Display for InstanceProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.kind.label(), self.name, self.detail)
+    }
+}
+
+/// Error returned when a frozen instance is incomplete. Callers can downcast
+/// an `anyhow::Error` to this type to get at the individual problems.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncompleteInstance {
+    pub problems: Vec<InstanceProblem>,
+}
+
+impl std::fmt::Display for IncompleteInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "instance is incomplete ({} problems)",
+            self.problems.len()
+        )?;
+        for problem in &self.problems {
+            write!(f, "\n  - {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for IncompleteInstance {}
+
+/// Summary of the changes made while materializing an instance. Paths are
+/// relative to the instance directory.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaterializeSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    /// Override files copied into the instance
+    pub overrides_copied: Vec<String>,
+    /// Override files not copied because the instance copy was changed in-game
+    /// and `keep_override_edits` is set
+    pub overrides_kept: Vec<String>,
+    /// options.txt keys set from the profile's gameOptions
+    pub options_set: Vec<String>,
+    /// Shader settings files written for the profile's active shader and packs
+    pub shader_files: Vec<String>,
+    /// Servers added to or updated in servers.dat from the profile
+    pub servers_set: Vec<String>,
+}
+
+impl MaterializeSummary {
+    /// Whether materialization changed anything on disk
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.replaced.is_empty()
+            && self.removed.is_empty()
+            && self.overrides_copied.is_empty()
+            && self.options_set.is_empty()
+            && self.shader_files.is_empty()
+            && self.servers_set.is_empty()
+    }
+}
+
+impl std::fmt::Display for MaterializeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} replaced, {} removed, {} unchanged, {} override files copied",
+            self.added.len(),
+            self.replaced.len(),
+            self.removed.len(),
+            self.unchanged,
+            self.overrides_copied.len()
+        )?;
+        if !self.overrides_kept.is_empty() {
+            write!(
+                f,
+                ", {} changed in-game and kept",
+                self.overrides_kept.len()
+            )?;
+        }
+        if !self.options_set.is_empty() {
+            write!(f, ", {} game options set", self.options_set.len())?;
+        }
+        if !self.shader_files.is_empty() {
+            write!(f, ", {} shader files written", self.shader_files.len())?;
+        }
+        if !self.servers_set.is_empty() {
+            write!(f, ", {} servers set", self.servers_set.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// A file the profile wants in one of the instance's managed directories
+#[derive(Debug, Clone)]
+pub struct PlannedFile {
+    /// Managed directory, relative to the instance (e.g. "mods")
+    pub dir: String,
+    /// Name of the content item in the profile
+    pub name: String,
+    pub file_name: String,
+    /// Blob in the content store the file is linked or copied from
+    pub source: PathBuf,
+    /// Expected sha256 hex of the file
+    pub hash: String,
+}
+
+impl PlannedFile {
+    pub fn rel_path(&self) -> String {
+        format!("{}/{}", self.dir, self.file_name)
+    }
+}
+
+/// Bring the instance directory in line with the profile, only touching files
+/// that differ from what the profile describes.
+pub fn materialize_instance(
+    paths: &Paths,
+    profile: &Profile,
+    options: &MaterializeOptions,
+) -> Result<MaterializeSummary> {
+    let declared = profile;
+    let profile = &resolve_profile(paths, declared)?;
     let instance_dir = paths.instance_dir(&profile.id);
     fs::create_dir_all(&instance_dir)
         .with_context(|| format!("failed to create instance dir: {}", instance_dir.display()))?;
 
-    sync_dir(&instance_dir.join("mods"))?;
-    sync_dir(&instance_dir.join("resourcepacks"))?;
-    sync_dir(&instance_dir.join("shaderpacks"))?;
+    let mut summary = MaterializeSummary::default();
+    let planned = plan_instance(paths, profile);
+    for world in &profile.datapack_worlds {
+        if !instance_dir.join("saves").join(world).is_dir() {
+            eprintln!(
+                "warning: world '{world}' does not exist yet, its datapacks are installed once it is created"
+            );
+        }
+    }
+    if !profile.datapacks.is_empty()
+        && profile.datapack_worlds.is_empty()
+        && profile.datapack_loader().is_none()
+    {
+        eprintln!(
+            "warning: datapacks not installed: set datapackWorlds or add a global datapack mod"
+        );
+    }
+    for dir in MANAGED_DIRS {
+        let files: Vec<&PlannedFile> = planned.iter().filter(|f| f.dir == dir).collect();
+        sync_managed_dir(
+            &instance_dir,
+            dir,
+            &files,
+            options.link_strategy,
+            &mut summary,
+        )?;
+    }
+    let datapacks: Vec<&PlannedFile> = planned
+        .iter()
+        .filter(|f| !MANAGED_DIRS.contains(&f.dir.as_str()))
+        .collect();
+    sync_datapacks(
+        paths,
+        &profile.id,
+        &instance_dir,
+        &datapacks,
+        options.link_strategy,
+        &mut summary,
+    )?;
 
-    populate_dir(
+    let overrides = resolve_overrides(paths, declared)?;
+    let mut applied = sync_overrides(
         paths,
-        &profile.mods,
-        ContentKind::Mod,
-        &instance_dir.join("mods"),
+        &profile.id,
+        &overrides,
+        &instance_dir,
+        options.keep_override_edits,
+        &mut summary,
     )?;
-    populate_dir(
+    let options_path = instance_dir.join(OPTIONS_FILE);
+    let packs: Vec<&str> = planned
+        .iter()
+        .filter(|f| f.dir == "resourcepacks")
+        .map(|f| f.file_name.as_str())
+        .collect();
+    let mut values = resource_pack_options(&options_path, &packs)?;
+    // Keys set explicitly in gameOptions win over the derived pack order
+    values.extend(profile.game_options.clone());
+    // gameOptions are written over an options.txt override. While the file is
+    // still the one placed from the override, record the result as placed, or
+    // later override changes would look like in-game edits.
+    let options_placed = match applied.get(OPTIONS_FILE) {
+        Some(entry) if options_path.is_file() => {
+            format!("sha256:{}", hash_file(&options_path)?) == entry.placed
+        }
+        _ => false,
+    };
+    summary.options_set = update_options_file(&options_path, &values)?;
+    if options_placed && !summary.options_set.is_empty() {
+        if let Some(entry) = applied.get_mut(OPTIONS_FILE) {
+            entry.placed = format!("sha256:{}", hash_file(&options_path)?);
+        }
+        save_override_state(paths, &profile.id, &applied)?;
+    }
+    summary.shader_files = apply_shaders(paths, declared, profile, &instance_dir, &planned)?;
+    summary.servers_set = apply_servers(paths, declared, profile, &instance_dir)?;
+
+    let mut rewritten = summary.shader_files.clone();
+    if !summary.options_set.is_empty() {
+        rewritten.push(OPTIONS_FILE.to_string());
+    }
+    record_config_baseline(paths, profile, &overrides, &rewritten)?;
+
+    Ok(summary)
+}
+
+/// Instance directories whose contents are fully owned by the profile
+pub const MANAGED_DIRS: [&str; 3] = ["mods", "resourcepacks", "shaderpacks"];
+
+/// `MANAGED_DIRS` plus the folders the profile's datapacks go to.
+pub fn content_dirs(paths: &Paths, profile: &Profile) -> Vec<String> {
+    let mut dirs: Vec<String> = MANAGED_DIRS.iter().map(|dir| dir.to_string()).collect();
+    dirs.extend(datapack_dirs(paths, profile));
+    dirs
+}
+
+/// Folders the profile's datapacks are installed into: `datapacks/` in each
+/// listed world that exists, otherwise the global folder of its datapack mod.
+/// Unlike `MANAGED_DIRS` these folders are shared with packs that came with a
+/// world or were added by hand, so only packs the launcher placed are removed.
+pub fn datapack_dirs(paths: &Paths, profile: &Profile) -> Vec<String> {
+    if profile.datapack_worlds.is_empty() {
+        if profile.datapacks.is_empty() {
+            return Vec::new();
+        }
+        return profile
+            .datapack_loader()
+            .map(|loader| vec![loader.dir().to_string()])
+            .unwrap_or_default();
+    }
+    let saves = paths.instance_saves(&profile.id);
+    profile
+        .datapack_worlds
+        .iter()
+        .filter(|world| is_valid_world_name(world) && saves.join(world).is_dir())
+        .map(|world| format!("saves/{world}/datapacks"))
+        .collect()
+}
+
+/// Compute the files the profile wants in its managed directories. Disabled
+/// items and items missing from the store are left out.
+pub fn plan_instance(paths: &Paths, profile: &Profile) -> Vec<PlannedFile> {
+    let mut planned = Vec::new();
+    plan_dir(paths, &profile.mods, ContentKind::Mod, "mods", &mut planned);
+    plan_dir(
         paths,
         &profile.resourcepacks,
         ContentKind::ResourcePack,
-        &instance_dir.join("resourcepacks"),
-    )?;
-    populate_dir(
+        "resourcepacks",
+        &mut planned,
+    );
+    plan_dir(
         paths,
         &profile.shaderpacks,
         ContentKind::ShaderPack,
-        &instance_dir.join("shaderpacks"),
-    )?;
-
-    let overrides_dir = paths.profile_overrides(&profile.id);
-    if overrides_dir.exists() {
-        copy_dir_merge(&overrides_dir, &instance_dir)?;
+        "shaderpacks",
+        &mut planned,
+    );
+    for dir in datapack_dirs(paths, profile) {
+        plan_dir(
+            paths,
+            &profile.datapacks,
+            ContentKind::Datapack,
+            &dir,
+            &mut planned,
+        );
     }
-
-    Ok(instance_dir)
+    planned
 }
 
-fn sync_dir(path: &Path) -> Result<()> {
-    if path.exists() {
-        fs::remove_dir_all(path)
-            .with_context(|| format!("failed to remove existing directory: {}", path.display()))?;
+/// Check that every enabled content item is in the store with the expected hash
+/// and that override files match the lockfile, if there is one.
+pub fn verify_instance(
+    paths: &Paths,
+    profile: &Profile,
+    lock: Option<&ProfileLock>,
+) -> Result<Vec<InstanceProblem>> {
+    let declared = profile;
+    let profile = &resolve_profile(paths, declared)?;
+    let mut problems = Vec::new();
+
+    let lists = [
+        (ContentKind::Mod, &profile.mods),
+        (ContentKind::ResourcePack, &profile.resourcepacks),
+        (ContentKind::ShaderPack, &profile.shaderpacks),
+        (ContentKind::Datapack, &profile.datapacks),
+    ];
+    for (kind, items) in lists {
+        for item in items.iter().filter(|item| item.enabled) {
+            let store_path = content_store_path(paths, kind, &item.hash);
+            let detail = if !store_path.exists() {
+                format!("{} missing from store ({})", kind.label(), item.hash)
+            } else {
+                let actual = hash_file(&store_path)?;
+                if actual == normalize_hash(&item.hash).to_lowercase() {
+                    continue;
+                }
+                format!(
+                    "{} in store hashes to sha256:{actual}, expected {}",
+                    kind.label(),
+                    item.hash
+                )
+            };
+            problems.push(InstanceProblem {
+                kind: ProblemKind::Content,
+                name: item.name.clone(),
+                detail,
+            });
+        }
     }
-    fs::create_dir_all(path)
-        .with_context(|| format!("failed to create directory: {}", path.display()))?;
-    Ok(())
+
+    let overrides = hash_overrides(paths, declared)?;
+    if let Some(lock) = lock {
+        for (path, hash) in &lock.overrides {
+            let detail = match overrides.get(path) {
+                None => "missing from overrides".to_string(),
+                Some(actual) if actual != hash => {
+                    format!("hashes to {actual}, lockfile has {hash}")
+                }
+                Some(_) => continue,
+            };
+            problems.push(InstanceProblem {
+                kind: ProblemKind::Override,
+                name: path.clone(),
+                detail,
+            });
+        }
+        for path in overrides
+            .keys()
+            .filter(|p| !lock.overrides.contains_key(*p))
+        {
+            problems.push(InstanceProblem {
+                kind: ProblemKind::Override,
+                name: path.clone(),
+                detail: "not in the lockfile".to_string(),
+            });
+        }
+    }
+
+    Ok(problems)
 }
 
-fn populate_dir(
+fn plan_dir(
     paths: &Paths,
     items: &[ContentRef],
     kind: ContentKind,
-    target_dir: &Path,
-) -> Result<()> {
+    dir: &str,
+    planned: &mut Vec<PlannedFile>,
+) {
     let default_ext = match kind {
         ContentKind::Mod => "jar",
-        ContentKind::ResourcePack | ContentKind::ShaderPack => "zip",
+        ContentKind::ResourcePack | ContentKind::ShaderPack | ContentKind::Datapack => "zip",
         ContentKind::Skin => "png",
     };
 
+    let mut taken = HashSet::new();
     for item in items {
         if !item.enabled {
             continue;
@@ -71,7 +473,7 @@ fn populate_dir(
         let store_path = content_store_path(paths, kind, &item.hash);
         if !store_path.exists() {
             eprintln!(
-                "warning: {} '{}' not found in store (hash: {}), skipping",
+                "warning: {} '{}' not found in store (hash: {}), skipping (run `shard profile fetch` to download it)",
                 kind.label(),
                 item.name,
                 item.hash
@@ -85,27 +487,350 @@ fn populate_dir(
             file_name.push('.');
             file_name.push_str(default_ext);
         }
+        let file_name = unique_name(&taken, &file_name);
+        taken.insert(file_name.clone());
 
-        let target_path = unique_path(target_dir, &file_name);
-        link_or_copy(&store_path, &target_path)?;
+        planned.push(PlannedFile {
+            dir: dir.to_string(),
+            name: item.name.clone(),
+            file_name,
+            source: store_path,
+            hash: normalize_hash(&item.hash).to_lowercase(),
+        });
     }
+}
 
-    Ok(())
+/// Like `unique_path`, but against the names already planned for a directory.
+fn unique_name(taken: &HashSet<String>, file_name: &str) -> String {
+    if !taken.contains(file_name) {
+        return file_name.to_string();
+    }
+    let stem = Path::new(file_name)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(file_name);
+    let ext = Path::new(file_name).extension().and_then(|s| s.to_str());
+    for idx in 1.. {
+        let mut name = format!("{}-{}", stem, idx);
+        if let Some(ext) = ext {
+            name.push('.');
+            name.push_str(ext);
+        }
+        if !taken.contains(&name) {
+            return name;
+        }
+    }
+    unreachable!()
 }
 
-fn link_or_copy(src: &Path, dst: &Path) -> Result<()> {
-    if let Err(err) = symlink_file(src, dst) {
-        fs::copy(src, dst).with_context(|| {
-            format!(
-                "failed to copy {} to {} after symlink error: {err}",
-                src.display(),
-                dst.display()
-            )
-        })?;
+fn sync_managed_dir(
+    instance_dir: &Path,
+    dir: &str,
+    files: &[&PlannedFile],
+    strategy: LinkStrategy,
+    summary: &mut MaterializeSummary,
+) -> Result<()> {
+    let target_dir = instance_dir.join(dir);
+    fs::create_dir_all(&target_dir)
+        .with_context(|| format!("failed to create directory: {}", target_dir.display()))?;
+
+    for entry in fs::read_dir(&target_dir)
+        .with_context(|| format!("failed to read directory: {}", target_dir.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if files
+            .iter()
+            .any(|f| f.file_name == name || is_pack_settings(f, &name))
+        {
+            continue;
+        }
+        remove_entry(&entry.path())?;
+        summary.removed.push(format!("{dir}/{name}"));
+    }
+
+    for file in files {
+        let target = target_dir.join(&file.file_name);
+        match fs::symlink_metadata(&target) {
+            Err(_) => {
+                place_file(&file.source, &target, strategy)?;
+                summary.added.push(file.rel_path());
+            }
+            Ok(meta) => {
+                if is_up_to_date(&target, &meta, file, strategy)? {
+                    summary.unchanged += 1;
+                } else {
+                    remove_entry(&target)?;
+                    place_file(&file.source, &target, strategy)?;
+                    summary.replaced.push(file.rel_path());
+                }
+            }
+        }
     }
+
     Ok(())
 }
 
+/// Place the profile's datapacks. Datapack folders are never fully owned: only
+/// packs the launcher placed, recorded in `.shard/datapacks.json`, are replaced
+/// or removed, so packs shipped with a world or added by players survive.
+fn sync_datapacks(
+    paths: &Paths,
+    profile_id: &str,
+    instance_dir: &Path,
+    files: &[&PlannedFile],
+    strategy: LinkStrategy,
+    summary: &mut MaterializeSummary,
+) -> Result<()> {
+    let state_path = paths.instance_state_dir(profile_id).join("datapacks.json");
+    let mut placed: BTreeSet<String> = if state_path.exists() {
+        let data = fs::read_to_string(&state_path)
+            .with_context(|| format!("failed to read {}", state_path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("failed to parse {}", state_path.display()))?
+    } else {
+        BTreeSet::new()
+    };
+
+    let wanted: HashSet<String> = files.iter().map(|file| file.rel_path()).collect();
+    for rel in placed.iter().filter(|rel| !wanted.contains(*rel)) {
+        let target = instance_dir.join(rel);
+        if fs::symlink_metadata(&target).is_ok() {
+            remove_entry(&target)?;
+            summary.removed.push(rel.clone());
+        }
+    }
+    placed.retain(|rel| wanted.contains(rel));
+
+    for file in files {
+        let rel = file.rel_path();
+        let target = instance_dir.join(&rel);
+        match fs::symlink_metadata(&target) {
+            Err(_) => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+                }
+                place_file(&file.source, &target, strategy)?;
+                summary.added.push(rel.clone());
+                placed.insert(rel);
+            }
+            Ok(meta) => {
+                // A link into the store can only have come from the launcher
+                let ours = placed.contains(&rel) || meta.file_type().is_symlink();
+                if is_up_to_date(&target, &meta, file, strategy)? {
+                    summary.unchanged += 1;
+                } else if ours {
+                    remove_entry(&target)?;
+                    place_file(&file.source, &target, strategy)?;
+                    summary.replaced.push(rel.clone());
+                } else {
+                    eprintln!(
+                        "warning: {rel} was not installed by the launcher, leaving it in place"
+                    );
+                    continue;
+                }
+                if ours {
+                    placed.insert(rel);
+                }
+            }
+        }
+    }
+
+    if let Some(parent) = state_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+    }
+    let data =
+        serde_json::to_string_pretty(&placed).context("failed to serialize datapack state")?;
+    fs::write(&state_path, data)
+        .with_context(|| format!("failed to write {}", state_path.display()))
+}
+
+/// Whether `name` is the settings file a shader loader keeps next to a pack.
+/// These are left in place so settings changed in-game survive.
+fn is_pack_settings(pack: &PlannedFile, name: &str) -> bool {
+    pack.dir == "shaderpacks" && name == settings_file_name(&pack.file_name)
+}
+
+/// Whether an existing instance file already provides the planned content.
+/// Symlinks and hard links only count under their own strategy, so switching
+/// strategies replaces them.
+fn is_up_to_date(
+    target: &Path,
+    meta: &fs::Metadata,
+    file: &PlannedFile,
+    strategy: LinkStrategy,
+) -> Result<bool> {
+    if meta.file_type().is_symlink() {
+        return Ok(strategy == LinkStrategy::Symlink
+            && fs::read_link(target).is_ok_and(|dest| dest == file.source));
+    }
+    if !meta.is_file() {
+        return Ok(false);
+    }
+    let source_meta = fs::metadata(&file.source)
+        .with_context(|| format!("failed to read {}", file.source.display()))?;
+    if meta.len() != source_meta.len() {
+        return Ok(false);
+    }
+    if same_file(meta, &source_meta) {
+        return Ok(strategy == LinkStrategy::Hardlink);
+    }
+    Ok(hash_file(target)? == file.hash)
+}
+
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &fs::Metadata, _b: &fs::Metadata) -> bool {
+    false
+}
+
+fn remove_entry(path: &Path) -> Result<()> {
+    let meta =
+        fs::symlink_metadata(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .with_context(|| format!("failed to remove {}", path.display()))
+}
+
+/// What was last done for an override file, kept in `.shard/overrides.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct AppliedOverride {
+    /// Hash of the override file that was last synced
+    source: String,
+    /// Hash of the instance file as the launcher left it; differs from
+    /// `source` once gameOptions are written over `options.txt`
+    placed: String,
+}
+
+fn load_override_state(
+    paths: &Paths,
+    profile_id: &str,
+) -> Result<BTreeMap<String, AppliedOverride>> {
+    let state_path = paths.instance_state_dir(profile_id).join("overrides.json");
+    if !state_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(&state_path)
+        .with_context(|| format!("failed to read {}", state_path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("failed to parse {}", state_path.display()))
+}
+
+fn save_override_state(
+    paths: &Paths,
+    profile_id: &str,
+    applied: &BTreeMap<String, AppliedOverride>,
+) -> Result<()> {
+    let state_path = paths.instance_state_dir(profile_id).join("overrides.json");
+    if let Some(parent) = state_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+    }
+    let data =
+        serde_json::to_string_pretty(applied).context("failed to serialize override state")?;
+    fs::write(&state_path, data)
+        .with_context(|| format!("failed to write {}", state_path.display()))
+}
+
+/// Copy override files into the instance, skipping those the instance already
+/// has as the launcher left them. Overrides win over changes made in the
+/// instance unless `keep_edits` is set, in which case changed files are left
+/// alone and reported in `overrides_kept`. Returns the saved override state.
+fn sync_overrides(
+    paths: &Paths,
+    profile_id: &str,
+    overrides: &BTreeMap<String, PathBuf>,
+    instance_dir: &Path,
+    keep_edits: bool,
+    summary: &mut MaterializeSummary,
+) -> Result<BTreeMap<String, AppliedOverride>> {
+    let mut applied = load_override_state(paths, profile_id)?;
+
+    for (rel, source) in overrides {
+        let hash = format!("sha256:{}", hash_file(source)?);
+        let previous = applied.get(rel);
+        let changed = previous.is_none_or(|entry| entry.source != hash);
+        let target = instance_dir.join(rel);
+        let mut placed = previous.map(|entry| entry.placed.clone());
+        let copy = if !target.exists() {
+            true
+        } else if !target.is_file() {
+            summary.overrides_kept.push(rel.clone());
+            false
+        } else {
+            let current = format!("sha256:{}", hash_file(&target)?);
+            if current == hash {
+                placed = Some(current);
+                false
+            } else if placed.as_ref() == Some(&current) {
+                changed
+            } else if keep_edits {
+                summary.overrides_kept.push(rel.clone());
+                false
+            } else {
+                true
+            }
+        };
+        if copy {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+            }
+            fs::copy(source, &target).with_context(|| format!("failed to copy override {rel}"))?;
+            summary.overrides_copied.push(rel.clone());
+            placed = Some(hash.clone());
+        }
+        let placed = placed.unwrap_or_else(|| hash.clone());
+        applied.insert(
+            rel.clone(),
+            AppliedOverride {
+                source: hash,
+                placed,
+            },
+        );
+    }
+    applied.retain(|rel, _| overrides.contains_key(rel));
+
+    save_override_state(paths, profile_id, &applied)?;
+    Ok(applied)
+}
+
+/// Place a store blob at `dst` using the given strategy. Links that cannot be
+/// created (no symlink permission, store on another filesystem, no reflink
+/// support) fall back to a plain copy.
+fn place_file(src: &Path, dst: &Path, strategy: LinkStrategy) -> Result<()> {
+    let linked = match strategy {
+        LinkStrategy::Symlink => symlink_file(src, dst),
+        LinkStrategy::Hardlink => fs::hard_link(src, dst),
+        LinkStrategy::Reflink => reflink_copy::reflink_or_copy(src, dst).map(|_| ()),
+        LinkStrategy::Copy => fs::copy(src, dst).map(|_| ()),
+    };
+    match linked {
+        Ok(()) => Ok(()),
+        Err(err) if strategy != LinkStrategy::Copy => {
+            fs::copy(src, dst).with_context(|| {
+                format!(
+                    "failed to copy {} to {} after {strategy} error: {err}",
+                    src.display(),
+                    dst.display()
+                )
+            })?;
+            Ok(())
+        }
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to copy {} to {}", src.display(), dst.display())),
+    }
+}
+
 #[cfg(unix)]
 fn symlink_file(src: &Path, dst: &Path) -> std::io::Result<()> {
     std::os::unix::fs::symlink(src, dst)
@@ -115,3 +840,194 @@ fn symlink_file(src: &Path, dst: &Path) -> std::io::Result<()> {
 fn symlink_file(src: &Path, dst: &Path) -> std::io::Result<()> {
     std::os::windows::fs::symlink_file(src, dst)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(dir: &str, file_name: &str, source: &Path) -> PlannedFile {
+        PlannedFile {
+            dir: dir.to_string(),
+            name: file_name.to_string(),
+            file_name: file_name.to_string(),
+            source: source.to_path_buf(),
+            hash: hash_file(source).unwrap(),
+        }
+    }
+
+    #[test]
+    fn syncs_managed_dir_incrementally() {
+        let dir = tempfile::tempdir().unwrap();
+        let instance = dir.path().join("instance");
+        let store = dir.path().join("store");
+        fs::create_dir_all(&store).unwrap();
+        let sodium = store.join("sodium");
+        let iris = store.join("iris");
+        fs::write(&sodium, b"sodium").unwrap();
+        fs::write(&iris, b"iris").unwrap();
+        fs::create_dir_all(instance.join("mods")).unwrap();
+        fs::write(instance.join("mods/stray.jar"), b"stray").unwrap();
+
+        let files = [
+            planned("mods", "sodium.jar", &sodium),
+            planned("mods", "iris.jar", &iris),
+        ];
+        let files: Vec<&PlannedFile> = files.iter().collect();
+        let mut summary = MaterializeSummary::default();
+        sync_managed_dir(&instance, "mods", &files, LinkStrategy::Copy, &mut summary).unwrap();
+        assert_eq!(summary.added, ["mods/sodium.jar", "mods/iris.jar"]);
+        assert_eq!(summary.removed, ["mods/stray.jar"]);
+        assert_eq!(fs::read(instance.join("mods/iris.jar")).unwrap(), b"iris");
+
+        fs::write(instance.join("mods/iris.jar"), b"tampered").unwrap();
+        let mut summary = MaterializeSummary::default();
+        sync_managed_dir(&instance, "mods", &files, LinkStrategy::Copy, &mut summary).unwrap();
+        assert!(summary.added.is_empty() && summary.removed.is_empty());
+        assert_eq!(summary.replaced, ["mods/iris.jar"]);
+        assert_eq!(summary.unchanged, 1);
+        assert_eq!(fs::read(instance.join("mods/iris.jar")).unwrap(), b"iris");
+    }
+
+    #[test]
+    fn overrides_win_over_in_game_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let instance = paths.instance_dir("a");
+        let options = dir.path().join("options.txt");
+        fs::write(&options, "fov:70\n").unwrap();
+        let overrides = BTreeMap::from([("options.txt".to_string(), options.clone())]);
+        let sync = || {
+            let mut summary = MaterializeSummary::default();
+            sync_overrides(&paths, "a", &overrides, &instance, false, &mut summary).unwrap();
+            summary
+        };
+
+        assert_eq!(sync().overrides_copied, ["options.txt"]);
+        assert!(sync().overrides_copied.is_empty());
+
+        fs::write(instance.join("options.txt"), "fov:90\n").unwrap();
+        assert_eq!(sync().overrides_copied, ["options.txt"]);
+        assert_eq!(
+            fs::read_to_string(instance.join("options.txt")).unwrap(),
+            "fov:70\n"
+        );
+    }
+
+    #[test]
+    fn restores_overrides_but_keeps_in_game_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let instance = paths.instance_dir("a");
+        let source_dir = dir.path().join("overrides");
+        fs::create_dir_all(source_dir.join("config")).unwrap();
+        let options = source_dir.join("options.txt");
+        let config = source_dir.join("config/sodium.json");
+        fs::write(&options, "fov:70\n").unwrap();
+        fs::write(&config, "{}").unwrap();
+        let overrides = BTreeMap::from([
+            ("options.txt".to_string(), options.clone()),
+            ("config/sodium.json".to_string(), config.clone()),
+        ]);
+        let sync = || {
+            let mut summary = MaterializeSummary::default();
+            sync_overrides(&paths, "a", &overrides, &instance, true, &mut summary).unwrap();
+            summary
+        };
+
+        assert_eq!(sync().overrides_copied.len(), 2);
+        assert!(sync().overrides_copied.is_empty());
+
+        // Deleted in the instance: restored even though the override is unchanged
+        fs::remove_file(instance.join("config/sodium.json")).unwrap();
+        assert_eq!(sync().overrides_copied, ["config/sodium.json"]);
+
+        // Edited in-game: left alone and reported, also once the override changes
+        fs::write(instance.join("options.txt"), "fov:90\n").unwrap();
+        let summary = sync();
+        assert!(summary.overrides_copied.is_empty());
+        assert_eq!(summary.overrides_kept, ["options.txt"]);
+        fs::write(&options, "fov:80\n").unwrap();
+        assert_eq!(sync().overrides_kept, ["options.txt"]);
+        assert_eq!(
+            fs::read_to_string(instance.join("options.txt")).unwrap(),
+            "fov:90\n"
+        );
+
+        // Untouched copies follow override changes
+        fs::write(&config, "{\"fog\":false}").unwrap();
+        assert_eq!(sync().overrides_copied, ["config/sodium.json"]);
+    }
+
+    #[test]
+    fn keeps_datapacks_the_launcher_did_not_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        paths.ensure().unwrap();
+        let pack = dir.path().join("pack.zip");
+        fs::write(&pack, b"pack").unwrap();
+        let stored =
+            crate::store::store_content(&paths, ContentKind::Datapack, &pack, None, None).unwrap();
+        let world_packs = paths.instance_saves("a").join("World/datapacks");
+        fs::create_dir_all(&world_packs).unwrap();
+        fs::write(world_packs.join("map.zip"), b"map").unwrap();
+
+        let mut profile: Profile = serde_json::from_value(serde_json::json!({
+            "id": "a",
+            "mcVersion": "1.21.4",
+            "datapacks": [{ "name": "pack", "hash": stored.hash }],
+            "datapackWorlds": ["World"]
+        }))
+        .unwrap();
+        let materialize = |profile: &Profile| {
+            materialize_instance(&paths, profile, &MaterializeOptions::default()).unwrap()
+        };
+
+        let summary = materialize(&profile);
+        let placed = format!("saves/World/datapacks/{}", stored.file_name);
+        assert_eq!(summary.added, [placed.as_str()]);
+        assert!(summary.removed.is_empty());
+        assert!(world_packs.join("map.zip").exists());
+
+        // Dropping the last datapack removes only the one the launcher placed
+        profile.datapacks.clear();
+        let summary = materialize(&profile);
+        assert_eq!(summary.removed, [placed.as_str()]);
+        assert!(!world_packs.join(&stored.file_name).exists());
+        assert_eq!(fs::read(world_packs.join("map.zip")).unwrap(), b"map");
+    }
+
+    #[test]
+    fn follows_options_override_under_game_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        paths.ensure().unwrap();
+        let profile: Profile = serde_json::from_value(serde_json::json!({
+            "id": "a",
+            "mcVersion": "1.21.4",
+            "gameOptions": { "renderDistance": "12" }
+        }))
+        .unwrap();
+        crate::profile::save_profile(&paths, &profile).unwrap();
+        let options = paths.profile_overrides("a").join(OPTIONS_FILE);
+        fs::create_dir_all(options.parent().unwrap()).unwrap();
+        fs::write(&options, "fov:70\n").unwrap();
+        let materialize =
+            || materialize_instance(&paths, &profile, &MaterializeOptions::default()).unwrap();
+
+        let summary = materialize();
+        assert_eq!(summary.overrides_copied, [OPTIONS_FILE]);
+        assert_eq!(summary.options_set, ["renderDistance"]);
+        assert!(materialize().is_empty());
+
+        // The instance file differs from the override only by gameOptions, so
+        // an override change is still applied
+        fs::write(&options, "fov:80\n").unwrap();
+        let summary = materialize();
+        assert_eq!(summary.overrides_copied, [OPTIONS_FILE]);
+        assert!(summary.overrides_kept.is_empty());
+        assert_eq!(
+            fs::read_to_string(paths.instance_dir("a").join(OPTIONS_FILE)).unwrap(),
+            "fov:80\nrenderDistance:12\n"
+        );
+    }
+}
@@ -1,20 +1,37 @@
 pub mod accounts;
 pub mod auth;
+pub mod backup;
 pub mod config;
 pub mod content_store;
 pub mod curseforge;
+pub mod diff;
+pub mod drift;
+pub mod fetch;
+pub mod inherit;
 pub mod instance;
 pub mod java;
 pub mod library;
+pub mod local;
+pub mod lock;
 pub mod logs;
+pub mod merge;
+pub mod migrate;
 pub mod minecraft;
 pub mod modpack;
 pub mod modrinth;
+pub mod nbt;
 pub mod ops;
+pub mod options;
 pub mod paths;
 pub mod profile;
+pub mod running;
+pub mod schema;
+pub mod servers;
+pub mod shaders;
 pub mod skin;
+pub mod snapshot;
 pub mod store;
 pub mod template;
 pub mod updates;
 pub mod util;
+pub mod worlds;
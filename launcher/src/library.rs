@@ -1,6 +1,6 @@
 //! Library management with SQLite database
 //!
-//! Tracks all content (mods, resourcepacks, shaderpacks, skins) with metadata,
+//! Tracks all content (mods, resourcepacks, shaderpacks, datapacks, skins) with metadata,
 //! tags, and profile relationships.
 
 use crate::paths::Paths;
@@ -18,6 +18,7 @@ pub enum LibraryContentType {
     Mod,
     ResourcePack,
     ShaderPack,
+    Datapack,
     Skin,
 }
 
@@ -29,6 +30,7 @@ impl LibraryContentType {
                 Some(Self::ResourcePack)
             }
             "shaderpack" | "shaderpacks" | "shader_pack" | "shader_packs" => Some(Self::ShaderPack),
+            "datapack" | "datapacks" | "data_pack" | "data_packs" => Some(Self::Datapack),
             "skin" | "skins" => Some(Self::Skin),
             _ => None,
         }
@@ -39,6 +41,7 @@ impl LibraryContentType {
             Self::Mod => "mod",
             Self::ResourcePack => "resourcepack",
             Self::ShaderPack => "shaderpack",
+            Self::Datapack => "datapack",
             Self::Skin => "skin",
         }
     }
@@ -48,6 +51,7 @@ impl LibraryContentType {
             Self::Mod => "Mod",
             Self::ResourcePack => "Resource Pack",
             Self::ShaderPack => "Shader Pack",
+            Self::Datapack => "Datapack",
             Self::Skin => "Skin",
         }
     }
@@ -58,6 +62,7 @@ impl LibraryContentType {
             ContentKind::Mod => Self::Mod,
             ContentKind::ResourcePack => Self::ResourcePack,
             ContentKind::ShaderPack => Self::ShaderPack,
+            ContentKind::Datapack => Self::Datapack,
             ContentKind::Skin => Self::Skin,
         }
     }
@@ -151,6 +156,8 @@ pub struct UnusedItemsSummary {
     pub mods: Vec<UnusedItem>,
     pub resourcepacks: Vec<UnusedItem>,
     pub shaderpacks: Vec<UnusedItem>,
+    #[serde(default)]
+    pub datapacks: Vec<UnusedItem>,
     pub skins: Vec<UnusedItem>,
     pub total_count: usize,
     pub total_bytes: u64,
@@ -163,6 +170,8 @@ pub struct LibraryStats {
     pub mods_count: u32,
     pub resourcepacks_count: u32,
     pub shaderpacks_count: u32,
+    #[serde(default)]
+    pub datapacks_count: u32,
     pub skins_count: u32,
     pub total_size: u64,
     pub tags_count: u32,
@@ -445,7 +454,8 @@ impl Library {
                 || item.source_platform.is_none()
                 || item.name.starts_with("mod-")
                 || item.name.starts_with("resourcepack-")
-                || item.name.starts_with("shaderpack-");
+                || item.name.starts_with("shaderpack-")
+                || item.name.starts_with("datapack-");
 
             if needs_update {
                 return Ok(Some(self.update_item_metadata(
@@ -879,7 +889,9 @@ impl Library {
             LibraryContentType::Mod => {
                 matches!(ext.as_deref(), Some("jar"))
             }
-            LibraryContentType::ResourcePack | LibraryContentType::ShaderPack => {
+            LibraryContentType::ResourcePack
+            | LibraryContentType::ShaderPack
+            | LibraryContentType::Datapack => {
                 matches!(ext.as_deref(), Some("zip") | Some("jar"))
             }
             LibraryContentType::Skin => {
@@ -898,6 +910,7 @@ impl Library {
             LibraryContentType::Mod => paths.store_mod_path(hash),
             LibraryContentType::ResourcePack => paths.store_resourcepack_path(hash),
             LibraryContentType::ShaderPack => paths.store_shaderpack_path(hash),
+            LibraryContentType::Datapack => paths.store_datapack_path(hash),
             LibraryContentType::Skin => paths.store_skin_path(hash),
         }
     }
@@ -928,6 +941,12 @@ impl Library {
             |row| row.get(0),
         )?;
 
+        let datapacks_count: u32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM library_items WHERE content_type = 'datapack'",
+            [],
+            |row| row.get(0),
+        )?;
+
         let skins_count: u32 = self.conn.query_row(
             "SELECT COUNT(*) FROM library_items WHERE content_type = 'skin'",
             [],
@@ -952,6 +971,7 @@ impl Library {
             mods_count,
             resourcepacks_count,
             shaderpacks_count,
+            datapacks_count,
             skins_count,
             total_size,
             tags_count,
@@ -967,6 +987,7 @@ impl Library {
             (&paths.store_mods, LibraryContentType::Mod),
             (&paths.store_resourcepacks, LibraryContentType::ResourcePack),
             (&paths.store_shaderpacks, LibraryContentType::ShaderPack),
+            (&paths.store_datapacks, LibraryContentType::Datapack),
             (&paths.store_skins, LibraryContentType::Skin),
         ] {
             if !store_dir.exists() {
@@ -1052,6 +1073,7 @@ impl Library {
                 LibraryContentType::Mod => summary.mods.push(item),
                 LibraryContentType::ResourcePack => summary.resourcepacks.push(item),
                 LibraryContentType::ShaderPack => summary.shaderpacks.push(item),
+                LibraryContentType::Datapack => summary.datapacks.push(item),
                 LibraryContentType::Skin => summary.skins.push(item),
             }
         }
@@ -1075,6 +1097,7 @@ impl Library {
             unused.mods.into_iter()
                 .chain(unused.resourcepacks)
                 .chain(unused.shaderpacks)
+                .chain(unused.datapacks)
                 .chain(unused.skins)
                 .collect()
         } else {
@@ -1084,6 +1107,7 @@ impl Library {
                     LibraryContentType::Mod => items.extend(unused.mods.clone()),
                     LibraryContentType::ResourcePack => items.extend(unused.resourcepacks.clone()),
                     LibraryContentType::ShaderPack => items.extend(unused.shaderpacks.clone()),
+                    LibraryContentType::Datapack => items.extend(unused.datapacks.clone()),
                     LibraryContentType::Skin => items.extend(unused.skins.clone()),
                 }
             }
@@ -0,0 +1,180 @@
+//! Machine-local profile settings.
+//!
+//! Memory, the Java path and the window size depend on the machine, not on the
+//! modpack, so they can be kept out of the shared `profile.json`. `Config.local` applies to every
+//! profile on this machine and `profiles/<id>/profile.local.json` to a single
+//! profile; both are layered over the profile when it is prepared for launch,
+//! the profile's own overlay last. Neither is ever written into `profile.json`,
+//! so `profile.local.json` belongs in `.gitignore`.
+
+use crate::config::load_config;
+use crate::instance::LinkStrategy;
+use crate::paths::Paths;
+use crate::profile::Profile;
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Settings that replace the profile's values on this machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LocalOverlay {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub java: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+    /// JVM args appended to the profile's
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_strategy: Option<LinkStrategy>,
+    /// Game window width; applies when height is set too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// Game window height; applies when width is set too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+impl LocalOverlay {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocalSource {
+    /// `local` in config.json
+    Machine,
+    /// profile.local.json
+    Profile,
+}
+
+impl LocalSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            LocalSource::Machine => "config.json",
+            LocalSource::Profile => "profile.local.json",
+        }
+    }
+}
+
+/// A profile value that was set by a local overlay
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalValue {
+    /// e.g. "runtime.memory"
+    pub field: String,
+    pub value: String,
+    pub source: LocalSource,
+}
+
+pub fn load_local_overlay(paths: &Paths, id: &str) -> Result<Option<LocalOverlay>> {
+    let path = paths.profile_local(id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read local overlay: {}", path.display()))?;
+    let overlay = serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse local overlay: {}", path.display()))?;
+    Ok(Some(overlay))
+}
+
+/// Apply the machine-wide and the profile's local overlay. Returns the values
+/// that ended up coming from an overlay.
+pub fn apply_local(paths: &Paths, profile: &mut Profile) -> Result<Vec<LocalValue>> {
+    let mut applied = Vec::new();
+    let machine = load_config(paths)?.local;
+    apply_overlay(profile, &machine, LocalSource::Machine, &mut applied);
+    if let Some(overlay) = load_local_overlay(paths, &profile.id)? {
+        apply_overlay(profile, &overlay, LocalSource::Profile, &mut applied);
+    }
+    Ok(applied)
+}
+
+fn apply_overlay(
+    profile: &mut Profile,
+    overlay: &LocalOverlay,
+    source: LocalSource,
+    applied: &mut Vec<LocalValue>,
+) {
+    let mut set = |field: &str, value: String, replaces: bool| {
+        if replaces {
+            applied.retain(|v| v.field != field);
+        }
+        applied.push(LocalValue {
+            field: field.to_string(),
+            value,
+            source,
+        });
+    };
+    if let Some(java) = &overlay.java {
+        profile.runtime.java = Some(java.clone());
+        set("runtime.java", java.clone(), true);
+    }
+    if let Some(memory) = &overlay.memory {
+        profile.runtime.memory = Some(memory.clone());
+        set("runtime.memory", memory.clone(), true);
+    }
+    if !overlay.args.is_empty() {
+        profile.runtime.args.extend(overlay.args.iter().cloned());
+        set("runtime.args", overlay.args.join(" "), false);
+    }
+    if let Some(strategy) = overlay.link_strategy {
+        profile.link_strategy = Some(strategy);
+        set("linkStrategy", strategy.to_string(), true);
+    }
+    if let Some(width) = overlay.width {
+        profile.runtime.width = Some(width);
+        set("runtime.width", width.to_string(), true);
+    }
+    if let Some(height) = overlay.height {
+        profile.runtime.height = Some(height);
+        set("runtime.height", height.to_string(), true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn profile_overlay_wins_over_machine() {
+        let mut profile: Profile = serde_json::from_value(json!({
+            "id": "test",
+            "mcVersion": "1.21.4",
+            "runtime": { "memory": "4G", "args": ["-Da=1"] }
+        }))
+        .unwrap();
+        let machine = LocalOverlay {
+            java: Some("/usr/bin/java".to_string()),
+            memory: Some("2G".to_string()),
+            width: Some(2560),
+            height: Some(1440),
+            ..Default::default()
+        };
+        let local = LocalOverlay {
+            memory: Some("8G".to_string()),
+            args: vec!["-Db=2".to_string()],
+            ..Default::default()
+        };
+
+        let mut applied = Vec::new();
+        apply_overlay(&mut profile, &machine, LocalSource::Machine, &mut applied);
+        apply_overlay(&mut profile, &local, LocalSource::Profile, &mut applied);
+
+        assert_eq!(profile.runtime.java.as_deref(), Some("/usr/bin/java"));
+        assert_eq!(profile.runtime.memory.as_deref(), Some("8G"));
+        assert_eq!(profile.runtime.args, ["-Da=1", "-Db=2"]);
+        assert_eq!(profile.runtime.width, Some(2560));
+        assert_eq!(profile.runtime.height, Some(1440));
+        let memory: Vec<_> = applied
+            .iter()
+            .filter(|v| v.field == "runtime.memory")
+            .map(|v| v.source)
+            .collect();
+        assert_eq!(memory, [LocalSource::Profile]);
+    }
+}
@@ -0,0 +1,270 @@
+//! Profile lockfiles.
+//!
+//! `profile.json` records what a profile asks for (`"latest"` loaders, template
+//! versions), while `profile.lock.json` records what that resolved to: the exact
+//! loader version, the version JSON that was launched, library and asset index
+//! hashes and the hash of every content item. A launch from a committed lockfile
+//! uses the pinned versions instead of resolving "latest" again.
+
+use crate::paths::Paths;
+use crate::profile::{ContentRef, Loader, Profile};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProfileLock {
+    /// Minecraft version and loader as declared in profile.json when the lock was written
+    pub declared: LockedIntent,
+    /// Resolved Minecraft version
+    #[serde(rename = "mcVersion")]
+    pub mc_version: String,
+    /// Loader with its exact resolved version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<Loader>,
+    /// Id of the version JSON used for launching (e.g. fabric-loader-0.16.9-1.21.4)
+    pub version_id: String,
+    /// Hash of the version JSON file ("sha256:<hex>")
+    pub version_json: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_index: Option<LockedAssetIndex>,
+    #[serde(default)]
+    pub libraries: Vec<LockedLibrary>,
+    #[serde(default)]
+    pub content: LockedContent,
+    /// Override file path (relative to overrides/) -> "sha256:<hex>"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockedIntent {
+    #[serde(rename = "mcVersion")]
+    pub mc_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<Loader>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockedAssetIndex {
+    pub id: String,
+    pub sha1: String,
+}
+
+/// A library from the merged version JSON. All libraries are recorded regardless
+/// of OS rules so the same lockfile is valid on every platform.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockedLibrary {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<String>,
+    /// Native classifier -> sha1
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub natives: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LockedContent {
+    #[serde(default)]
+    pub mods: Vec<LockedContentRef>,
+    #[serde(default)]
+    pub resourcepacks: Vec<LockedContentRef>,
+    #[serde(default)]
+    pub shaderpacks: Vec<LockedContentRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub datapacks: Vec<LockedContentRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockedContentRef {
+    pub name: String,
+    pub hash: String,
+}
+
+impl LockedIntent {
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            mc_version: profile.mc_version.clone(),
+            loader: profile.loader.clone(),
+        }
+    }
+}
+
+impl LockedContent {
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            mods: lock_content_list(&profile.mods),
+            resourcepacks: lock_content_list(&profile.resourcepacks),
+            shaderpacks: lock_content_list(&profile.shaderpacks),
+            datapacks: lock_content_list(&profile.datapacks),
+        }
+    }
+}
+
+impl ProfileLock {
+    /// Whether the lock was produced from the same declared version and loader
+    /// the profile has now. A stale lock must not be used to pin versions.
+    pub fn matches_intent(&self, profile: &Profile) -> bool {
+        self.declared == LockedIntent::from_profile(profile)
+    }
+
+    /// Describe content whose hash differs between the profile and the lock.
+    pub fn content_drift(&self, profile: &Profile) -> Vec<String> {
+        let current = LockedContent::from_profile(profile);
+        let mut drift = Vec::new();
+        diff_locked_list("mod", &self.content.mods, &current.mods, &mut drift);
+        diff_locked_list(
+            "resourcepack",
+            &self.content.resourcepacks,
+            &current.resourcepacks,
+            &mut drift,
+        );
+        diff_locked_list(
+            "shaderpack",
+            &self.content.shaderpacks,
+            &current.shaderpacks,
+            &mut drift,
+        );
+        diff_locked_list(
+            "datapack",
+            &self.content.datapacks,
+            &current.datapacks,
+            &mut drift,
+        );
+        drift
+    }
+}
+
+fn lock_content_list(items: &[ContentRef]) -> Vec<LockedContentRef> {
+    items
+        .iter()
+        .map(|item| LockedContentRef {
+            name: item.name.clone(),
+            hash: item.hash.clone(),
+        })
+        .collect()
+}
+
+fn diff_locked_list(
+    label: &str,
+    locked: &[LockedContentRef],
+    current: &[LockedContentRef],
+    out: &mut Vec<String>,
+) {
+    for item in current {
+        match locked.iter().find(|l| l.name == item.name) {
+            None => out.push(format!("{label} {} is not in the lockfile", item.name)),
+            Some(l) if l.hash != item.hash => out.push(format!(
+                "{label} {} changed ({} -> {})",
+                item.name, l.hash, item.hash
+            )),
+            Some(_) => {}
+        }
+    }
+    for item in locked {
+        if !current.iter().any(|c| c.name == item.name) {
+            out.push(format!("{label} {} was removed from the profile", item.name));
+        }
+    }
+}
+
+pub fn load_lock(paths: &Paths, id: &str) -> Result<Option<ProfileLock>> {
+    let path = paths.profile_lock(id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read lockfile: {}", path.display()))?;
+    let lock: ProfileLock = serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse lockfile: {}", path.display()))?;
+    Ok(Some(lock))
+}
+
+pub fn save_lock(paths: &Paths, id: &str, lock: &ProfileLock) -> Result<()> {
+    let path = paths.profile_lock(id);
+    let data = serde_json::to_string_pretty(lock).context("failed to serialize lockfile")?;
+    fs::write(&path, data)
+        .with_context(|| format!("failed to write lockfile: {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn profile(value: serde_json::Value) -> Profile {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn lock_for(profile: &Profile) -> ProfileLock {
+        ProfileLock {
+            declared: LockedIntent::from_profile(profile),
+            mc_version: "1.21.4".to_string(),
+            loader: Some(Loader {
+                loader_type: "fabric".to_string(),
+                version: "0.16.9".to_string(),
+            }),
+            version_id: "fabric-loader-0.16.9-1.21.4".to_string(),
+            version_json: "sha256:abc".to_string(),
+            asset_index: Some(LockedAssetIndex {
+                id: "19".to_string(),
+                sha1: "def".to_string(),
+            }),
+            libraries: vec![LockedLibrary {
+                name: "net.fabricmc:fabric-loader:0.16.9".to_string(),
+                sha1: Some("123".to_string()),
+                natives: BTreeMap::new(),
+            }],
+            content: LockedContent::from_profile(profile),
+            overrides: BTreeMap::from([("options.txt".to_string(), "sha256:456".to_string())]),
+        }
+    }
+
+    #[test]
+    fn matches_intent_only_for_the_declared_versions() {
+        let declared = profile(json!({
+            "id": "a",
+            "mcVersion": "1.21.4",
+            "loader": { "type": "fabric", "version": "latest" },
+            "mods": [{ "name": "sodium", "hash": "sha256:a" }]
+        }));
+        let lock = lock_for(&declared);
+        assert!(lock.matches_intent(&declared));
+
+        // Content changes do not make the pinned versions stale
+        let mut with_mod = declared.clone();
+        with_mod.mods[0].hash = "sha256:b".to_string();
+        assert!(lock.matches_intent(&with_mod));
+        assert_eq!(
+            lock.content_drift(&with_mod),
+            ["mod sodium changed (sha256:a -> sha256:b)"]
+        );
+
+        let mut newer = declared.clone();
+        newer.mc_version = "1.21.5".to_string();
+        assert!(!lock.matches_intent(&newer));
+
+        let mut vanilla = declared;
+        vanilla.loader = None;
+        assert!(!lock.matches_intent(&vanilla));
+    }
+
+    #[test]
+    fn lock_round_trips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        fs::create_dir_all(paths.profile_dir("a")).unwrap();
+        assert_eq!(load_lock(&paths, "a").unwrap(), None);
+
+        let declared = profile(json!({
+            "id": "a",
+            "mcVersion": "1.21.4",
+            "datapacks": [{ "name": "terralith", "hash": "sha256:c" }]
+        }));
+        let lock = lock_for(&declared);
+        save_lock(&paths, "a", &lock).unwrap();
+        assert_eq!(load_lock(&paths, "a").unwrap(), Some(lock));
+    }
+}
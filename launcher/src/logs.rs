@@ -1,17 +1,32 @@
 //! Log management and viewing for Minecraft instances
 //!
-//! Handles reading logs from running and past game sessions.
+//! Handles reading logs from running and past game sessions. Besides the
+//! game's own logs in the instance, the launcher keeps what the game printed
+//! to stdout/stderr for each launch in `logs/<profile>/session-<id>.log`, next
+//! to a `session-<id>.json` record of how the session went. Only the newest
+//! sessions are kept, `session_log_limit` in the config (50 by default).
 
+use crate::config::load_config;
 use crate::paths::Paths;
-use anyhow::{Context, Result};
+use crate::running::running_instances;
+use crate::util::now_epoch_secs;
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Child;
 use std::sync::mpsc::{self, Receiver, Sender};
-use std::thread;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
+/// Prefix of session log file names
+const SESSION_PREFIX: &str = "session-";
+
+/// Session logs kept per profile unless the config says otherwise
+pub const DEFAULT_SESSION_LOG_LIMIT: usize = 50;
+
 /// Log entry parsed from Minecraft log
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
@@ -70,6 +85,34 @@ pub struct LogFile {
     pub modified: u64,
     /// Whether this is the current/latest log
     pub is_current: bool,
+    /// For a launcher session log, the session it captured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<GameSession>,
+}
+
+/// A launch whose output was captured into a session log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSession {
+    pub profile_id: String,
+    /// Seconds since the Unix epoch
+    pub started: u64,
+    /// Unset while the game is running or if the launcher died first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended: Option<u64>,
+    /// Unset if the game was killed by a signal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Game process, recorded once it has started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// Java command line, with the access token redacted
+    pub command: Vec<String>,
+}
+
+impl GameSession {
+    pub fn duration(&self) -> Option<u64> {
+        self.ended.map(|ended| ended.saturating_sub(self.started))
+    }
 }
 
 /// Log session representing a game run
@@ -98,6 +141,230 @@ impl Paths {
     pub fn instance_crash_reports(&self, profile_id: &str) -> PathBuf {
         self.instance_dir(profile_id).join("crash-reports")
     }
+
+    /// Get the directory of launcher session logs for a profile
+    pub fn session_logs_dir(&self, profile_id: &str) -> PathBuf {
+        self.logs.join(profile_id)
+    }
+}
+
+/// Game output being written to a session log
+pub struct SessionLog {
+    log_path: PathBuf,
+    record_path: PathBuf,
+    file: Arc<Mutex<File>>,
+    readers: Vec<JoinHandle<()>>,
+    session: GameSession,
+}
+
+impl SessionLog {
+    /// Create the log and its record for a launch of `command`, pruning the
+    /// profile's oldest session logs to make room.
+    pub fn start(paths: &Paths, profile_id: &str, command: Vec<String>) -> Result<Self> {
+        let dir = paths.session_logs_dir(profile_id);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create dir: {}", dir.display()))?;
+        let limit = load_config(paths)
+            .ok()
+            .and_then(|config| config.session_log_limit)
+            .unwrap_or(DEFAULT_SESSION_LOG_LIMIT);
+        if let Err(err) = prune_session_logs(paths, profile_id, limit.saturating_sub(1)) {
+            eprintln!("warning: failed to prune session logs: {err:#}");
+        }
+        let started = now_epoch_secs();
+        let mut id = started.to_string();
+        let mut idx = 1;
+        while dir.join(format!("{SESSION_PREFIX}{id}.log")).exists() {
+            id = format!("{started}-{idx}");
+            idx += 1;
+        }
+        let log_path = dir.join(format!("{SESSION_PREFIX}{id}.log"));
+        let file = File::create(&log_path)
+            .with_context(|| format!("failed to create {}", log_path.display()))?;
+        let log = Self {
+            record_path: log_path.with_extension("json"),
+            log_path,
+            file: Arc::new(Mutex::new(file)),
+            readers: Vec::new(),
+            session: GameSession {
+                profile_id: profile_id.to_string(),
+                started,
+                ended: None,
+                exit_code: None,
+                pid: None,
+                command,
+            },
+        };
+        log.write_record()?;
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.log_path
+    }
+
+    /// Copy the child's piped stdout and stderr into the log, and also to the
+    /// launcher's own stdout and stderr if `echo` is set.
+    pub fn capture(&mut self, child: &mut Child, echo: bool) {
+        self.session.pid = Some(child.id());
+        if let Err(err) = self.write_record() {
+            eprintln!("warning: failed to record session: {err:#}");
+        }
+        if let Some(stdout) = child.stdout.take() {
+            let file = Arc::clone(&self.file);
+            self.readers.push(thread::spawn(move || {
+                copy_lines(stdout, &file, echo.then(std::io::stdout));
+            }));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let file = Arc::clone(&self.file);
+            self.readers.push(thread::spawn(move || {
+                copy_lines(stderr, &file, echo.then(std::io::stderr));
+            }));
+        }
+    }
+
+    /// Record how the session ended once the game has exited.
+    pub fn finish(mut self, exit_code: Option<i32>) -> Result<GameSession> {
+        for reader in self.readers.drain(..) {
+            let _ = reader.join();
+        }
+        self.session.ended = Some(now_epoch_secs());
+        self.session.exit_code = exit_code;
+        self.write_record()?;
+        Ok(self.session)
+    }
+
+    fn write_record(&self) -> Result<()> {
+        let data = serde_json::to_string_pretty(&self.session)
+            .context("failed to serialize session record")?;
+        fs::write(&self.record_path, data)
+            .with_context(|| format!("failed to write {}", self.record_path.display()))
+    }
+}
+
+/// Copy lines from the game to the log (and `echo`) until the stream closes.
+fn copy_lines(source: impl Read, file: &Mutex<File>, mut echo: Option<impl Write>) {
+    let mut reader = BufReader::new(source);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if let Ok(mut file) = file.lock() {
+            let _ = file.write_all(&line);
+        }
+        if let Some(out) = echo.as_mut() {
+            let _ = out.write_all(&line);
+            let _ = out.flush();
+        }
+    }
+}
+
+/// Session logs of a profile, newest first, each with its record if it has
+/// one. A session is current only while its game is in the running registry,
+/// so one whose launcher died before the game exited is not.
+pub fn list_session_logs(paths: &Paths, profile_id: &str) -> Result<Vec<LogFile>> {
+    let dir = paths.session_logs_dir(profile_id);
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    let running: Vec<u32> = running_instances(paths, profile_id)?
+        .iter()
+        .map(|instance| instance.pid)
+        .collect();
+    for entry in
+        fs::read_dir(&dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let path = entry.context("failed to read dir entry")?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !name.starts_with(SESSION_PREFIX) || !name.ends_with(".log") {
+            continue;
+        }
+        let name = name.to_string();
+        let metadata = fs::metadata(&path).ok();
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let modified = metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let session: Option<GameSession> = fs::read_to_string(path.with_extension("json"))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok());
+        files.push(LogFile {
+            name,
+            path,
+            size,
+            modified,
+            is_current: session.as_ref().is_some_and(|s| {
+                s.ended.is_none() && s.pid.is_some_and(|pid| running.contains(&pid))
+            }),
+            session,
+        });
+    }
+    files.sort_by_key(|file| std::cmp::Reverse(session_order(&file.name)));
+    Ok(files)
+}
+
+/// Session log capturing the running game with process `pid`, if any.
+pub fn active_session_log(paths: &Paths, profile_id: &str, pid: u32) -> Result<Option<PathBuf>> {
+    Ok(list_session_logs(paths, profile_id)?
+        .into_iter()
+        .find(|file| file.is_current && file.session.as_ref().is_some_and(|s| s.pid == Some(pid)))
+        .map(|file| file.path))
+}
+
+/// Start time and collision index of a session from its log name
+/// (`session-<started>[-<idx>].log`), which order sessions by launch.
+fn session_order(name: &str) -> (u64, u64) {
+    let id = name
+        .strip_prefix(SESSION_PREFIX)
+        .and_then(|rest| rest.strip_suffix(".log"))
+        .unwrap_or_default();
+    let (started, idx) = id.split_once('-').unwrap_or((id, "0"));
+    (started.parse().unwrap_or(0), idx.parse().unwrap_or(0))
+}
+
+/// Remove all but the newest `keep` session logs of a profile, with their
+/// records. Current sessions are never removed. Returns how many were removed.
+pub fn prune_session_logs(paths: &Paths, profile_id: &str, keep: usize) -> Result<usize> {
+    let mut removed = 0;
+    for file in list_session_logs(paths, profile_id)?
+        .into_iter()
+        .skip(keep)
+        .filter(|file| !file.is_current)
+    {
+        fs::remove_file(&file.path)
+            .with_context(|| format!("failed to remove {}", file.path.display()))?;
+        let record = file.path.with_extension("json");
+        if record.exists() {
+            fs::remove_file(&record)
+                .with_context(|| format!("failed to remove {}", record.display()))?;
+        }
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Path of a log file of a profile by name: a session log from the launcher
+/// or a file in the instance's `logs/`.
+pub fn log_file_path(paths: &Paths, profile_id: &str, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+        bail!("invalid log file name: {name}");
+    }
+    if name.starts_with(SESSION_PREFIX) {
+        let path = paths.session_logs_dir(profile_id).join(name);
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+    Ok(paths.instance_logs_dir(profile_id).join(name))
 }
 
 /// Parse a single log line into a LogEntry
@@ -192,7 +459,7 @@ pub fn read_log_tail(path: &PathBuf, lines: usize) -> Result<Vec<LogEntry>> {
 /// List all log files for a profile
 pub fn list_log_files(paths: &Paths, profile_id: &str) -> Result<Vec<LogFile>> {
     let logs_dir = paths.instance_logs_dir(profile_id);
-    let mut files = Vec::new();
+    let mut files = list_session_logs(paths, profile_id)?;
 
     if !logs_dir.exists() {
         return Ok(files);
@@ -226,6 +493,7 @@ pub fn list_log_files(paths: &Paths, profile_id: &str) -> Result<Vec<LogFile>> {
                 size,
                 modified,
                 is_current,
+                session: None,
             });
         }
     }
@@ -271,6 +539,7 @@ pub fn list_crash_reports(paths: &Paths, profile_id: &str) -> Result<Vec<LogFile
                 size,
                 modified,
                 is_current: false,
+                session: None,
             });
         }
     }
@@ -439,3 +708,46 @@ pub fn format_entry(entry: &LogEntry, colored: bool) -> String {
         entry.message.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn keeps_the_newest_sessions() {
+        let dir = tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let log = SessionLog::start(&paths, "demo", vec!["java".to_string()]).unwrap();
+            ids.push(log.path().file_name().unwrap().to_owned());
+            log.finish(Some(0)).unwrap();
+        }
+
+        assert_eq!(prune_session_logs(&paths, "demo", 2).unwrap(), 1);
+        let left: Vec<_> = list_session_logs(&paths, "demo")
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path.file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(left, [ids[2].clone(), ids[1].clone()]);
+        let oldest = paths.session_logs_dir("demo").join(&ids[0]);
+        assert!(!oldest.with_extension("json").exists());
+    }
+
+    #[test]
+    fn unfinished_session_without_a_running_game_is_not_current() {
+        let dir = tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let mut log = SessionLog::start(&paths, "demo", vec!["java".to_string()]).unwrap();
+        // As if the launcher died while the game ran
+        log.session.pid = Some(std::process::id());
+        log.write_record().unwrap();
+
+        let files = list_session_logs(&paths, "demo").unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].session.as_ref().unwrap().ended.is_none());
+        assert!(!files[0].is_current);
+    }
+}
@@ -6,24 +6,43 @@ use semver::Version;
 use serde::Deserialize;
 use shard::accounts::{load_accounts, remove_account, save_accounts, set_active};
 use shard::auth::request_device_code;
+use shard::backup::{create_backup, list_backups, prune_backups, restore_backup};
 use shard::config::{load_config, save_config};
 use shard::content_store::{ContentStore, ContentType, Platform, SearchOptions};
+use shard::diff::{DiffSide, diff_sides};
+use shard::drift::{capture_drift, instance_drift};
+use shard::fetch::fetch_profile_content;
+use shard::inherit::resolve_profile;
+use shard::instance::{LinkStrategy, plan_instance};
 use shard::library::{
     Library, LibraryContentType, LibraryFilter, LibraryItemInput,
 };
+use shard::local::apply_local;
 use shard::logs::{
-    filter_by_level, format_entry, list_crash_reports, list_log_files, read_log_file,
-    read_log_tail, search_logs, watch_log, LogLevel,
+    active_session_log, filter_by_level, format_entry, list_crash_reports, list_log_files,
+    log_file_path, read_log_file, read_log_tail, search_logs, watch_log, LogLevel, LogWatcher,
 };
-use shard::minecraft::{launch, prepare};
+use shard::lock::save_lock;
+use shard::migrate::{
+    DocumentKind, TEMPLATE_SCHEMA_VERSION, migrate_all, parse_document, pending_migrations,
+};
+use shard::minecraft::{LaunchOptions, QuickPlay, launch, lock_profile, prepare};
+use shard::merge::merge_profiles;
 use shard::modpack::import_mrpack;
 use shard::ops::{finish_device_code_flow, parse_loader, resolve_input, resolve_launch_account};
+use shard::options::validate_option;
 use shard::paths::Paths;
 use shard::profile::{
-    ContentRef, Loader, Runtime, clone_profile, create_profile, delete_profile, diff_profiles,
-    list_profiles, load_profile, remove_mod, remove_resourcepack, remove_shaderpack, rename_profile,
-    save_profile, upsert_mod, upsert_resourcepack, upsert_shaderpack,
+    ContentRef, IssueSeverity, Loader, ResourcePackPolicy, Runtime, Server, ShaderLoader, check_profile,
+    clone_profile, create_profile, delete_profile, is_valid_world_name, list_profiles,
+    load_profile, move_resourcepack, read_profile_file, remove_datapack, remove_mod, remove_resourcepack, remove_server, remove_shaderpack,
+    rename_profile, save_profile, upsert_datapack, upsert_mod, upsert_resourcepack, upsert_server, upsert_shaderpack,
 };
+use shard::running::{kill_instances, list_running, running_instances};
+use shard::schema::{SchemaKind, json_schema};
+use shard::servers::{list_instance_servers, store_server_icon};
+use shard::shaders::{resolve_pack_settings, save_pack_settings};
+use shard::snapshot::{list_snapshots, rollback_profile};
 use shard::skin::{
     get_active_cape, get_active_skin, get_avatar_url, get_body_url, get_profile as get_mc_profile,
     get_skin_url, hide_cape, reset_skin, set_cape, set_skin_url, upload_skin, SkinVariant,
@@ -33,8 +52,11 @@ use shard::template::{
     delete_template, init_builtin_templates, list_templates, load_template, save_template,
     ContentSource, Template, TemplateLoader, TemplateRuntime,
 };
+use shard::util::now_epoch_secs;
+use shard::worlds::{copy_world, export_world_zip, import_world_zip, list_worlds};
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -86,6 +108,26 @@ enum Command {
         #[command(subcommand)]
         command: PackCommand,
     },
+    /// Datapack management
+    Datapack {
+        #[command(subcommand)]
+        command: PackCommand,
+    },
+    /// Multiplayer servers in a profile's server list
+    Server {
+        #[command(subcommand)]
+        command: ServerCommand,
+    },
+    /// Worlds in a profile's instance
+    World {
+        #[command(subcommand)]
+        command: WorldCommand,
+    },
+    /// Deduplicated world backups
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommand,
+    },
     /// Account management
     Account {
         #[command(subcommand)]
@@ -133,6 +175,52 @@ enum Command {
         account: Option<String>,
         #[arg(long)]
         prepare_only: bool,
+        /// Fail if any content, override file or library is missing or does not match the lockfile
+        #[arg(long)]
+        frozen: bool,
+        /// Join this server (host or host:port) once the game starts
+        #[arg(long, group = "quick_play")]
+        server: Option<String>,
+        /// Open this world (folder in saves/) once the game starts
+        #[arg(long, group = "quick_play")]
+        world: Option<String>,
+        /// Join this realm (by ID) once the game starts
+        #[arg(long, group = "quick_play")]
+        realm: Option<String>,
+        /// Window width (overrides runtime.width)
+        #[arg(long)]
+        width: Option<u32>,
+        /// Window height (overrides runtime.height)
+        #[arg(long)]
+        height: Option<u32>,
+        /// Start in fullscreen
+        #[arg(long)]
+        fullscreen: bool,
+        /// Launch in demo mode
+        #[arg(long)]
+        demo: bool,
+        /// Launch even if the profile is already running
+        #[arg(long)]
+        force: bool,
+    },
+    /// List running game instances
+    Ps,
+    /// Stop a profile's running game
+    Kill {
+        profile: String,
+        /// Kill the process instead of asking the game to close
+        #[arg(long)]
+        force: bool,
+    },
+    /// Follow a running game's output until it exits
+    Attach { profile: String },
+    /// Print the JSON Schema for profile, template or config files
+    Schema { kind: SchemaKind },
+    /// Upgrade profiles, templates, accounts and config to the current schema
+    Migrate {
+        /// Only report which files would be rewritten; fails if there are any
+        #[arg(long)]
+        check: bool,
     },
 }
 
@@ -164,10 +252,90 @@ enum ProfileCommand {
         /// New profile ID
         new_id: String,
     },
-    /// Diff two profiles by mod names
-    Diff { a: String, b: String },
+    /// Diff two profiles: versions, loader, runtime, content and overrides
+    Diff {
+        /// Profile ID, profile directory or profile.json file
+        a: String,
+        /// Profile ID, profile directory or profile.json file
+        b: String,
+        /// Print the diff as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Print a profile manifest
-    Show { id: String },
+    Show {
+        id: String,
+        /// Print the profile as launched: its `extends` chain flattened and
+        /// local overlays applied
+        #[arg(long)]
+        resolved: bool,
+    },
+    /// Resolve versions and write profile.lock.json
+    Lock { id: String },
+    /// Download content missing from the local store
+    Fetch { id: String },
+    /// Validate a profile; exits non-zero if any error is found
+    Check {
+        id: String,
+        /// Print issues as JSON
+        #[arg(long)]
+        json: bool,
+        /// Skip checks that need the network (Mojang manifest, platform versions)
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Three-way merge of profile.json files, for use as a git merge driver:
+    /// `shard profile merge-driver %O %A %B`
+    MergeDriver {
+        /// Common ancestor (%O)
+        base: PathBuf,
+        /// Our version (%A); the merge result is written here
+        ours: PathBuf,
+        /// Their version (%B)
+        theirs: PathBuf,
+    },
+    /// List saved snapshots of a profile
+    History { id: String },
+    /// Restore a profile and its overrides from a snapshot
+    Rollback { id: String, snapshot: String },
+    /// Show files in the instance that differ from the profile
+    Status { id: String },
+    /// Import files changed in the instance back into the profile
+    Capture {
+        id: String,
+        /// Capture everything without asking
+        #[arg(long)]
+        all: bool,
+    },
+    /// List, get or set the options.txt values a profile manages
+    Options {
+        id: String,
+        /// Option key, e.g. renderDistance; omit to list all
+        key: Option<String>,
+        /// New value; omit to print the current one
+        value: Option<String>,
+        /// Stop managing the key
+        #[arg(long, conflicts_with = "value")]
+        unset: bool,
+    },
+    /// Show or set the shader pack the shader loader selects
+    Shader {
+        id: String,
+        /// Shader pack name or hash; omit to show the current one
+        pack: Option<String>,
+        /// Stop selecting a shader pack
+        #[arg(long, conflicts_with = "pack")]
+        clear: bool,
+        /// Store the pack's settings from the instance in the profile
+        #[arg(long, conflicts_with = "clear")]
+        save_settings: bool,
+    },
+    /// Set how content is placed into the instance (symlink, hardlink, reflink, copy)
+    SetLinkStrategy {
+        id: String,
+        /// Strategy to use; omit to follow the global config
+        strategy: Option<LinkStrategy>,
+    },
     /// Delete a profile
     Delete { id: String },
     /// List all profiles
@@ -204,6 +372,21 @@ enum PackCommand {
     },
     /// Remove a pack by name or hash from a profile
     Remove { profile: String, target: String },
+    /// Move a resource pack to a position in the load order (1 = top)
+    Move {
+        profile: String,
+        target: String,
+        position: usize,
+    },
+    /// Show or set the worlds datapacks are installed into
+    Target {
+        profile: String,
+        /// World folder names in saves/
+        worlds: Vec<String>,
+        /// Use the global datapack folder of a mod like Paxi instead of worlds
+        #[arg(long, conflicts_with = "worlds")]
+        global: bool,
+    },
     /// List packs in a profile
     List { profile: String },
 }
@@ -220,6 +403,102 @@ enum ModpackCommand {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum ServerCommand {
+    /// List the profile's servers and those added in-game
+    List { profile: String },
+    /// Add a server, or update the one with the same address
+    Add {
+        profile: String,
+        /// Name shown in the server list
+        name: String,
+        /// Host, with an optional :port
+        address: String,
+        /// PNG icon to show next to the server
+        #[arg(long)]
+        icon: Option<PathBuf>,
+        /// What to do with the server's resource pack (prompt, enabled, disabled)
+        #[arg(long, default_value = "prompt")]
+        resource_pack: ResourcePackPolicy,
+    },
+    /// Remove a server by address or name
+    Remove { profile: String, target: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum WorldCommand {
+    /// List worlds, most recently played first
+    List { profile: String },
+    /// Copy a world to another profile
+    Copy {
+        from: String,
+        /// World folder name
+        world: String,
+        to: String,
+        /// Folder name for the copy (default: same as the original)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Import a world from a zip file
+    Import {
+        profile: String,
+        zip: PathBuf,
+        /// Folder name for the world (default: its folder in the zip)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Export a world to a zip file
+    Export {
+        profile: String,
+        /// World folder name
+        world: String,
+        /// Output file (default: <world>.zip)
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BackupCommand {
+    /// Back up worlds (default: every world of the profile)
+    Create {
+        profile: String,
+        worlds: Vec<String>,
+    },
+    /// List backups, oldest first
+    List {
+        profile: String,
+        /// Only backups of this world
+        world: Option<String>,
+    },
+    /// Restore a world from a backup; the current state is backed up first
+    Restore {
+        profile: String,
+        world: String,
+        backup: String,
+        /// Restore into this world folder instead
+        #[arg(long = "as")]
+        target: Option<String>,
+        /// Restore even if the game is running or has the world open
+        #[arg(long)]
+        force: bool,
+    },
+    /// Delete backups outside the retention policy and unreferenced files
+    Prune {
+        profile: String,
+        /// Only prune backups of this world
+        world: Option<String>,
+        /// Most recent backups to keep (default: from config)
+        #[arg(long)]
+        keep_last: Option<usize>,
+        /// Days to keep the newest backup of (default: from config)
+        #[arg(long)]
+        keep_daily: Option<usize>,
+        /// Weeks to keep the newest backup of (default: from config)
+        #[arg(long)]
+        keep_weekly: Option<usize>,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum AccountCommand {
     /// Add a Microsoft account (device code flow)
@@ -408,6 +687,7 @@ enum StoreContentType {
     Mod,
     Resourcepack,
     Shader,
+    Datapack,
 }
 
 impl From<StoreContentType> for ContentType {
@@ -416,6 +696,7 @@ impl From<StoreContentType> for ContentType {
             StoreContentType::Mod => ContentType::Mod,
             StoreContentType::Resourcepack => ContentType::ResourcePack,
             StoreContentType::Shader => ContentType::ShaderPack,
+            StoreContentType::Datapack => ContentType::Datapack,
         }
     }
 }
@@ -460,7 +741,8 @@ enum LogsCommand {
         /// Search for text in logs
         #[arg(long)]
         search: Option<String>,
-        /// Show specific log file instead of latest
+        /// Show specific log file instead of latest, including launcher
+        /// session logs (`session-<id>.log`)
         #[arg(long)]
         file: Option<String>,
     },
@@ -491,6 +773,22 @@ enum ConfigCommand {
     SetClientSecret { client_secret: String },
     /// Set CurseForge API key
     SetCurseforgeKey { api_key: String },
+    /// Set how content is placed into instances (symlink, hardlink, reflink, copy)
+    SetLinkStrategy { strategy: LinkStrategy },
+    /// Back up the worlds played in a session when the game exits
+    SetBackupOnExit {
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Set which world backups are kept
+    SetBackupRetention {
+        #[arg(long)]
+        keep_last: Option<usize>,
+        #[arg(long)]
+        keep_daily: Option<usize>,
+        #[arg(long)]
+        keep_weekly: Option<usize>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -516,7 +814,7 @@ enum AppUpdateCommand {
 enum LibraryCommand {
     /// List library items
     List {
-        /// Content type filter (mod, resourcepack, shaderpack, skin)
+        /// Content type filter (mod, resourcepack, shaderpack, datapack, skin)
         #[arg(long, short = 't')]
         content_type: Option<String>,
         /// Search by name
@@ -538,7 +836,7 @@ enum LibraryCommand {
     Import {
         /// Path to file or folder
         path: PathBuf,
-        /// Content type (mod, resourcepack, shaderpack, skin)
+        /// Content type (mod, resourcepack, shaderpack, datapack, skin)
         #[arg(long, short = 't')]
         content_type: String,
         /// Recursive import for folders
@@ -656,7 +954,12 @@ fn run() -> Result<()> {
                         Some(value) => Some(parse_loader(&value)?),
                         None => None,
                     };
-                    let runtime = Runtime { java, memory, args };
+                    let runtime = Runtime {
+                        java,
+                        memory,
+                        args,
+                        ..Runtime::default()
+                    };
                     create_profile(&paths, &id, &mc_version, loader, runtime)?;
                     println!("created profile {id}");
                 }
@@ -665,39 +968,278 @@ fn run() -> Result<()> {
                 clone_profile(&paths, &src, &dst)?;
                 println!("cloned profile {src} -> {dst}");
             }
-            ProfileCommand::Diff { a, b } => {
-                let profile_a = load_profile(&paths, &a)?;
-                let profile_b = load_profile(&paths, &b)?;
-                let (only_a, only_b, both) = diff_profiles(&profile_a, &profile_b);
-                println!("only in {a}:");
-                if only_a.is_empty() {
-                    println!("  (none)");
+            ProfileCommand::Diff { a, b, json } => {
+                let diff = diff_sides(&DiffSide::load(&paths, &a)?, &DiffSide::load(&paths, &b)?);
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&diff)?);
                 } else {
-                    for name in only_a {
-                        println!("  {name}");
+                    print!("{diff}");
+                }
+            }
+            ProfileCommand::Show { id, resolved } => {
+                let mut profile = load_profile(&paths, &id)?;
+                let mut local = Vec::new();
+                if resolved {
+                    profile = resolve_profile(&paths, &profile)?;
+                    local = apply_local(&paths, &mut profile)?;
+                }
+                let data = serde_json::to_string_pretty(&profile)?;
+                println!("{data}");
+                for value in local {
+                    eprintln!(
+                        "{} = {} (from {})",
+                        value.field,
+                        value.value,
+                        value.source.label()
+                    );
+                }
+            }
+            ProfileCommand::Lock { id } => {
+                let profile = load_profile(&paths, &id)?;
+                let lock = lock_profile(&paths, &profile)?;
+                save_lock(&paths, &id, &lock)?;
+                println!("locked profile {id}");
+                println!("minecraft: {}", lock.mc_version);
+                if let Some(loader) = &lock.loader {
+                    println!("loader: {} {}", loader.loader_type, loader.version);
+                }
+                println!("version: {}", lock.version_id);
+                println!("libraries: {}", lock.libraries.len());
+                println!(
+                    "content: {} mods, {} resourcepacks, {} shaderpacks",
+                    lock.content.mods.len(),
+                    lock.content.resourcepacks.len(),
+                    lock.content.shaderpacks.len()
+                );
+            }
+            ProfileCommand::Options {
+                id,
+                key,
+                value,
+                unset,
+            } => {
+                let mut profile = load_profile(&paths, &id)?;
+                match (key, value) {
+                    (None, _) => {
+                        for (key, value) in &profile.game_options {
+                            println!("{key}:{value}");
+                        }
+                    }
+                    (Some(key), None) if unset => {
+                        if profile.game_options.remove(&key).is_none() {
+                            bail!("option not set in profile {id}: {key}");
+                        }
+                        save_profile(&paths, &profile)?;
+                        println!("unset {key}");
+                    }
+                    (Some(key), None) => match profile.game_options.get(&key) {
+                        Some(value) => println!("{value}"),
+                        None => bail!("option not set in profile {id}: {key}"),
+                    },
+                    (Some(key), Some(value)) => {
+                        validate_option(&key, &value)?;
+                        profile.game_options.insert(key.clone(), value.clone());
+                        save_profile(&paths, &profile)?;
+                        println!("set {key}:{value}");
                     }
                 }
-                println!("only in {b}:");
-                if only_b.is_empty() {
-                    println!("  (none)");
+            }
+            ProfileCommand::Shader {
+                id,
+                pack,
+                clear,
+                save_settings,
+            } => {
+                let mut profile = load_profile(&paths, &id)?;
+                let resolved = resolve_profile(&paths, &profile)?;
+                if clear {
+                    profile.active_shader = None;
+                    save_profile(&paths, &profile)?;
+                    println!("cleared active shader");
+                } else if save_settings {
+                    let Some(target) = pack.or(resolved.active_shader.clone()) else {
+                        bail!("no shader pack given and profile {id} has no active shader");
+                    };
+                    let planned = plan_instance(&paths, &resolved);
+                    let Some(file) = planned.iter().find(|f| {
+                        f.dir == "shaderpacks"
+                            && resolved.shaderpacks.iter().any(|item| {
+                                item.name == f.name && (item.name == target || item.hash == target)
+                            })
+                    }) else {
+                        bail!("shader pack not found in profile {id}: {target}");
+                    };
+                    let saved = save_pack_settings(&paths, &id, file)?;
+                    println!("saved {} settings to {}", file.name, saved.display());
+                } else if let Some(pack) = pack {
+                    if !resolved
+                        .shaderpacks
+                        .iter()
+                        .any(|item| item.name == pack || item.hash == pack)
+                    {
+                        bail!("shader pack not found in profile {id}: {pack}");
+                    }
+                    profile.active_shader = Some(pack.clone());
+                    save_profile(&paths, &profile)?;
+                    println!("active shader: {pack}");
+                    if !matches!(
+                        resolved.primary_shader_loader(),
+                        Some(ShaderLoader::Iris | ShaderLoader::Optifine)
+                    ) {
+                        eprintln!("warning: profile {id} has no Iris or OptiFine mod");
+                    }
                 } else {
-                    for name in only_b {
-                        println!("  {name}");
+                    match &resolved.active_shader {
+                        Some(active) => println!("active shader: {active}"),
+                        None => println!("no active shader"),
+                    }
+                    match resolved.primary_shader_loader() {
+                        Some(loader) => println!("shader loader: {}", loader.modrinth_name()),
+                        None => println!("shader loader: none"),
+                    }
+                    for item in &resolved.shaderpacks {
+                        if resolve_pack_settings(&paths, &profile, &item.name)?.is_some() {
+                            println!("settings stored: {}", item.name);
+                        }
                     }
                 }
-                println!("in both:");
-                if both.is_empty() {
-                    println!("  (none)");
+            }
+            ProfileCommand::Check { id, json, offline } => {
+                let profile = load_profile(&paths, &id)?;
+                let store = if offline {
+                    None
                 } else {
-                    for name in both {
-                        println!("  {name}");
+                    let config = load_config(&paths)?;
+                    Some(ContentStore::new(config.curseforge_api_key.as_deref()))
+                };
+                let issues = check_profile(&paths, &profile, store.as_ref())?;
+                let errors = issues
+                    .iter()
+                    .filter(|issue| issue.severity == IssueSeverity::Error)
+                    .count();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&issues)?);
+                } else {
+                    for issue in &issues {
+                        println!("{issue}");
                     }
+                    println!("{errors} errors, {} warnings", issues.len() - errors);
+                }
+                if errors > 0 {
+                    bail!("profile {id} has {errors} errors");
                 }
             }
-            ProfileCommand::Show { id } => {
+            ProfileCommand::Fetch { id } => {
                 let profile = load_profile(&paths, &id)?;
-                let data = serde_json::to_string_pretty(&profile)?;
-                println!("{data}");
+                let config = load_config(&paths)?;
+                let report =
+                    fetch_profile_content(&paths, &profile, config.curseforge_api_key.as_deref())?;
+                for name in &report.fetched {
+                    println!("fetched {name}");
+                }
+                for failure in &report.failed {
+                    eprintln!("failed {} {}: {}", failure.kind, failure.name, failure.reason);
+                }
+                println!(
+                    "{} fetched, {} already present, {} failed",
+                    report.fetched.len(),
+                    report.present,
+                    report.failed.len()
+                );
+                if !report.failed.is_empty() {
+                    bail!("some content could not be fetched for profile {id}");
+                }
+            }
+            ProfileCommand::MergeDriver { base, ours, theirs } => {
+                let result = merge_profiles(
+                    &read_profile_file(&base)?,
+                    &read_profile_file(&ours)?,
+                    &read_profile_file(&theirs)?,
+                );
+                let data = serde_json::to_string_pretty(&result.profile)?;
+                fs::write(&ours, data)
+                    .with_context(|| format!("failed to write {}", ours.display()))?;
+                if !result.conflicts.is_empty() {
+                    for conflict in &result.conflicts {
+                        eprintln!("conflict: {conflict}");
+                    }
+                    bail!(
+                        "{} conflicts merging profile; our side was kept for each",
+                        result.conflicts.len()
+                    );
+                }
+            }
+            ProfileCommand::History { id } => {
+                let snapshots = list_snapshots(&paths, &id)?;
+                if snapshots.is_empty() {
+                    println!("no snapshots for profile {id}");
+                }
+                let now = now_epoch_secs();
+                for snapshot in snapshots.iter().rev() {
+                    let mut line = format!(
+                        "{}\t{}\t{}",
+                        snapshot.id,
+                        format_age(now.saturating_sub(snapshot.created)),
+                        snapshot.mc_version
+                    );
+                    if let Some(loader) = &snapshot.loader {
+                        line.push_str(&format!(" {loader}"));
+                    }
+                    line.push_str(&format!(
+                        "\t{} mods, {} resourcepacks, {} shaderpacks, {} overrides",
+                        snapshot.mods, snapshot.resourcepacks, snapshot.shaderpacks, snapshot.overrides
+                    ));
+                    println!("{line}");
+                }
+            }
+            ProfileCommand::Rollback { id, snapshot } => {
+                rollback_profile(&paths, &id, &snapshot)?;
+                println!("rolled back profile {id} to snapshot {snapshot}");
+            }
+            ProfileCommand::Status { id } => {
+                let profile = load_profile(&paths, &id)?;
+                let drift = instance_drift(&paths, &profile)?;
+                if drift.is_empty() {
+                    println!("instance matches profile {id}");
+                }
+                for item in &drift {
+                    println!("{item}");
+                }
+            }
+            ProfileCommand::Capture { id, all } => {
+                let mut profile = load_profile(&paths, &id)?;
+                let drift = instance_drift(&paths, &profile)?;
+                if drift.is_empty() {
+                    println!("nothing to capture for profile {id}");
+                    return Ok(());
+                }
+                let mut selected = Vec::new();
+                for item in drift {
+                    if all || confirm(&format!("capture {item}?"))? {
+                        selected.push(item);
+                    }
+                }
+                let report = capture_drift(&paths, &mut profile, &selected)?;
+                for path in &report.content {
+                    println!("added {path} to profile");
+                }
+                for path in &report.overrides {
+                    println!("copied {path} to overrides");
+                }
+                println!(
+                    "{} content files and {} config files captured",
+                    report.content.len(),
+                    report.overrides.len()
+                );
+            }
+            ProfileCommand::SetLinkStrategy { id, strategy } => {
+                let mut profile = load_profile(&paths, &id)?;
+                profile.link_strategy = strategy;
+                save_profile(&paths, &profile)?;
+                match strategy {
+                    Some(strategy) => println!("profile {id} now uses {strategy} links"),
+                    None => println!("profile {id} now follows the global link strategy"),
+                }
             }
             ProfileCommand::Rename { id, new_id } => {
                 rename_profile(&paths, &id, &new_id)?;
@@ -775,6 +1317,12 @@ fn run() -> Result<()> {
         Command::Shaderpack { command } => {
             handle_pack_command(&paths, ContentKind::ShaderPack, command)?
         }
+        Command::Datapack { command } => {
+            handle_pack_command(&paths, ContentKind::Datapack, command)?
+        }
+        Command::Server { command } => handle_server_command(&paths, command)?,
+        Command::World { command } => handle_world_command(&paths, command)?,
+        Command::Backup { command } => handle_backup_command(&paths, command)?,
         Command::Account { command } => handle_account_command(&paths, command)?,
         Command::Template { command } => handle_template_command(&paths, command)?,
         Command::Store { command } => handle_store_command(&paths, command)?,
@@ -805,32 +1353,181 @@ fn run() -> Result<()> {
                 save_config(&paths, &config)?;
                 println!("saved CurseForge API key");
             }
+            ConfigCommand::SetLinkStrategy { strategy } => {
+                let mut config = load_config(&paths)?;
+                config.link_strategy = strategy;
+                save_config(&paths, &config)?;
+                println!("saved link strategy: {strategy}");
+            }
+            ConfigCommand::SetBackupOnExit { enabled } => {
+                let mut config = load_config(&paths)?;
+                config.backup_on_exit = enabled;
+                save_config(&paths, &config)?;
+                println!("saved backup on exit: {enabled}");
+            }
+            ConfigCommand::SetBackupRetention {
+                keep_last,
+                keep_daily,
+                keep_weekly,
+            } => {
+                let mut config = load_config(&paths)?;
+                let retention = &mut config.backup_retention;
+                retention.keep_last = keep_last.unwrap_or(retention.keep_last);
+                retention.keep_daily = keep_daily.unwrap_or(retention.keep_daily);
+                retention.keep_weekly = keep_weekly.unwrap_or(retention.keep_weekly);
+                let retention = config.backup_retention;
+                save_config(&paths, &config)?;
+                println!(
+                    "saved backup retention: last {}, daily {}, weekly {}",
+                    retention.keep_last, retention.keep_daily, retention.keep_weekly
+                );
+            }
         },
         Command::AppUpdate { command } => handle_app_update_command(command)?,
+        Command::Schema { kind } => {
+            println!("{}", serde_json::to_string_pretty(&json_schema(kind))?);
+        }
+        Command::Migrate { check } => {
+            if check {
+                let pending = pending_migrations(&paths)?;
+                for item in &pending {
+                    println!("{item}");
+                }
+                if !pending.is_empty() {
+                    bail!("{} files need to be migrated", pending.len());
+                }
+                println!("all files are up to date");
+            } else {
+                let migrated = migrate_all(&paths)?;
+                for item in &migrated {
+                    println!("migrated {item}");
+                }
+                if migrated.is_empty() {
+                    println!("all files are up to date");
+                }
+            }
+        }
         Command::Launch {
             profile,
             account,
             prepare_only,
+            frozen,
+            server,
+            world,
+            realm,
+            width,
+            height,
+            fullscreen,
+            demo,
+            force,
         } => {
             let profile_data = load_profile(&paths, &profile)?;
             let launch_account = resolve_launch_account(&paths, account)?;
+            let quick_play = server
+                .map(QuickPlay::Server)
+                .or(world.map(QuickPlay::World))
+                .or(realm.map(QuickPlay::Realm));
+            let options = LaunchOptions {
+                frozen,
+                quick_play,
+                width,
+                height,
+                fullscreen,
+                demo,
+                force,
+            };
             if prepare_only {
-                let plan = prepare(&paths, &profile_data, &launch_account)?;
+                let plan = prepare(&paths, &profile_data, &launch_account, &options)?;
                 println!("prepared instance: {}", plan.instance_dir.display());
+                println!("instance changes: {}", plan.materialized);
                 println!("java: {}", plan.java_exec);
                 println!("main class: {}", plan.main_class);
                 println!("classpath: {}", plan.classpath);
                 println!("jvm args: {}", plan.jvm_args.join(" "));
                 println!("game args: {}", plan.game_args.join(" "));
             } else {
-                launch(&paths, &profile_data, &launch_account)?;
+                launch(&paths, &profile_data, &launch_account, &options)?;
+            }
+        }
+        Command::Ps => {
+            let running = list_running(&paths)?;
+            if running.is_empty() {
+                println!("no running instances");
+            }
+            let now = now_epoch_secs();
+            for instance in running {
+                println!(
+                    "{}\t{}\t{}\tstarted {}\t{} {}",
+                    instance.profile,
+                    instance.pid,
+                    instance.account,
+                    format_age(now.saturating_sub(instance.started)),
+                    instance.java,
+                    instance.main_class
+                );
             }
         }
+        Command::Kill { profile, force } => {
+            for instance in kill_instances(&paths, &profile, force)? {
+                let action = if force { "killed" } else { "stopping" };
+                println!("{action} {profile} (pid {})", instance.pid);
+            }
+        }
+        Command::Attach { profile } => {
+            let running = running_instances(&paths, &profile)?;
+            let Some(instance) = running.first() else {
+                bail!("profile {profile} is not running");
+            };
+            let pid = instance.pid;
+            let Some(log) = active_session_log(&paths, &profile, pid)? else {
+                bail!(
+                    "profile {profile} is running (pid {pid}) but its output is not being captured"
+                );
+            };
+            println!(
+                "attached to {profile} (pid {pid}); Ctrl+C detaches without stopping the game"
+            );
+            let colored = atty::is(atty::Stream::Stdout);
+            let mut watcher = LogWatcher::new(log)?;
+            let poll = Duration::from_millis(250);
+            loop {
+                for entry in watcher.read_new()? {
+                    println!("{}", format_entry(&entry, colored));
+                }
+                if !running_instances(&paths, &profile)?
+                    .iter()
+                    .any(|instance| instance.pid == pid)
+                {
+                    break;
+                }
+                std::thread::sleep(poll);
+            }
+            println!("{profile} exited");
+        }
     }
 
     Ok(())
 }
 
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush().context("failed to flush stdout")?;
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("failed to read answer")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 fn handle_app_update_command(command: AppUpdateCommand) -> Result<()> {
     match command {
         AppUpdateCommand::Check {
@@ -976,6 +1673,7 @@ fn handle_pack_command(paths: &Paths, kind: ContentKind, command: PackCommand) -
             let changed = match kind {
                 ContentKind::ResourcePack => upsert_resourcepack(&mut profile_data, pack_ref),
                 ContentKind::ShaderPack => upsert_shaderpack(&mut profile_data, pack_ref),
+                ContentKind::Datapack => upsert_datapack(&mut profile_data, pack_ref),
                 ContentKind::Mod | ContentKind::Skin => false,
             };
             save_profile(paths, &profile_data)?;
@@ -990,6 +1688,7 @@ fn handle_pack_command(paths: &Paths, kind: ContentKind, command: PackCommand) -
             let changed = match kind {
                 ContentKind::ResourcePack => remove_resourcepack(&mut profile_data, &target),
                 ContentKind::ShaderPack => remove_shaderpack(&mut profile_data, &target),
+                ContentKind::Datapack => remove_datapack(&mut profile_data, &target),
                 ContentKind::Mod | ContentKind::Skin => false,
             };
             if changed {
@@ -999,11 +1698,59 @@ fn handle_pack_command(paths: &Paths, kind: ContentKind, command: PackCommand) -
                 bail!("pack not found in profile {profile}");
             }
         }
+        PackCommand::Move {
+            profile,
+            target,
+            position,
+        } => {
+            if !matches!(kind, ContentKind::ResourcePack) {
+                bail!("only resource packs have a load order");
+            }
+            if position == 0 {
+                bail!("position starts at 1");
+            }
+            let mut profile_data = load_profile(paths, &profile)?;
+            if !move_resourcepack(&mut profile_data, &target, position - 1) {
+                bail!("pack not found in profile {profile}");
+            }
+            save_profile(paths, &profile_data)?;
+            for (i, pack) in profile_data.resourcepacks.iter().enumerate() {
+                println!("{}\t{}", i + 1, pack.name);
+            }
+        }
+        PackCommand::Target {
+            profile,
+            worlds,
+            global,
+        } => {
+            if !matches!(kind, ContentKind::Datapack) {
+                bail!("only datapacks have a target");
+            }
+            let mut profile_data = load_profile(paths, &profile)?;
+            if global || !worlds.is_empty() {
+                if let Some(world) = worlds.iter().find(|w| !is_valid_world_name(w)) {
+                    bail!("not a world folder name: {world}");
+                }
+                profile_data.datapack_worlds = worlds;
+                save_profile(paths, &profile_data)?;
+            }
+            let resolved = resolve_profile(paths, &profile_data)?;
+            if !resolved.datapack_worlds.is_empty() {
+                println!("worlds: {}", resolved.datapack_worlds.join(", "));
+            } else if let Some(loader) = resolved.datapack_loader() {
+                println!("global: {}", loader.dir());
+            } else {
+                println!(
+                    "global: no datapack mod in profile {profile}, datapacks are not installed"
+                );
+            }
+        }
         PackCommand::List { profile } => {
             let profile_data = load_profile(paths, &profile)?;
             let list = match kind {
                 ContentKind::ResourcePack => profile_data.resourcepacks,
                 ContentKind::ShaderPack => profile_data.shaderpacks,
+                ContentKind::Datapack => profile_data.datapacks,
                 ContentKind::Mod | ContentKind::Skin => Vec::new(),
             };
             if list.is_empty() {
@@ -1018,6 +1765,222 @@ fn handle_pack_command(paths: &Paths, kind: ContentKind, command: PackCommand) -
     Ok(())
 }
 
+fn handle_server_command(paths: &Paths, command: ServerCommand) -> Result<()> {
+    match command {
+        ServerCommand::List { profile } => {
+            let profile_data = load_profile(paths, &profile)?;
+            let resolved = resolve_profile(paths, &profile_data)?;
+            let instance = list_instance_servers(&resolved, &paths.instance_dir(&profile))?;
+            if resolved.servers.is_empty() && instance.is_empty() {
+                println!("no servers in profile {profile}");
+            }
+            for server in &resolved.servers {
+                let mut details = Vec::new();
+                if let Some(icon) = &server.icon {
+                    details.push(format!("icon {icon}"));
+                }
+                if !server.resource_pack.is_prompt() {
+                    details.push(format!("resource pack {}", server.resource_pack.label()));
+                }
+                if details.is_empty() {
+                    println!("{}\t{}", server.name, server.address);
+                } else {
+                    println!("{}\t{}\t{}", server.name, server.address, details.join(", "));
+                }
+            }
+            let added: Vec<_> = instance.iter().filter(|entry| !entry.managed).collect();
+            if !added.is_empty() {
+                println!("added in-game:");
+                for entry in added {
+                    println!("  {}\t{}", entry.name, entry.address);
+                }
+            }
+        }
+        ServerCommand::Add {
+            profile,
+            name,
+            address,
+            icon,
+            resource_pack,
+        } => {
+            let mut profile_data = load_profile(paths, &profile)?;
+            if address.trim().is_empty() {
+                bail!("server address is empty");
+            }
+            let icon = icon
+                .map(|path| store_server_icon(paths, &profile, &address, &path))
+                .transpose()?;
+            let added = upsert_server(
+                &mut profile_data,
+                Server {
+                    name,
+                    address: address.clone(),
+                    icon,
+                    resource_pack,
+                },
+            );
+            save_profile(paths, &profile_data)?;
+            if added {
+                println!("added server {address} to profile {profile}");
+            } else {
+                println!("updated server {address} in profile {profile}");
+            }
+        }
+        ServerCommand::Remove { profile, target } => {
+            let mut profile_data = load_profile(paths, &profile)?;
+            if !remove_server(&mut profile_data, &target) {
+                bail!("server not found in profile {profile}: {target}");
+            }
+            save_profile(paths, &profile_data)?;
+            println!("removed server {target} from profile {profile}");
+        }
+    }
+    Ok(())
+}
+
+fn handle_world_command(paths: &Paths, command: WorldCommand) -> Result<()> {
+    match command {
+        WorldCommand::List { profile } => {
+            if !paths.is_profile_present(&profile) {
+                bail!("profile not found: {profile}");
+            }
+            let worlds = list_worlds(paths, &profile)?;
+            if worlds.is_empty() {
+                println!("no worlds in profile {profile}");
+            }
+            let now_ms = now_epoch_secs() * 1000;
+            for world in worlds {
+                let mut mode = world.game_mode.clone();
+                if world.hardcore {
+                    mode.push_str(" (hardcore)");
+                }
+                let played = world
+                    .last_played
+                    .and_then(|ms| u64::try_from(ms).ok())
+                    .map(|ms| format_age(now_ms.saturating_sub(ms) / 1000))
+                    .unwrap_or_else(|| "never".to_string());
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{} bytes",
+                    world.folder,
+                    world.name,
+                    mode,
+                    world.version.as_deref().unwrap_or("-"),
+                    played,
+                    world.size
+                );
+                if let Some(seed) = world.seed {
+                    println!("  seed: {seed}");
+                }
+            }
+        }
+        WorldCommand::Copy {
+            from,
+            world,
+            to,
+            name,
+        } => {
+            let folder = copy_world(paths, &from, &world, &to, name.as_deref())?;
+            println!("copied {world} to {to} as {folder}");
+        }
+        WorldCommand::Import { profile, zip, name } => {
+            let folder = import_world_zip(paths, &profile, &zip, name.as_deref())?;
+            println!("imported {folder} into {profile}");
+        }
+        WorldCommand::Export {
+            profile,
+            world,
+            output,
+        } => {
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{world}.zip")));
+            export_world_zip(paths, &profile, &world, &output)?;
+            println!("exported {world} to {}", output.display());
+        }
+    }
+    Ok(())
+}
+
+fn handle_backup_command(paths: &Paths, command: BackupCommand) -> Result<()> {
+    match command {
+        BackupCommand::Create { profile, worlds } => {
+            if !paths.is_profile_present(&profile) {
+                bail!("profile not found: {profile}");
+            }
+            let worlds = if worlds.is_empty() {
+                list_worlds(paths, &profile)?
+                    .into_iter()
+                    .map(|world| world.folder)
+                    .collect()
+            } else {
+                worlds
+            };
+            if worlds.is_empty() {
+                println!("no worlds in profile {profile}");
+            }
+            let retention = load_config(paths)?.backup_retention;
+            for world in worlds {
+                match create_backup(paths, &profile, &world, &retention)? {
+                    Some(backup) => println!(
+                        "backed up {world} as {} ({} files, {} new bytes)",
+                        backup.id, backup.files, backup.stored
+                    ),
+                    None => println!("{world} is unchanged since its last backup"),
+                }
+            }
+        }
+        BackupCommand::List { profile, world } => {
+            let backups = list_backups(paths, &profile, world.as_deref())?;
+            if backups.is_empty() {
+                println!("no backups for profile {profile}");
+            }
+            let now = now_epoch_secs();
+            for backup in backups {
+                println!(
+                    "{}\t{}\t{}\t{} files\t{} bytes\t{} new",
+                    backup.world,
+                    backup.id,
+                    format_age(now.saturating_sub(backup.created)),
+                    backup.files,
+                    backup.size,
+                    backup.stored
+                );
+            }
+        }
+        BackupCommand::Restore {
+            profile,
+            world,
+            backup,
+            target,
+            force,
+        } => {
+            let folder =
+                restore_backup(paths, &profile, &world, &backup, target.as_deref(), force)?;
+            println!("restored {world} from {backup} into {folder}");
+        }
+        BackupCommand::Prune {
+            profile,
+            world,
+            keep_last,
+            keep_daily,
+            keep_weekly,
+        } => {
+            let mut retention = load_config(paths)?.backup_retention;
+            retention.keep_last = keep_last.unwrap_or(retention.keep_last);
+            retention.keep_daily = keep_daily.unwrap_or(retention.keep_daily);
+            retention.keep_weekly = keep_weekly.unwrap_or(retention.keep_weekly);
+            let report = prune_backups(paths, &profile, world.as_deref(), &retention)?;
+            for backup in &report.removed {
+                println!("removed {} {}", backup.world, backup.id);
+            }
+            println!(
+                "removed {} backups, freed {} bytes",
+                report.removed.len(),
+                report.freed
+            );
+        }
+    }
+    Ok(())
+}
+
 fn handle_account_command(paths: &Paths, command: AccountCommand) -> Result<()> {
     match command {
         AccountCommand::Add {
@@ -1331,6 +2294,7 @@ fn handle_template_command(paths: &Paths, command: TemplateCommand) -> Result<()
             };
 
             let template = Template {
+                schema_version: TEMPLATE_SCHEMA_VERSION,
                 id: id.clone(),
                 name,
                 description: description.unwrap_or_default(),
@@ -1348,8 +2312,7 @@ fn handle_template_command(paths: &Paths, command: TemplateCommand) -> Result<()
         TemplateCommand::Import { path } => {
             let data = std::fs::read_to_string(&path)
                 .with_context(|| format!("failed to read file: {}", path.display()))?;
-            let template: Template =
-                serde_json::from_str(&data).context("failed to parse template JSON")?;
+            let template: Template = parse_document(DocumentKind::Template, &data)?;
             save_template(paths, &template)?;
             println!("imported template {}", template.id);
         }
@@ -1488,6 +2451,8 @@ fn handle_store_command(paths: &Paths, command: StoreCommand) -> Result<()> {
                         .map(|sl| sl.modrinth_name().to_string())
                 }
                 ContentType::ResourcePack => None,
+                // Modrinth publishes datapack versions under their own loader
+                ContentType::Datapack => Some("datapack".to_string()),
             };
 
             // Get version
@@ -1520,6 +2485,7 @@ fn handle_store_command(paths: &Paths, command: StoreCommand) -> Result<()> {
                 ContentType::Mod | ContentType::ModPack => upsert_mod(&mut profile_data, content_ref),
                 ContentType::ResourcePack => upsert_resourcepack(&mut profile_data, content_ref),
                 ContentType::ShaderPack => upsert_shaderpack(&mut profile_data, content_ref),
+                ContentType::Datapack => upsert_datapack(&mut profile_data, content_ref),
             };
 
             save_profile(paths, &profile_data)?;
@@ -1542,7 +2508,15 @@ fn handle_logs_command(paths: &Paths, command: LogsCommand) -> Result<()> {
             } else {
                 for file in files {
                     let current = if file.is_current { " (current)" } else { "" };
-                    println!("{}\t{} bytes{}", file.name, file.size, current);
+                    let session = match &file.session {
+                        Some(session) => match (session.exit_code, session.duration()) {
+                            (_, None) => String::new(),
+                            (Some(code), Some(secs)) => format!("\texited {code} after {secs}s"),
+                            (None, Some(secs)) => format!("\tkilled after {secs}s"),
+                        },
+                        None => String::new(),
+                    };
+                    println!("{}\t{} bytes{}{}", file.name, file.size, current, session);
                 }
             }
         }
@@ -1554,7 +2528,7 @@ fn handle_logs_command(paths: &Paths, command: LogsCommand) -> Result<()> {
             file,
         } => {
             let log_path = if let Some(filename) = file {
-                paths.instance_logs_dir(&profile).join(filename)
+                log_file_path(paths, &profile, &filename)?
             } else {
                 paths.instance_latest_log(&profile)
             };
@@ -1685,6 +2659,7 @@ fn create_profile_from_template(
         } else {
             args
         },
+        ..Runtime::default()
     };
 
     // Create the profile
@@ -2000,7 +2975,7 @@ fn handle_library_command(paths: &Paths, command: LibraryCommand) -> Result<()>
             recursive,
         } => {
             let ct = LibraryContentType::from_str(&content_type)
-                .context("invalid content type; use: mod, resourcepack, shaderpack, skin")?;
+                .context("invalid content type; use: mod, resourcepack, shaderpack, datapack, skin")?;
 
             if path.is_dir() {
                 let result = library.import_folder(paths, &path, ct, recursive)?;
@@ -2036,6 +3011,7 @@ fn handle_library_command(paths: &Paths, command: LibraryCommand) -> Result<()>
                                 paths.store_resourcepack_path(&item.hash)
                             }
                             LibraryContentType::ShaderPack => paths.store_shaderpack_path(&item.hash),
+                            LibraryContentType::Datapack => paths.store_datapack_path(&item.hash),
                             LibraryContentType::Skin => paths.store_skin_path(&item.hash),
                         };
                         if store_path.exists() {
@@ -2077,6 +3053,7 @@ fn handle_library_command(paths: &Paths, command: LibraryCommand) -> Result<()>
             println!("  Mods: {}", stats.mods_count);
             println!("  Resource packs: {}", stats.resourcepacks_count);
             println!("  Shader packs: {}", stats.shaderpacks_count);
+            println!("  Datapacks: {}", stats.datapacks_count);
             println!("  Skins: {}", stats.skins_count);
             println!("  Total size: {} bytes", stats.total_size);
             println!("  Tags: {}", stats.tags_count);
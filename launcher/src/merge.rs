@@ -0,0 +1,460 @@
+//! Three-way merge of profiles.
+//!
+//! Used as a git merge driver for `profile.json` so that two branches adding
+//! different mods merge cleanly. Content lists are merged item by item, keyed by
+//! platform project (or name, see `diff::content_key`); settings are merged as
+//! whole values. Only changes to the same item or setting on both sides that
+//! disagree are reported as conflicts, in which case our side is kept.
+
+use crate::diff::{content_key, describe_server};
+use crate::profile::{ContentRef, Profile, Server};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// A change made on both sides that could not be merged
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeConflict {
+    /// Setting or content item, e.g. "loader" or "mods/sodium"
+    pub path: String,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+impl std::fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: ours {}, theirs {}",
+            self.path,
+            self.ours.as_deref().unwrap_or("removed"),
+            self.theirs.as_deref().unwrap_or("removed")
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// Merged profile; conflicting values are taken from our side
+    pub profile: Profile,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+pub fn merge_profiles(base: &Profile, ours: &Profile, theirs: &Profile) -> MergeResult {
+    let mut conflicts = Vec::new();
+    let mut profile = ours.clone();
+
+    profile.extends = merge_value(
+        "extends",
+        &base.extends,
+        &ours.extends,
+        &theirs.extends,
+        Clone::clone,
+        &mut conflicts,
+    );
+    profile.mc_version = merge_value(
+        "mcVersion",
+        &base.mc_version,
+        &ours.mc_version,
+        &theirs.mc_version,
+        |v| Some(v.clone()),
+        &mut conflicts,
+    );
+    profile.loader = merge_value(
+        "loader",
+        &base.loader,
+        &ours.loader,
+        &theirs.loader,
+        |l| {
+            l.as_ref()
+                .map(|l| format!("{} {}", l.loader_type, l.version))
+        },
+        &mut conflicts,
+    );
+    profile.runtime.java = merge_value(
+        "runtime.java",
+        &base.runtime.java,
+        &ours.runtime.java,
+        &theirs.runtime.java,
+        Clone::clone,
+        &mut conflicts,
+    );
+    profile.runtime.memory = merge_value(
+        "runtime.memory",
+        &base.runtime.memory,
+        &ours.runtime.memory,
+        &theirs.runtime.memory,
+        Clone::clone,
+        &mut conflicts,
+    );
+    profile.runtime.args = merge_value(
+        "runtime.args",
+        &base.runtime.args,
+        &ours.runtime.args,
+        &theirs.runtime.args,
+        |args| Some(args.join(" ")),
+        &mut conflicts,
+    );
+    profile.runtime.width = merge_value(
+        "runtime.width",
+        &base.runtime.width,
+        &ours.runtime.width,
+        &theirs.runtime.width,
+        |v| v.map(|v| v.to_string()),
+        &mut conflicts,
+    );
+    profile.runtime.height = merge_value(
+        "runtime.height",
+        &base.runtime.height,
+        &ours.runtime.height,
+        &theirs.runtime.height,
+        |v| v.map(|v| v.to_string()),
+        &mut conflicts,
+    );
+    profile.runtime.fullscreen = merge_value(
+        "runtime.fullscreen",
+        &base.runtime.fullscreen,
+        &ours.runtime.fullscreen,
+        &theirs.runtime.fullscreen,
+        |v| v.map(|v| v.to_string()),
+        &mut conflicts,
+    );
+    profile.runtime.demo = merge_value(
+        "runtime.demo",
+        &base.runtime.demo,
+        &ours.runtime.demo,
+        &theirs.runtime.demo,
+        |v| v.map(|v| v.to_string()),
+        &mut conflicts,
+    );
+    let keys: BTreeSet<&String> = base
+        .game_options
+        .keys()
+        .chain(ours.game_options.keys())
+        .chain(theirs.game_options.keys())
+        .collect();
+    for key in keys {
+        let value = merge_value(
+            &format!("gameOptions.{key}"),
+            &base.game_options.get(key),
+            &ours.game_options.get(key),
+            &theirs.game_options.get(key),
+            |v| v.cloned(),
+            &mut conflicts,
+        );
+        match value {
+            Some(value) => profile.game_options.insert(key.clone(), value.clone()),
+            None => profile.game_options.remove(key),
+        };
+    }
+    profile.servers = merge_servers(
+        &base.servers,
+        &ours.servers,
+        &theirs.servers,
+        &mut conflicts,
+    );
+    profile.datapack_worlds = merge_value(
+        "datapackWorlds",
+        &base.datapack_worlds,
+        &ours.datapack_worlds,
+        &theirs.datapack_worlds,
+        |worlds| Some(worlds.join(", ")),
+        &mut conflicts,
+    );
+    profile.active_shader = merge_value(
+        "activeShader",
+        &base.active_shader,
+        &ours.active_shader,
+        &theirs.active_shader,
+        |v| v.clone(),
+        &mut conflicts,
+    );
+    profile.files = merge_value(
+        "files",
+        &base.files,
+        &ours.files,
+        &theirs.files,
+        |files| Some(files.config_overrides.clone()),
+        &mut conflicts,
+    );
+    profile.frozen = merge_value(
+        "frozen",
+        &base.frozen,
+        &ours.frozen,
+        &theirs.frozen,
+        |v| v.map(|v| v.to_string()),
+        &mut conflicts,
+    );
+    profile.link_strategy = merge_value(
+        "linkStrategy",
+        &base.link_strategy,
+        &ours.link_strategy,
+        &theirs.link_strategy,
+        |s| s.map(|s| s.to_string()),
+        &mut conflicts,
+    );
+
+    profile.remove = merge_value(
+        "remove",
+        &base.remove,
+        &ours.remove,
+        &theirs.remove,
+        |r| serde_json::to_string(r).ok(),
+        &mut conflicts,
+    );
+
+    profile.mods = merge_content("mods", &base.mods, &ours.mods, &theirs.mods, &mut conflicts);
+    profile.resourcepacks = merge_content(
+        "resourcepacks",
+        &base.resourcepacks,
+        &ours.resourcepacks,
+        &theirs.resourcepacks,
+        &mut conflicts,
+    );
+    profile.shaderpacks = merge_content(
+        "shaderpacks",
+        &base.shaderpacks,
+        &ours.shaderpacks,
+        &theirs.shaderpacks,
+        &mut conflicts,
+    );
+    profile.datapacks = merge_content(
+        "datapacks",
+        &base.datapacks,
+        &ours.datapacks,
+        &theirs.datapacks,
+        &mut conflicts,
+    );
+
+    MergeResult { profile, conflicts }
+}
+
+fn merge_value<T: Clone + PartialEq>(
+    path: &str,
+    base: &T,
+    ours: &T,
+    theirs: &T,
+    describe: impl Fn(&T) -> Option<String>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> T {
+    if ours == theirs || theirs == base {
+        return ours.clone();
+    }
+    if ours == base {
+        return theirs.clone();
+    }
+    conflicts.push(MergeConflict {
+        path: path.to_string(),
+        ours: describe(ours),
+        theirs: describe(theirs),
+    });
+    ours.clone()
+}
+
+/// Merge server lists by address, like content lists.
+fn merge_servers(
+    base: &[Server],
+    ours: &[Server],
+    theirs: &[Server],
+    conflicts: &mut Vec<MergeConflict>,
+) -> Vec<Server> {
+    let find = |list: &[Server], address: &str| -> Option<Server> {
+        list.iter()
+            .find(|server| server.address.eq_ignore_ascii_case(address))
+            .cloned()
+    };
+
+    let mut addresses: Vec<String> = ours.iter().map(|s| s.address.to_lowercase()).collect();
+    for server in theirs {
+        let address = server.address.to_lowercase();
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+
+    addresses
+        .into_iter()
+        .filter_map(|address| {
+            merge_value(
+                &format!("servers/{address}"),
+                &find(base, &address),
+                &find(ours, &address),
+                &find(theirs, &address),
+                |server| server.as_ref().map(describe_server),
+                conflicts,
+            )
+        })
+        .collect()
+}
+
+/// Merge a content list by item identity. Our order is kept and items only
+/// added on their side are appended.
+fn merge_content(
+    label: &str,
+    base: &[ContentRef],
+    ours: &[ContentRef],
+    theirs: &[ContentRef],
+    conflicts: &mut Vec<MergeConflict>,
+) -> Vec<ContentRef> {
+    let find = |list: &[ContentRef], key: &str| -> Option<ContentRef> {
+        list.iter().find(|item| content_key(item) == key).cloned()
+    };
+
+    let mut keys: Vec<String> = ours.iter().map(content_key).collect();
+    for item in theirs {
+        let key = content_key(item);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut merged = Vec::new();
+    for key in keys {
+        let b = find(base, &key);
+        let o = find(ours, &key);
+        let t = find(theirs, &key);
+        let kept = match (o, t) {
+            (Some(o), Some(t)) if o == t => Some(o),
+            (Some(o), Some(t)) if b.as_ref() == Some(&o) => Some(t),
+            (Some(o), Some(t)) if b.as_ref() == Some(&t) => Some(o),
+            // Removed on one side and untouched on the other
+            (Some(o), None) if b.as_ref() == Some(&o) => None,
+            (None, Some(t)) if b.as_ref() == Some(&t) => None,
+            // Added on one side only
+            (Some(o), None) if b.is_none() => Some(o),
+            (None, Some(t)) if b.is_none() => Some(t),
+            (None, None) => None,
+            (o, t) => {
+                let name = o.as_ref().or(t.as_ref()).map(|item| item.name.clone());
+                conflicts.push(MergeConflict {
+                    path: format!("{label}/{}", name.unwrap_or(key)),
+                    ours: o.as_ref().map(describe_content),
+                    theirs: t.as_ref().map(describe_content),
+                });
+                o
+            }
+        };
+        merged.extend(kept);
+    }
+    merged
+}
+
+fn describe_content(item: &ContentRef) -> String {
+    let mut out = item.version.clone().unwrap_or_else(|| item.hash.clone());
+    if !item.enabled {
+        out.push_str(" (disabled)");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn profile(mods: serde_json::Value) -> Profile {
+        serde_json::from_value(json!({
+            "id": "test",
+            "mcVersion": "1.21.4",
+            "mods": mods,
+        }))
+        .unwrap()
+    }
+
+    fn names(profile: &Profile) -> Vec<&str> {
+        profile.mods.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn merges_additions_from_both_sides() {
+        let base = profile(json!([{ "name": "sodium", "hash": "a" }]));
+        let ours = profile(json!([
+            { "name": "sodium", "hash": "a" },
+            { "name": "lithium", "hash": "b" }
+        ]));
+        let theirs = profile(json!([
+            { "name": "sodium", "hash": "a" },
+            { "name": "iris", "hash": "c" }
+        ]));
+
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(names(&result.profile), ["sodium", "lithium", "iris"]);
+    }
+
+    #[test]
+    fn takes_one_sided_update_and_removal() {
+        let base = profile(json!([
+            { "name": "sodium", "hash": "a", "version": "0.5" },
+            { "name": "lithium", "hash": "b" }
+        ]));
+        let ours = profile(json!([{ "name": "sodium", "hash": "a", "version": "0.5" }]));
+        let theirs = profile(json!([
+            { "name": "sodium", "hash": "x", "version": "0.6" },
+            { "name": "lithium", "hash": "b" }
+        ]));
+
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(names(&result.profile), ["sodium"]);
+        assert_eq!(result.profile.mods[0].version.as_deref(), Some("0.6"));
+    }
+
+    #[test]
+    fn matches_items_by_project_id() {
+        let base = profile(json!([
+            { "name": "Sodium", "hash": "a", "platform": "modrinth", "project_id": "AANobbMI" }
+        ]));
+        let ours = base.clone();
+        let theirs = profile(json!([
+            { "name": "sodium-fabric", "hash": "b", "platform": "modrinth", "project_id": "AANobbMI" }
+        ]));
+
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(names(&result.profile), ["sodium-fabric"]);
+    }
+
+    #[test]
+    fn reports_conflicting_version_bumps() {
+        let base = profile(json!([{ "name": "sodium", "hash": "a", "version": "0.5" }]));
+        let ours = profile(json!([{ "name": "sodium", "hash": "b", "version": "0.6.0" }]));
+        let theirs = profile(json!([{ "name": "sodium", "hash": "c", "version": "0.6.1" }]));
+
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "mods/sodium");
+        assert_eq!(result.conflicts[0].ours.as_deref(), Some("0.6.0"));
+        assert_eq!(result.conflicts[0].theirs.as_deref(), Some("0.6.1"));
+        assert_eq!(result.profile.mods[0].hash, "b");
+    }
+
+    #[test]
+    fn reports_update_against_removal() {
+        let base = profile(json!([{ "name": "sodium", "hash": "a" }]));
+        let ours = profile(json!([{ "name": "sodium", "hash": "b" }]));
+        let theirs = profile(json!([]));
+
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].theirs, None);
+    }
+
+    #[test]
+    fn merges_settings() {
+        let base = profile(json!([]));
+        let mut ours = base.clone();
+        ours.runtime.memory = Some("4G".to_string());
+        let mut theirs = base.clone();
+        theirs.mc_version = "1.21.5".to_string();
+
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.profile.mc_version, "1.21.5");
+        assert_eq!(result.profile.runtime.memory.as_deref(), Some("4G"));
+
+        theirs.runtime.memory = Some("8G".to_string());
+        let result = merge_profiles(&base, &ours, &theirs);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "runtime.memory");
+    }
+}
@@ -0,0 +1,338 @@
+//! Versioned on-disk formats.
+//!
+//! `profile.json`, templates, `accounts.json` and `config.json` carry a
+//! `schemaVersion`; files without one are version 0. Documents are upgraded
+//! when they are loaded: the raw JSON goes through every migration registered
+//! after its version, the original file is kept as `<file>.v<N>.bak` under
+//! `migrations/` in the launcher home (never next to the file, which may be in
+//! a versioned profile folder), and the upgraded document is written in its
+//! place.
+//!
+//! In strict mode, keys that do not map to any field are an error instead of
+//! being dropped.
+
+use crate::accounts::Accounts;
+use crate::config::Config;
+use crate::paths::Paths;
+use crate::profile::{Profile, list_profiles};
+use crate::template::{Template, list_templates};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Upgrades a document by one version, in place
+type Migration = fn(&mut Value) -> Result<()>;
+
+// Entry `n` of each list upgrades a document from version `n` to `n + 1`.
+const PROFILE_MIGRATIONS: &[Migration] = &[unversioned];
+const TEMPLATE_MIGRATIONS: &[Migration] = &[unversioned];
+const ACCOUNTS_MIGRATIONS: &[Migration] = &[unversioned];
+const CONFIG_MIGRATIONS: &[Migration] = &[unversioned];
+
+pub const PROFILE_SCHEMA_VERSION: u32 = PROFILE_MIGRATIONS.len() as u32;
+pub const TEMPLATE_SCHEMA_VERSION: u32 = TEMPLATE_MIGRATIONS.len() as u32;
+pub const ACCOUNTS_SCHEMA_VERSION: u32 = ACCOUNTS_MIGRATIONS.len() as u32;
+pub const CONFIG_SCHEMA_VERSION: u32 = CONFIG_MIGRATIONS.len() as u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Profile,
+    Template,
+    Accounts,
+    Config,
+}
+
+impl DocumentKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DocumentKind::Profile => "profile",
+            DocumentKind::Template => "template",
+            DocumentKind::Accounts => "accounts",
+            DocumentKind::Config => "config",
+        }
+    }
+
+    pub fn current_version(self) -> u32 {
+        self.migrations().len() as u32
+    }
+
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            DocumentKind::Profile => PROFILE_MIGRATIONS,
+            DocumentKind::Template => TEMPLATE_MIGRATIONS,
+            DocumentKind::Accounts => ACCOUNTS_MIGRATIONS,
+            DocumentKind::Config => CONFIG_MIGRATIONS,
+        }
+    }
+}
+
+/// A file written with an older schema than the current one
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingMigration {
+    pub path: PathBuf,
+    pub kind: DocumentKind,
+    pub from: u32,
+    pub to: u32,
+}
+
+impl std::fmt::Display for PendingMigration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: v{} -> v{}",
+            self.kind.label(),
+            self.path.display(),
+            self.from,
+            self.to
+        )
+    }
+}
+
+/// Documents written before `schemaVersion` existed already have the version 1
+/// layout.
+fn unversioned(_: &mut Value) -> Result<()> {
+    Ok(())
+}
+
+fn document_version(kind: DocumentKind, value: &Value) -> Result<u32> {
+    let version = match value.get("schemaVersion") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("schemaVersion is not a valid number")?,
+    };
+    let current = kind.current_version();
+    if version > current {
+        bail!(
+            "{} schema version {version} is newer than this launcher supports ({current}); update shard",
+            kind.label()
+        );
+    }
+    Ok(version)
+}
+
+/// Upgrade a document in place. Returns the version it had if it was changed.
+pub fn migrate_value(kind: DocumentKind, value: &mut Value) -> Result<Option<u32>> {
+    let version = document_version(kind, value)?;
+    let current = kind.current_version();
+    if version == current {
+        return Ok(None);
+    }
+    for migration in &kind.migrations()[version as usize..] {
+        migration(value)?;
+    }
+    value
+        .as_object_mut()
+        .context("document is not a JSON object")?
+        .insert("schemaVersion".to_string(), current.into());
+    Ok(Some(version))
+}
+
+/// Parse a document, upgrading it in memory only.
+pub fn parse_document<T: DeserializeOwned>(kind: DocumentKind, data: &str) -> Result<T> {
+    let mut value: Value = serde_json::from_str(data)
+        .with_context(|| format!("failed to parse {} JSON", kind.label()))?;
+    migrate_value(kind, &mut value)?;
+    serde_json::from_value(value).with_context(|| format!("failed to parse {} JSON", kind.label()))
+}
+
+/// Read a document from disk. If it is older than the current schema, the
+/// original is backed up and the upgraded document is written in its place.
+/// With `strict`, unknown keys are rejected.
+pub fn load_document<T: DeserializeOwned + Serialize>(
+    paths: &Paths,
+    kind: DocumentKind,
+    path: &Path,
+    strict: bool,
+) -> Result<T> {
+    let label = kind.label();
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {label} file: {}", path.display()))?;
+    let mut value: Value = serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse {label} JSON: {}", path.display()))?;
+    let from = migrate_value(kind, &mut value)
+        .with_context(|| format!("failed to migrate {label}: {}", path.display()))?;
+    let document: T = from_value(value, strict)
+        .with_context(|| format!("failed to parse {label} JSON: {}", path.display()))?;
+
+    if let Some(from) = from {
+        let backup = backup_path(paths, path, from);
+        if let Some(parent) = backup.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+        }
+        fs::copy(path, &backup)
+            .with_context(|| format!("failed to back up {label}: {}", backup.display()))?;
+        let data = serde_json::to_string_pretty(&document)
+            .with_context(|| format!("failed to serialize {label}"))?;
+        fs::write(path, data)
+            .with_context(|| format!("failed to write {label} file: {}", path.display()))?;
+    }
+    Ok(document)
+}
+
+fn from_value<T: DeserializeOwned>(value: Value, strict: bool) -> Result<T> {
+    if !strict {
+        return Ok(serde_json::from_value(value)?);
+    }
+    let mut unknown = Vec::new();
+    let document = serde_ignored::deserialize(value, |path| unknown.push(format_path(&path)))?;
+    if !unknown.is_empty() {
+        bail!("unknown fields: {}", unknown.join(", "));
+    }
+    Ok(document)
+}
+
+/// Render a path like `mods[2].hash`.
+fn format_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}[{index}]", format_path(parent)),
+        Path::Map { parent, key } => {
+            let parent = format_path(parent);
+            if parent.is_empty() {
+                key.clone()
+            } else {
+                format!("{parent}.{key}")
+            }
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => format_path(parent),
+    }
+}
+
+/// `<home>/profiles/a/profile.json` is backed up as
+/// `<home>/migrations/profiles/a/profile.json.v<N>.bak`.
+fn backup_path(paths: &Paths, path: &Path, version: u32) -> PathBuf {
+    let rel = match path.strip_prefix(&paths.home) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => PathBuf::from(path.file_name().unwrap_or_default()),
+    };
+    let name = rel
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    paths
+        .migration_backups
+        .join(rel.with_file_name(format!("{name}.v{version}.bak")))
+}
+
+/// List the files under the shard home that are older than the current schema.
+pub fn pending_migrations(paths: &Paths) -> Result<Vec<PendingMigration>> {
+    let mut files = vec![
+        (DocumentKind::Config, paths.config.clone()),
+        (DocumentKind::Accounts, paths.accounts.clone()),
+    ];
+    for id in list_profiles(paths)? {
+        files.push((DocumentKind::Profile, paths.profile_json(&id)));
+    }
+    for id in list_templates(paths)? {
+        files.push((DocumentKind::Template, paths.template_json(&id)));
+    }
+
+    let mut pending = Vec::new();
+    for (kind, path) in files {
+        if !path.exists() {
+            continue;
+        }
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {} file: {}", kind.label(), path.display()))?;
+        let value: Value = serde_json::from_str(&data).with_context(|| {
+            format!("failed to parse {} JSON: {}", kind.label(), path.display())
+        })?;
+        let from = document_version(kind, &value)
+            .with_context(|| format!("failed to read schema version: {}", path.display()))?;
+        let to = kind.current_version();
+        if from < to {
+            pending.push(PendingMigration {
+                path,
+                kind,
+                from,
+                to,
+            });
+        }
+    }
+    Ok(pending)
+}
+
+/// Upgrade every file that is older than the current schema.
+pub fn migrate_all(paths: &Paths) -> Result<Vec<PendingMigration>> {
+    let pending = pending_migrations(paths)?;
+    for item in &pending {
+        match item.kind {
+            DocumentKind::Profile => {
+                load_document::<Profile>(paths, item.kind, &item.path, false)?;
+            }
+            DocumentKind::Template => {
+                load_document::<Template>(paths, item.kind, &item.path, false)?;
+            }
+            DocumentKind::Accounts => {
+                load_document::<Accounts>(paths, item.kind, &item.path, false)?;
+            }
+            DocumentKind::Config => {
+                load_document::<Config>(paths, item.kind, &item.path, false)?;
+            }
+        }
+    }
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn stamps_unversioned_documents() {
+        let mut value = json!({ "id": "test", "mcVersion": "1.21.4" });
+        let from = migrate_value(DocumentKind::Profile, &mut value).unwrap();
+        assert_eq!(from, Some(0));
+        assert_eq!(value["schemaVersion"], PROFILE_SCHEMA_VERSION);
+
+        let from = migrate_value(DocumentKind::Profile, &mut value).unwrap();
+        assert_eq!(from, None);
+    }
+
+    #[test]
+    fn strict_mode_reports_unknown_field_paths() {
+        let value = json!({
+            "id": "test",
+            "mcversion": "1.21.4",
+            "mods": [{ "name": "sodium", "hsah": "a", "hash": "a" }]
+        });
+        let err = from_value::<Profile>(value.clone(), true).unwrap_err();
+        assert_eq!(err.to_string(), "unknown fields: mcversion, mods[0].hsah");
+        assert!(from_value::<Profile>(value, false).is_ok());
+    }
+
+    #[test]
+    fn backs_up_outside_the_profile_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let path = paths.profile_json("a");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, r#"{ "id": "a", "mcVersion": "1.21.4" }"#).unwrap();
+
+        let pending = migrate_all(&paths).unwrap();
+        assert_eq!(pending.len(), 1);
+        let backup = paths
+            .migration_backups
+            .join("profiles/a/profile.json.v0.bak");
+        assert!(backup.exists());
+        assert_eq!(fs::read_dir(paths.profile_dir("a")).unwrap().count(), 1);
+        assert!(pending_migrations(&paths).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_newer_documents() {
+        let mut value = json!({ "schemaVersion": PROFILE_SCHEMA_VERSION + 1 });
+        assert!(migrate_value(DocumentKind::Profile, &mut value).is_err());
+    }
+}
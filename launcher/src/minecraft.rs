@@ -1,19 +1,34 @@
-use crate::instance::materialize_instance;
+use crate::backup::backup_after_session;
+use crate::config::load_config;
+use crate::inherit::{hash_overrides, resolve_profile};
+use crate::instance::{
+    IncompleteInstance, InstanceProblem, MaterializeOptions, MaterializeSummary, ProblemKind,
+    materialize_instance, verify_instance,
+};
 use crate::java::{detect_installations, get_required_java_version, is_java_compatible};
+use crate::local::apply_local;
+use crate::lock::{
+    LockedAssetIndex, LockedContent, LockedIntent, LockedLibrary, ProfileLock, load_lock,
+};
+use crate::logs::SessionLog;
 use crate::paths::Paths;
 use crate::profile::{Loader, Profile};
+use crate::running::{RunningInstance, claim_launch, ensure_not_running, register, unregister};
+use crate::store::hash_file;
 use crate::util::normalize_path_separator;
+use crate::worlds::world_dir;
 use anyhow::{Context, Result, bail};
 use reqwest::blocking::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha1::{Digest, Sha1};
 use shell_words::split;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
 
 const VERSION_MANIFEST_URL: &str =
     "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
@@ -30,6 +45,8 @@ pub struct LaunchAccount {
 #[derive(Debug, Clone)]
 pub struct LaunchPlan {
     pub instance_dir: PathBuf,
+    /// Changes made to the instance directory while preparing
+    pub materialized: MaterializeSummary,
     pub java_exec: String,
     pub jvm_args: Vec<String>,
     pub classpath: String,
@@ -37,14 +54,217 @@ pub struct LaunchPlan {
     pub game_args: Vec<String>,
 }
 
-pub fn prepare(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Result<LaunchPlan> {
-    let instance_dir = materialize_instance(paths, profile)?;
+impl LaunchPlan {
+    /// The full Java command line with every occurrence of `access_token`
+    /// replaced, safe to write to logs.
+    pub fn redacted_command(&self, access_token: &str) -> Vec<String> {
+        let redact = |arg: &String| {
+            if access_token.is_empty() {
+                arg.clone()
+            } else {
+                arg.replace(access_token, "<redacted>")
+            }
+        };
+        let mut command = vec![self.java_exec.clone()];
+        command.extend(self.jvm_args.iter().map(redact));
+        command.push("-cp".to_string());
+        command.push(self.classpath.clone());
+        command.push(self.main_class.clone());
+        command.extend(self.game_args.iter().map(redact));
+        command
+    }
+}
 
-    let java_path = profile.runtime.java.as_deref();
-    let version_id = resolve_version_id(paths, &profile.mc_version, profile.loader.as_ref(), java_path)?;
-    let resolved = resolve_version(paths, &version_id)?;
+/// Options for preparing a launch
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// Refuse to launch if any content, override file or library is missing or
+    /// does not match the lockfile
+    pub frozen: bool,
+    /// Join a server, world or realm as soon as the game starts
+    pub quick_play: Option<QuickPlay>,
+    /// Window width, overriding the profile's `runtime.width`
+    pub width: Option<u32>,
+    /// Window height, overriding the profile's `runtime.height`
+    pub height: Option<u32>,
+    /// Start in fullscreen even if the profile does not ask for it
+    pub fullscreen: bool,
+    /// Launch in demo mode even if the profile does not ask for it
+    pub demo: bool,
+    /// Prepare and launch even if the profile is already running
+    pub force: bool,
+}
+
+/// Where Quick Play takes the player after the game starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickPlay {
+    /// Server address, `host` or `host:port`
+    Server(String),
+    /// World folder name in `saves/`
+    World(String),
+    /// Realm ID
+    Realm(String),
+}
+
+impl QuickPlay {
+    /// Rule feature that enables this mode's arguments in the version JSON
+    fn feature(&self) -> &'static str {
+        match self {
+            QuickPlay::Server(_) => "is_quick_play_multiplayer",
+            QuickPlay::World(_) => "is_quick_play_singleplayer",
+            QuickPlay::Realm(_) => "is_quick_play_realms",
+        }
+    }
+
+    /// Argument variable the version JSON substitutes the target into
+    fn var(&self) -> &'static str {
+        match self {
+            QuickPlay::Server(_) => "quickPlayMultiplayer",
+            QuickPlay::World(_) => "quickPlaySingleplayer",
+            QuickPlay::Realm(_) => "quickPlayRealms",
+        }
+    }
+
+    fn target(&self) -> &str {
+        match self {
+            QuickPlay::Server(target) | QuickPlay::World(target) | QuickPlay::Realm(target) => {
+                target
+            }
+        }
+    }
+
+    /// Fail early on a target the game could not join.
+    fn validate(&self, paths: &Paths, profile_id: &str) -> Result<()> {
+        match self {
+            QuickPlay::Server(address) => {
+                split_server_address(address)?;
+            }
+            QuickPlay::World(world) => {
+                world_dir(paths, profile_id, world)?;
+            }
+            QuickPlay::Realm(id) if id.trim().is_empty() => bail!("realm ID is empty"),
+            QuickPlay::Realm(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub fn prepare(
+    paths: &Paths,
+    profile: &Profile,
+    account: &LaunchAccount,
+    options: &LaunchOptions,
+) -> Result<LaunchPlan> {
+    // Materializing would swap content out from under a running game.
+    if !options.force {
+        ensure_not_running(paths, &profile.id)?;
+    }
+    let declared = profile;
+    let mut profile = resolve_profile(paths, declared)?;
+    apply_local(paths, &mut profile)?;
+    let profile = &profile;
+    let frozen = options.frozen || profile.frozen == Some(true);
+    let mut problems = Vec::new();
+
+    // A lockfile pins the versions the profile leaves open, as long as it was
+    // written for the mcVersion/loader the profile declares now.
+    let lock = load_lock(paths, &profile.id)?;
+    let pinned = match &lock {
+        Some(lock) if lock.matches_intent(profile) => Some(lock),
+        Some(_) => {
+            let detail = format!(
+                "out of date with profile.json; run `shard profile lock {}` to refresh it",
+                profile.id
+            );
+            if frozen {
+                problems.push(InstanceProblem {
+                    kind: ProblemKind::Lockfile,
+                    name: "profile.lock.json".to_string(),
+                    detail,
+                });
+            } else {
+                eprintln!("warning: profile.lock.json is {detail}");
+            }
+            None
+        }
+        None => None,
+    };
+    let (mc_version, loader) = match pinned {
+        Some(lock) => (lock.mc_version.clone(), lock.loader.clone()),
+        None => (
+            resolve_mc_version(paths, &profile.mc_version)?,
+            profile.loader.clone(),
+        ),
+    };
+
+    // A frozen launch is checked before anything is resolved: resolving a
+    // Forge or NeoForge version can download and run its installer. With a
+    // lockfile it launches the pinned version JSON, which must already be
+    // installed, and hashes every library on disk against it.
+    let version_id = match pinned {
+        Some(lock) if frozen => lock.version_id.clone(),
+        _ => resolve_version_id(
+            paths,
+            &mc_version,
+            loader.as_ref(),
+            profile.runtime.java.as_deref(),
+        )?,
+    };
+    let mut resolved = None;
+    if let Some(lock) = pinned {
+        let mismatches = if frozen && !paths.minecraft_version_json(&version_id).exists() {
+            vec![InstanceProblem {
+                kind: ProblemKind::Lockfile,
+                name: "profile.lock.json".to_string(),
+                detail: format!(
+                    "version {version_id} is not installed; launch once without --frozen"
+                ),
+            }]
+        } else {
+            let version = resolve_version(paths, &version_id)?;
+            let mut mismatches =
+                lock_mismatches(paths, lock, profile, &version_id, &version.merged)?;
+            if frozen {
+                mismatches.extend(verify_library_files(paths, &version.merged)?);
+            }
+            resolved = Some(version);
+            mismatches
+        };
+        if frozen {
+            problems.extend(mismatches);
+        } else {
+            for mismatch in mismatches {
+                eprintln!("warning: {mismatch}");
+            }
+        }
+    }
+
+    // Report lockfile problems together with missing content instead of
+    // stopping at the first one.
+    if frozen {
+        problems.extend(verify_instance(paths, declared, lock.as_ref())?);
+    }
+    if !problems.is_empty() {
+        return Err(IncompleteInstance { problems }.into());
+    }
+    let resolved = match resolved {
+        Some(resolved) => resolved,
+        None => resolve_version(paths, &version_id)?,
+    };
     let version = resolved.merged;
 
+    let config = load_config(paths)?;
+    let materialized = materialize_instance(
+        paths,
+        declared,
+        &MaterializeOptions {
+            link_strategy: profile.link_strategy.unwrap_or(config.link_strategy),
+            keep_override_edits: config.keep_override_edits,
+        },
+    )?;
+    let instance_dir = paths.instance_dir(&profile.id);
+
     // Collect client JARs from versions in the chain.
     // Forge/NeoForge handle the client JAR internally via their processed JARs,
     // so we shouldn't add the vanilla client JAR to the classpath for those loaders.
@@ -67,14 +287,22 @@ pub fn prepare(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Res
     let asset_index_id = ensure_assets(paths, &version)?;
     let (classpath, natives_dir) = ensure_libraries(paths, &version, &instance_dir, &client_jars)?;
 
-    let java_exec = resolve_java(profile.runtime.java.as_deref(), &profile.mc_version);
+    let java_exec = resolve_java(profile.runtime.java.as_deref(), &mc_version);
     let assets_root = paths
         .minecraft_assets_objects
         .parent()
         .context("assets root missing")?
         .to_path_buf();
 
-    let vars = build_var_map(
+    let resolution = match (
+        options.width.or(profile.runtime.width),
+        options.height.or(profile.runtime.height),
+    ) {
+        (Some(0), _) | (_, Some(0)) => bail!("window width and height must be greater than 0"),
+        (Some(width), Some(height)) => Some((width, height)),
+        _ => None,
+    };
+    let mut vars = build_var_map(
         &instance_dir,
         &assets_root,
         &asset_index_id,
@@ -84,8 +312,20 @@ pub fn prepare(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Res
         &version,
         account,
     );
+    if let Some(quick_play) = &options.quick_play {
+        quick_play.validate(paths, &profile.id)?;
+    }
 
-    let (mut jvm_args, game_args) = build_args(&version, &vars)?;
+    let features = LaunchFeatures {
+        resolution,
+        fullscreen: options.fullscreen || profile.runtime.fullscreen == Some(true),
+        demo: options.demo || profile.runtime.demo == Some(true),
+        quick_play: options.quick_play.as_ref(),
+        quick_play_log: paths.instance_state_dir(&profile.id).join("quickPlay.json"),
+    };
+    let (ctx, extra_game_args) = features.apply(&version, &mut vars)?;
+    let (mut jvm_args, mut game_args) = build_args(&version, &vars, &ctx)?;
+    game_args.extend(extra_game_args);
 
     if let Some(memory) = &profile.runtime.memory
         && !jvm_args.iter().any(|arg| arg.starts_with("-Xmx")) {
@@ -106,6 +346,7 @@ pub fn prepare(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Res
 
     Ok(LaunchPlan {
         instance_dir,
+        materialized,
         java_exec,
         jvm_args,
         classpath,
@@ -114,19 +355,96 @@ pub fn prepare(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Res
     })
 }
 
-pub fn launch(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Result<()> {
-    let plan = prepare(paths, profile, account)?;
+pub fn launch(
+    paths: &Paths,
+    profile: &Profile,
+    account: &LaunchAccount,
+    options: &LaunchOptions,
+) -> Result<()> {
+    // Held until the game is registered so a second launch sees it running
+    let launch_lock = if options.force {
+        None
+    } else {
+        Some(claim_launch(paths, &profile.id)?)
+    };
+    let plan = prepare(paths, profile, account, options)?;
+    if !plan.materialized.is_empty() {
+        eprintln!("instance updated: {}", plan.materialized);
+    }
 
-    let status = Command::new(&plan.java_exec)
+    let started = SystemTime::now();
+    // Losing the session log is no reason not to play
+    let mut session = SessionLog::start(
+        paths,
+        &profile.id,
+        plan.redacted_command(&account.access_token),
+    )
+    .inspect_err(|err| {
+        eprintln!("warning: failed to create session log, output will not be kept: {err:#}");
+    })
+    .ok();
+    let output = || {
+        if session.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        }
+    };
+    let spawned = Command::new(&plan.java_exec)
         .args(&plan.jvm_args)
         .arg("-cp")
         .arg(&plan.classpath)
         .arg(&plan.main_class)
         .args(&plan.game_args)
         .current_dir(&plan.instance_dir)
-        .status()
-        .context("failed to launch java")?;
+        .stdout(output())
+        .stderr(output())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            if let Some(session) = session {
+                let _ = session.finish(None);
+            }
+            return Err(err).context("failed to launch java");
+        }
+    };
+    if let Some(session) = session.as_mut() {
+        session.capture(&mut child, true);
+    }
+    let running = RunningInstance::new(
+        &profile.id,
+        &account.username,
+        child.id(),
+        &plan.java_exec,
+        &plan.main_class,
+    );
+    if let Err(err) = register(paths, &running) {
+        eprintln!("warning: failed to record running instance: {err:#}");
+    }
+    drop(launch_lock);
+    let status = child.wait().context("failed to wait for minecraft");
+    if let Err(err) = unregister(paths, running.pid) {
+        eprintln!("warning: failed to clear running instance: {err:#}");
+    }
+    // A failed wait still ends the session and backs up what was played
+    let exit_code = status.as_ref().ok().and_then(|status| status.code());
+    if let Some(session) = session
+        && let Err(err) = session.finish(exit_code)
+    {
+        eprintln!("warning: failed to record session: {err:#}");
+    }
+
+    match backup_after_session(paths, &profile.id, started) {
+        Ok(backups) => {
+            for backup in backups {
+                eprintln!("backed up world {} as {}", backup.world, backup.id);
+            }
+        }
+        Err(err) => eprintln!("warning: world backup failed: {err:#}"),
+    }
 
+    let status = status?;
     if !status.success() {
         bail!("minecraft exited with status {status}");
     }
@@ -134,6 +452,227 @@ pub fn launch(paths: &Paths, profile: &Profile, account: &LaunchAccount) -> Resu
     Ok(())
 }
 
+/// Resolve everything the profile leaves open (`latest` Minecraft and loader
+/// versions, the version JSON chain) and record it as a lockfile.
+pub fn lock_profile(paths: &Paths, profile: &Profile) -> Result<ProfileLock> {
+    let declared = profile;
+    let profile = &resolve_profile(paths, declared)?;
+    let mc_version = resolve_mc_version(paths, &profile.mc_version)?;
+    let loader = match &profile.loader {
+        Some(loader) => Some(resolve_loader_version(&mc_version, loader)?),
+        None => None,
+    };
+    let java_path = profile.runtime.java.as_deref();
+    let version_id = resolve_version_id(paths, &mc_version, loader.as_ref(), java_path)?;
+    let resolved = resolve_version(paths, &version_id)?;
+
+    Ok(ProfileLock {
+        declared: LockedIntent::from_profile(profile),
+        mc_version,
+        loader,
+        version_json: version_json_hash(paths, &version_id)?,
+        version_id,
+        asset_index: locked_asset_index(&resolved.merged),
+        libraries: locked_libraries(&resolved.merged),
+        content: LockedContent::from_profile(profile),
+        overrides: hash_overrides(paths, declared)?,
+    })
+}
+
+/// Compare a freshly resolved version against a lockfile.
+fn lock_mismatches(
+    paths: &Paths,
+    lock: &ProfileLock,
+    profile: &Profile,
+    version_id: &str,
+    version: &VersionJson,
+) -> Result<Vec<InstanceProblem>> {
+    let lockfile_problem = |detail: String| InstanceProblem {
+        kind: ProblemKind::Lockfile,
+        name: "profile.lock.json".to_string(),
+        detail,
+    };
+    let library_problem = |name: &str, detail: &str| InstanceProblem {
+        kind: ProblemKind::Library,
+        name: name.to_string(),
+        detail: detail.to_string(),
+    };
+
+    let mut mismatches = Vec::new();
+    if lock.version_id != version_id {
+        mismatches.push(lockfile_problem(format!(
+            "resolved version {version_id} but the lockfile has {}",
+            lock.version_id
+        )));
+    } else if version_json_hash(paths, version_id)? != lock.version_json {
+        mismatches.push(lockfile_problem(format!(
+            "version JSON for {version_id} differs from the lockfile"
+        )));
+    }
+
+    if locked_asset_index(version) != lock.asset_index {
+        mismatches.push(lockfile_problem(
+            "asset index differs from the lockfile".to_string(),
+        ));
+    }
+
+    let libraries = locked_libraries(version);
+    for library in &libraries {
+        match lock.libraries.iter().find(|l| l.name == library.name) {
+            None => mismatches.push(library_problem(&library.name, "not in the lockfile")),
+            Some(locked) if locked != library => {
+                mismatches.push(library_problem(&library.name, "hash differs from the lockfile"))
+            }
+            Some(_) => {}
+        }
+    }
+    for locked in &lock.libraries {
+        if !libraries.iter().any(|l| l.name == locked.name) {
+            mismatches.push(library_problem(
+                &locked.name,
+                "in the lockfile but no longer used",
+            ));
+        }
+    }
+
+    mismatches.extend(lock.content_drift(profile).into_iter().map(lockfile_problem));
+    Ok(mismatches)
+}
+
+/// Libraries (and this platform's natives) of a version that are missing from
+/// the library folder or do not hash to the version JSON's sha1.
+fn verify_library_files(paths: &Paths, version: &VersionJson) -> Result<Vec<InstanceProblem>> {
+    let mut problems = Vec::new();
+    for library in version.libraries.iter().filter(|l| library_allowed(l)) {
+        let downloads = library.downloads.as_ref();
+        let mut files = Vec::new();
+        match downloads.and_then(|d| d.artifact.as_ref()) {
+            Some(artifact) => files.push((artifact.path.clone(), Some(&artifact.sha1))),
+            None => {
+                if let Some(path) = maven_path_from_name(&library.name) {
+                    files.push((path, None));
+                }
+            }
+        }
+        if let Some(natives) = library.natives.as_ref()
+            && let Some(classifier) = natives.get(&os_key())
+        {
+            let classifier = classifier.replace("${arch}", arch_marker());
+            match downloads
+                .and_then(|d| d.classifiers.as_ref())
+                .and_then(|classifiers| classifiers.get(&classifier))
+            {
+                Some(artifact) => files.push((artifact.path.clone(), Some(&artifact.sha1))),
+                None => {
+                    if let Some(path) =
+                        maven_path_from_name_with_classifier(&library.name, &classifier)
+                    {
+                        files.push((path, None));
+                    }
+                }
+            }
+        }
+
+        for (path, sha1) in files {
+            let file = paths.minecraft_library_path(&path);
+            let detail = if !file.exists() {
+                format!("{path} missing from libraries")
+            } else if let Some(expected) = sha1 {
+                let actual = sha1_file(&file)?;
+                if actual.eq_ignore_ascii_case(expected) {
+                    continue;
+                }
+                format!("{path} hashes to sha1:{actual}, expected sha1:{expected}")
+            } else {
+                continue;
+            };
+            problems.push(InstanceProblem {
+                kind: ProblemKind::Library,
+                name: library.name.clone(),
+                detail,
+            });
+        }
+    }
+    Ok(problems)
+}
+
+fn version_json_hash(paths: &Paths, id: &str) -> Result<String> {
+    let hash = hash_file(&paths.minecraft_version_json(id))?;
+    Ok(format!("sha256:{hash}"))
+}
+
+fn locked_asset_index(version: &VersionJson) -> Option<LockedAssetIndex> {
+    version.asset_index.as_ref().map(|index| LockedAssetIndex {
+        id: index.id.clone(),
+        sha1: index.sha1.clone(),
+    })
+}
+
+fn locked_libraries(version: &VersionJson) -> Vec<LockedLibrary> {
+    version
+        .libraries
+        .iter()
+        .map(|library| {
+            let downloads = library.downloads.as_ref();
+            let natives = downloads
+                .and_then(|d| d.classifiers.as_ref())
+                .map(|classifiers| {
+                    classifiers
+                        .iter()
+                        .map(|(classifier, artifact)| (classifier.clone(), artifact.sha1.clone()))
+                        .collect::<BTreeMap<_, _>>()
+                })
+                .unwrap_or_default();
+            LockedLibrary {
+                name: library.name.clone(),
+                sha1: downloads
+                    .and_then(|d| d.artifact.as_ref())
+                    .map(|artifact| artifact.sha1.clone()),
+                natives,
+            }
+        })
+        .collect()
+}
+
+/// Resolve a declared Minecraft version, mapping "latest" to the newest release.
+fn resolve_mc_version(paths: &Paths, mc_version: &str) -> Result<String> {
+    if !mc_version.eq_ignore_ascii_case("latest") {
+        return Ok(mc_version.to_string());
+    }
+    let manifest = load_version_manifest(paths)?;
+    manifest
+        .latest
+        .map(|latest| latest.release)
+        .context("version manifest has no latest release")
+}
+
+/// Whether the Mojang version manifest lists `mc_version` ("latest" always is).
+pub fn is_known_mc_version(paths: &Paths, mc_version: &str) -> Result<bool> {
+    if mc_version.eq_ignore_ascii_case("latest") {
+        return Ok(true);
+    }
+    let manifest = load_version_manifest(paths)?;
+    Ok(manifest.versions.iter().any(|v| v.id == mc_version))
+}
+
+/// Resolve a declared loader, mapping "latest" to an exact loader version.
+fn resolve_loader_version(mc_version: &str, loader: &Loader) -> Result<Loader> {
+    if !loader.version.eq_ignore_ascii_case("latest") {
+        return Ok(loader.clone());
+    }
+    let version = match loader.loader_type.as_str() {
+        "fabric" => resolve_fabric_latest_version()?,
+        "quilt" => resolve_quilt_latest_version()?,
+        "neoforge" => resolve_neoforge_latest_version(mc_version)?,
+        "forge" => resolve_forge_latest_version(mc_version)?,
+        other => bail!("unsupported loader type: {other}"),
+    };
+    Ok(Loader {
+        loader_type: loader.loader_type.clone(),
+        version,
+    })
+}
+
 fn resolve_version_id(paths: &Paths, mc_version: &str, loader: Option<&Loader>, java: Option<&str>) -> Result<String> {
     match loader {
         None => Ok(mc_version.to_string()),
@@ -681,13 +1220,14 @@ fn ensure_libraries(
 fn build_args(
     version: &VersionJson,
     vars: &HashMap<String, String>,
+    ctx: &RuleContext,
 ) -> Result<(Vec<String>, Vec<String>)> {
     let mut jvm_args = Vec::new();
     let mut game_args = Vec::new();
 
     if let Some(arguments) = &version.arguments {
-        jvm_args.extend(collect_args(&arguments.jvm, vars));
-        game_args.extend(collect_args(&arguments.game, vars));
+        jvm_args.extend(collect_args(&arguments.jvm, vars, ctx));
+        game_args.extend(collect_args(&arguments.game, vars, ctx));
     } else if let Some(raw) = &version.minecraft_arguments {
         let parts = split(raw).context("failed to parse minecraftArguments")?;
         game_args.extend(parts.into_iter().map(|arg| substitute_vars(&arg, vars)));
@@ -696,14 +1236,17 @@ fn build_args(
     Ok((jvm_args, game_args))
 }
 
-fn collect_args(list: &[Argument], vars: &HashMap<String, String>) -> Vec<String> {
-    let ctx = RuleContext::new();
+fn collect_args(
+    list: &[Argument],
+    vars: &HashMap<String, String>,
+    ctx: &RuleContext,
+) -> Vec<String> {
     let mut out = Vec::new();
     for arg in list {
         match arg {
             Argument::Simple(value) => out.push(substitute_vars(value, vars)),
             Argument::WithRules { rules, value } => {
-                if rules_allow(rules, &ctx) {
+                if rules_allow(rules, ctx) {
                     match value {
                         ArgValue::Single(value) => out.push(substitute_vars(value, vars)),
                         ArgValue::Multiple(values) => {
@@ -717,6 +1260,129 @@ fn collect_args(list: &[Argument], vars: &HashMap<String, String>) -> Vec<String
     out
 }
 
+/// What the launch asks of the game beyond the version's defaults
+struct LaunchFeatures<'a> {
+    resolution: Option<(u32, u32)>,
+    fullscreen: bool,
+    demo: bool,
+    quick_play: Option<&'a QuickPlay>,
+    /// Where the game logs the Quick Play session (`${quickPlayPath}`)
+    quick_play_log: PathBuf,
+}
+
+impl LaunchFeatures<'_> {
+    /// Turn on the rule features the version declares for these settings and
+    /// add the variables their arguments use. Versions that do not declare one
+    /// get the equivalent game arguments, which are returned to be appended.
+    fn apply(
+        &self,
+        version: &VersionJson,
+        vars: &mut HashMap<String, String>,
+    ) -> Result<(RuleContext, Vec<String>)> {
+        let mut ctx = RuleContext::new();
+        let mut extra = Vec::new();
+        if let Some((width, height)) = self.resolution {
+            vars.insert("resolution_width".into(), width.to_string());
+            vars.insert("resolution_height".into(), height.to_string());
+            if declares_feature(version, "has_custom_resolution") {
+                ctx.enable("has_custom_resolution");
+            } else {
+                extra.extend([
+                    "--width".to_string(),
+                    width.to_string(),
+                    "--height".to_string(),
+                    height.to_string(),
+                ]);
+            }
+        }
+        if self.demo {
+            if declares_feature(version, "is_demo_user") {
+                ctx.enable("is_demo_user");
+            } else {
+                extra.push("--demo".to_string());
+            }
+        }
+        // No version declares a feature for fullscreen.
+        if self.fullscreen {
+            extra.push("--fullscreen".to_string());
+        }
+        if let Some(quick_play) = self.quick_play {
+            if declares_feature(version, quick_play.feature()) {
+                ctx.enable(quick_play.feature());
+                vars.insert(quick_play.var().into(), quick_play.target().to_string());
+                // Lets the game log the session to ${quickPlayPath}
+                if declares_feature(version, "has_quick_plays_support") {
+                    ctx.enable("has_quick_plays_support");
+                    vars.insert(
+                        "quickPlayPath".into(),
+                        normalize_path_separator(&self.quick_play_log.to_string_lossy()),
+                    );
+                }
+            } else {
+                extra.extend(legacy_quick_play_args(quick_play, &version.id)?);
+            }
+        }
+        Ok((ctx, extra))
+    }
+}
+
+/// Whether any game argument of the version is gated on `feature`
+fn declares_feature(version: &VersionJson, feature: &str) -> bool {
+    let Some(arguments) = &version.arguments else {
+        return false;
+    };
+    arguments.game.iter().any(|arg| match arg {
+        Argument::Simple(_) => false,
+        Argument::WithRules { rules, .. } => rules.iter().any(|rule| {
+            rule.features
+                .as_ref()
+                .is_some_and(|features| features.contains_key(feature))
+        }),
+    })
+}
+
+/// Game arguments for versions from before Quick Play (1.20), which can only
+/// join a server on start.
+fn legacy_quick_play_args(quick_play: &QuickPlay, version_id: &str) -> Result<Vec<String>> {
+    let QuickPlay::Server(address) = quick_play else {
+        bail!(
+            "{version_id} does not support Quick Play into a world or realm (needs 1.20 or newer)"
+        );
+    };
+    let (host, port) = split_server_address(address)?;
+    Ok(vec![
+        "--server".to_string(),
+        host.to_string(),
+        "--port".to_string(),
+        port.to_string(),
+    ])
+}
+
+/// Split `host[:port]` (with `[...]` around IPv6 hosts), defaulting to 25565.
+fn split_server_address(address: &str) -> Result<(&str, u16)> {
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest
+            .split_once(']')
+            .with_context(|| format!("invalid server address: {address}"))?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (address, None),
+        }
+    };
+    if host.is_empty() {
+        bail!("invalid server address: {address}");
+    }
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .with_context(|| format!("invalid port in server address: {address}"))?,
+        None => 25565,
+    };
+    Ok((host, port))
+}
+
 fn substitute_vars(value: &str, vars: &HashMap<String, String>) -> String {
     let mut out = value.to_string();
     for (key, val) in vars {
@@ -1054,9 +1720,16 @@ fn join_url(base: &str, path: &str) -> String {
 
 #[derive(Clone, Deserialize)]
 struct VersionManifest {
+    #[serde(default)]
+    latest: Option<LatestVersions>,
     versions: Vec<VersionEntry>,
 }
 
+#[derive(Clone, Deserialize)]
+struct LatestVersions {
+    release: String,
+}
+
 #[derive(Clone, Deserialize)]
 struct VersionEntry {
     id: String,
@@ -1171,6 +1844,10 @@ impl RuleContext {
             features,
         }
     }
+
+    fn enable(&mut self, feature: &str) {
+        self.features.insert(feature.to_string(), true);
+    }
 }
 
 #[derive(Clone, Deserialize)]
@@ -1327,3 +2004,147 @@ fn merge_versions(mut parent: VersionJson, mut child: VersionJson) -> VersionJso
 
     child
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_server_addresses() {
+        assert_eq!(
+            split_server_address("mc.example.net").unwrap(),
+            ("mc.example.net", 25565)
+        );
+        assert_eq!(
+            split_server_address("mc.example.net:25566").unwrap(),
+            ("mc.example.net", 25566)
+        );
+        assert_eq!(split_server_address("[::1]:25570").unwrap(), ("::1", 25570));
+        assert_eq!(split_server_address("[::1]").unwrap(), ("::1", 25565));
+        assert!(split_server_address(":25565").is_err());
+        assert!(split_server_address("mc.example.net:port").is_err());
+    }
+
+    #[test]
+    fn enables_declared_features_or_falls_back_to_args() {
+        let modern: VersionJson = serde_json::from_value(serde_json::json!({
+            "id": "1.21.4",
+            "arguments": {
+                "game": [
+                    "--username", "${auth_player_name}",
+                    {
+                        "rules": [{"action": "allow", "features": {"has_custom_resolution": true}}],
+                        "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]
+                    },
+                    {
+                        "rules": [{"action": "allow", "features": {"has_quick_plays_support": true}}],
+                        "value": ["--quickPlayPath", "${quickPlayPath}"]
+                    },
+                    {
+                        "rules": [{"action": "allow", "features": {"is_quick_play_multiplayer": true}}],
+                        "value": ["--quickPlayMultiplayer", "${quickPlayMultiplayer}"]
+                    }
+                ]
+            }
+        }))
+        .unwrap();
+        let quick_play = QuickPlay::Server("mc.example.net".to_string());
+        let features = LaunchFeatures {
+            resolution: Some((1280, 720)),
+            fullscreen: false,
+            demo: false,
+            quick_play: Some(&quick_play),
+            quick_play_log: PathBuf::from("quickPlay.json"),
+        };
+        let mut vars = HashMap::from([("auth_player_name".to_string(), "Steve".to_string())]);
+        let (ctx, extra) = features.apply(&modern, &mut vars).unwrap();
+        assert!(extra.is_empty());
+        let (_, game_args) = build_args(&modern, &vars, &ctx).unwrap();
+        assert_eq!(
+            game_args,
+            [
+                "--username",
+                "Steve",
+                "--width",
+                "1280",
+                "--height",
+                "720",
+                "--quickPlayPath",
+                "quickPlay.json",
+                "--quickPlayMultiplayer",
+                "mc.example.net"
+            ]
+        );
+
+        // Without a Quick Play target the game is not asked to log one
+        let plain = LaunchFeatures {
+            resolution: None,
+            fullscreen: false,
+            demo: false,
+            quick_play: None,
+            quick_play_log: PathBuf::from("quickPlay.json"),
+        };
+        let mut vars = HashMap::from([("auth_player_name".to_string(), "Steve".to_string())]);
+        let (ctx, _) = plain.apply(&modern, &mut vars).unwrap();
+        let (_, game_args) = build_args(&modern, &vars, &ctx).unwrap();
+        assert_eq!(game_args, ["--username", "Steve"]);
+
+        let legacy: VersionJson = serde_json::from_value(serde_json::json!({
+            "id": "1.12.2",
+            "minecraftArguments": "--username ${auth_player_name}"
+        }))
+        .unwrap();
+        let (_, extra) = features.apply(&legacy, &mut HashMap::new()).unwrap();
+        assert_eq!(
+            extra,
+            [
+                "--width",
+                "1280",
+                "--height",
+                "720",
+                "--server",
+                "mc.example.net",
+                "--port",
+                "25565"
+            ]
+        );
+        let world = QuickPlay::World("New World".to_string());
+        let features = LaunchFeatures {
+            quick_play: Some(&world),
+            ..features
+        };
+        assert!(features.apply(&legacy, &mut HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn redacts_access_token_from_command() {
+        let plan = LaunchPlan {
+            instance_dir: PathBuf::from("instance"),
+            materialized: MaterializeSummary::default(),
+            java_exec: "java".to_string(),
+            jvm_args: vec!["-Xmx2G".to_string()],
+            classpath: "a.jar".to_string(),
+            main_class: "net.minecraft.client.main.Main".to_string(),
+            game_args: vec![
+                "--accessToken".to_string(),
+                "secret".to_string(),
+                "--session".to_string(),
+                "token:secret:uuid".to_string(),
+            ],
+        };
+        assert_eq!(
+            plan.redacted_command("secret"),
+            [
+                "java",
+                "-Xmx2G",
+                "-cp",
+                "a.jar",
+                "net.minecraft.client.main.Main",
+                "--accessToken",
+                "<redacted>",
+                "--session",
+                "token:<redacted>:uuid",
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::paths::Paths;
-use crate::profile::{ContentRef, Loader, Profile, Runtime, create_profile, load_profile, save_profile, upsert_mod, upsert_resourcepack, upsert_shaderpack};
+use crate::profile::{ContentRef, Loader, Profile, Runtime, create_profile, load_profile, save_profile, upsert_datapack, upsert_mod, upsert_resourcepack, upsert_shaderpack};
 use crate::store::{ContentKind, store_content, store_from_url};
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
@@ -107,6 +107,7 @@ pub fn import_mrpack(paths: &Paths, pack_path: &Path, profile_id: Option<&str>)
                     ContentKind::Mod => { upsert_mod(&mut profile, content_ref); }
                     ContentKind::ResourcePack => { upsert_resourcepack(&mut profile, content_ref); }
                     ContentKind::ShaderPack => { upsert_shaderpack(&mut profile, content_ref); }
+                    ContentKind::Datapack => { upsert_datapack(&mut profile, content_ref); }
                     ContentKind::Skin => {}
                 }
             }
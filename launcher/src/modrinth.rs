@@ -14,6 +14,7 @@ pub enum ProjectType {
     Modpack,
     Resourcepack,
     Shader,
+    Datapack,
 }
 
 impl std::fmt::Display for ProjectType {
@@ -23,6 +24,7 @@ impl std::fmt::Display for ProjectType {
             ProjectType::Modpack => write!(f, "modpack"),
             ProjectType::Resourcepack => write!(f, "resourcepack"),
             ProjectType::Shader => write!(f, "shader"),
+            ProjectType::Datapack => write!(f, "datapack"),
         }
     }
 }
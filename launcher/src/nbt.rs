@@ -0,0 +1,405 @@
+//! Minimal reader and writer for Minecraft's NBT format.
+//!
+//! Only what the launcher needs for files such as `level.dat` and
+//! `servers.dat`: a named root compound in big-endian binary NBT, optionally
+//! gzip-compressed when read. Files are written uncompressed. Strings are
+//! Java's modified UTF-8, so names with NUL or characters outside the BMP
+//! (such as emoji) survive a read and write.
+
+use anyhow::{Context, Result, bail};
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Nesting limit for lists and compounds, as in the game
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+/// Named tags in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound(pub Vec<(String, Tag)>);
+
+impl Compound {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, tag)| tag)
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Any integer tag, widened
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            Tag::Byte(v) => Some(i64::from(*v)),
+            Tag::Short(v) => Some(i64::from(*v)),
+            Tag::Int(v) => Some(i64::from(*v)),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_compound(&self, name: &str) -> Option<&Compound> {
+        match self.get(name)? {
+            Tag::Compound(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Set a tag, keeping its position if it already exists.
+    pub fn insert(&mut self, name: &str, tag: Tag) {
+        match self.0.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = tag,
+            None => self.0.push((name.to_string(), tag)),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|(n, _)| n != name);
+    }
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+}
+
+/// Read an NBT file, gzip-compressed or not. Returns the root compound.
+pub fn read_file(path: &Path) -> Result<Compound> {
+    let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let data = if data.starts_with(&[0x1f, 0x8b]) {
+        let mut out = Vec::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_end(&mut out)
+            .with_context(|| format!("failed to decompress {}", path.display()))?;
+        out
+    } else {
+        data
+    };
+    parse(&data).with_context(|| format!("failed to parse NBT: {}", path.display()))
+}
+
+/// Write a root compound to an uncompressed NBT file. The file is replaced
+/// in one step, so the game never sees it half written.
+pub fn write_file(path: &Path, root: &Compound) -> Result<()> {
+    let data = to_bytes(root)?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, data).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Serialize a root compound (with an empty name) as uncompressed NBT.
+pub fn to_bytes(root: &Compound) -> Result<Vec<u8>> {
+    let mut out = vec![10];
+    write_string(&mut out, "")?;
+    write_payload(&mut out, &Tag::Compound(root.clone()))?;
+    Ok(out)
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) -> Result<()> {
+    let bytes = encode_modified_utf8(value);
+    let len = u16::try_from(bytes.len()).context("NBT string too long")?;
+    out.extend(len.to_be_bytes());
+    out.extend(bytes);
+    Ok(())
+}
+
+/// Encode as Java's modified UTF-8: NUL takes two bytes and characters
+/// outside the BMP are written as a surrogate pair of three bytes each.
+fn encode_modified_utf8(value: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7f => out.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                out.push(0xc0 | (unit >> 6) as u8);
+                out.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                out.push(0xe0 | (unit >> 12) as u8);
+                out.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                out.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    out
+}
+
+/// Decode Java's modified UTF-8. Four-byte sequences, which some tools write
+/// instead of surrogate pairs, are accepted too.
+fn decode_modified_utf8(bytes: &[u8]) -> Result<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while let Some(&first) = rest.first() {
+        let (len, mask) = match first {
+            0x00..=0x7f => (1, 0x7f),
+            0xc0..=0xdf => (2, 0x1f),
+            0xe0..=0xef => (3, 0x0f),
+            0xf0..=0xf7 => (4, 0x07),
+            _ => bail!("malformed modified UTF-8 string"),
+        };
+        if rest.len() < len || rest[1..len].iter().any(|b| b & 0xc0 != 0x80) {
+            bail!("malformed modified UTF-8 string");
+        }
+        let mut code = u32::from(first & mask);
+        for b in &rest[1..len] {
+            code = (code << 6) | u32::from(b & 0x3f);
+        }
+        if len == 4 {
+            let c = char::from_u32(code).context("malformed modified UTF-8 string")?;
+            units.extend(c.encode_utf16(&mut [0; 2]).iter());
+        } else {
+            units.push(code as u16);
+        }
+        rest = &rest[len..];
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) -> Result<()> {
+    let len = i32::try_from(len).context("NBT array too long")?;
+    out.extend(len.to_be_bytes());
+    Ok(())
+}
+
+fn write_payload(out: &mut Vec<u8>, tag: &Tag) -> Result<()> {
+    match tag {
+        Tag::Byte(v) => out.extend(v.to_be_bytes()),
+        Tag::Short(v) => out.extend(v.to_be_bytes()),
+        Tag::Int(v) => out.extend(v.to_be_bytes()),
+        Tag::Long(v) => out.extend(v.to_be_bytes()),
+        Tag::Float(v) => out.extend(v.to_be_bytes()),
+        Tag::Double(v) => out.extend(v.to_be_bytes()),
+        Tag::ByteArray(items) => {
+            write_len(out, items.len())?;
+            out.extend(items.iter().map(|b| *b as u8));
+        }
+        Tag::String(value) => write_string(out, value)?,
+        Tag::List(items) => {
+            // Empty lists are written with the End type, as the game does.
+            out.push(items.first().map_or(0, Tag::id));
+            write_len(out, items.len())?;
+            for item in items {
+                write_payload(out, item)?;
+            }
+        }
+        Tag::Compound(compound) => {
+            for (name, item) in &compound.0 {
+                out.push(item.id());
+                write_string(out, name)?;
+                write_payload(out, item)?;
+            }
+            out.push(0);
+        }
+        Tag::IntArray(items) => {
+            write_len(out, items.len())?;
+            for item in items {
+                out.extend(item.to_be_bytes());
+            }
+        }
+        Tag::LongArray(items) => {
+            write_len(out, items.len())?;
+            for item in items {
+                out.extend(item.to_be_bytes());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse uncompressed NBT data into its root compound.
+pub fn parse(data: &[u8]) -> Result<Compound> {
+    let mut reader = Reader { data, pos: 0 };
+    let id = reader.u8()?;
+    if id != 10 {
+        bail!("root tag is not a compound");
+    }
+    reader.string()?;
+    match reader.payload(id, 0)? {
+        Tag::Compound(root) => Ok(root),
+        _ => unreachable!(),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len());
+        let Some(end) = end else {
+            bail!("unexpected end of data");
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("slice has N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = i32::from_be_bytes(self.array()?);
+        usize::try_from(len).context("negative length")
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        decode_modified_utf8(self.take(len)?)
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag> {
+        if depth > MAX_DEPTH {
+            bail!("NBT nested too deeply");
+        }
+        Ok(match id {
+            1 => Tag::Byte(i8::from_be_bytes(self.array()?)),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let len = self.len()?;
+                Tag::ByteArray(self.take(len)?.iter().map(|b| *b as i8).collect())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_id = self.u8()?;
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.payload(item_id, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let item_id = self.u8()?;
+                    if item_id == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(item_id, depth + 1)?));
+                }
+                Tag::Compound(Compound(entries))
+            }
+            11 => {
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(i32::from_be_bytes(self.array()?));
+                }
+                Tag::IntArray(items)
+            }
+            12 => {
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(i64::from_be_bytes(self.array()?));
+                }
+                Tag::LongArray(items)
+            }
+            _ => bail!("unknown NBT tag type {id}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_compound() {
+        let mut data = vec![10, 0, 0];
+        data.extend([10, 0, 4]);
+        data.extend(b"Data");
+        data.extend([8, 0, 9]);
+        data.extend(b"LevelName");
+        data.extend([0, 5]);
+        data.extend(b"World");
+        data.extend([4, 0, 10]);
+        data.extend(b"LastPlayed");
+        data.extend(1_700_000_000_000i64.to_be_bytes());
+        data.extend([9, 0, 1, b'L', 3, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2]);
+        data.extend([0, 0]);
+
+        let root = parse(&data).unwrap();
+        let level = root.get_compound("Data").unwrap();
+        assert_eq!(level.get_str("LevelName"), Some("World"));
+        assert_eq!(level.get_i64("LastPlayed"), Some(1_700_000_000_000));
+        assert_eq!(
+            level.get("L"),
+            Some(&Tag::List(vec![Tag::Int(1), Tag::Int(2)]))
+        );
+
+        assert!(parse(&data[..data.len() - 3]).is_err());
+
+        assert_eq!(to_bytes(&root).unwrap(), data);
+    }
+
+    #[test]
+    fn round_trips_modified_utf8() {
+        let name = "Friends \u{1f3b2} \0 café";
+        let encoded = encode_modified_utf8(name);
+        // The emoji is a surrogate pair of two three-byte sequences, NUL is C0 80
+        assert!(encoded.windows(3).any(|w| w == [0xed, 0xa0, 0xbc]));
+        assert!(encoded.windows(2).any(|w| w == [0xc0, 0x80]));
+        assert!(!encoded.contains(&0));
+        assert_eq!(decode_modified_utf8(&encoded).unwrap(), name);
+
+        let root = Compound(vec![("name".to_string(), Tag::String(name.to_string()))]);
+        let parsed = parse(&to_bytes(&root).unwrap()).unwrap();
+        assert_eq!(parsed.get_str("name"), Some(name));
+
+        // Plain UTF-8 from other tools still reads
+        let plain = "Friends \u{1f3b2} café";
+        assert_eq!(decode_modified_utf8(plain.as_bytes()).unwrap(), plain);
+    }
+}
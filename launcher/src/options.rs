@@ -0,0 +1,191 @@
+//! `options.txt` handling.
+//!
+//! The game keeps its settings as `key:value` lines in `options.txt`. A profile
+//! declares the settings it cares about in `gameOptions`; those keys are written
+//! into the instance's `options.txt` whenever it is materialized, and every
+//! other line, including settings the player changed in-game, is left alone.
+//!
+//! The profile's resource pack order is written the same way, into
+//! `resourcePacks` and `incompatibleResourcePacks`.
+
+use anyhow::{Context, Result, bail};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Settings file in the instance directory
+pub const OPTIONS_FILE: &str = "options.txt";
+
+/// Reject keys and values that cannot be written as a single `key:value` line.
+pub fn validate_option(key: &str, value: &str) -> Result<()> {
+    if key.is_empty() || key.contains(':') || key.contains(['\n', '\r']) {
+        bail!("invalid option key: {key:?}");
+    }
+    if value.contains(['\n', '\r']) {
+        bail!("option value for {key} must be a single line");
+    }
+    Ok(())
+}
+
+/// Set `values` in an options file, keeping all other lines and their order.
+/// Keys the file does not have yet are appended; the file is created if
+/// missing. Returns the keys whose value changed.
+pub fn update_options_file(path: &Path, values: &BTreeMap<String, String>) -> Result<Vec<String>> {
+    update_key_values(path, ':', values)
+}
+
+/// Like `update_options_file`, for `key=value` files such as the shader
+/// loaders' `.properties` and `optionsshaders.txt`.
+pub fn update_properties_file(
+    path: &Path,
+    values: &BTreeMap<String, String>,
+) -> Result<Vec<String>> {
+    update_key_values(path, '=', values)
+}
+
+fn update_key_values(
+    path: &Path,
+    separator: char,
+    values: &BTreeMap<String, String>,
+) -> Result<Vec<String>> {
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+    let data = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let (out, changed) = merge_options(&data, separator, values);
+    if !changed.is_empty() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+        }
+        fs::write(path, out).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(changed)
+}
+
+/// The `resourcePacks` value that enables the given pack files, highest
+/// priority first. Built-in packs already selected in the options file (such
+/// as `vanilla` or `fabric`) stay at the bottom of the stack, and packs from
+/// the folder that are not given are deselected. Empty when there is nothing to
+/// change: no packs given and none from the folder selected.
+pub fn resource_pack_options(path: &Path, file_names: &[&str]) -> Result<BTreeMap<String, String>> {
+    let data = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    Ok(pack_order_values(&data, file_names))
+}
+
+fn pack_order_values(data: &str, file_names: &[&str]) -> BTreeMap<String, String> {
+    let current = read_list(data, "resourcePacks");
+    if file_names.is_empty() && !current.iter().any(|pack| pack.starts_with("file/")) {
+        return BTreeMap::new();
+    }
+    let mut selected: Vec<String> = current
+        .into_iter()
+        .filter(|pack| !pack.starts_with("file/"))
+        .collect();
+    if selected.is_empty() {
+        selected.push("vanilla".to_string());
+    }
+    // The game lists packs bottom to top, so the highest priority goes last.
+    selected.extend(file_names.iter().rev().map(|name| format!("file/{name}")));
+
+    BTreeMap::from([("resourcePacks".to_string(), json_list(&selected))])
+}
+
+fn read_list(data: &str, key: &str) -> Vec<String> {
+    data.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, value)| serde_json::from_str(value).ok())
+        .unwrap_or_default()
+}
+
+fn json_list(items: &[String]) -> String {
+    serde_json::to_string(items).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn merge_options(
+    data: &str,
+    separator: char,
+    values: &BTreeMap<String, String>,
+) -> (String, Vec<String>) {
+    let mut lines: Vec<String> = data.lines().map(str::to_string).collect();
+    let mut seen = HashSet::new();
+    let mut changed = Vec::new();
+    for line in &mut lines {
+        let Some((key, value)) = line.split_once(separator) else {
+            continue;
+        };
+        let Some(wanted) = values.get(key) else {
+            continue;
+        };
+        let key = key.to_string();
+        if value != wanted {
+            *line = format!("{key}{separator}{wanted}");
+            changed.push(key.clone());
+        }
+        seen.insert(key);
+    }
+    for (key, value) in values {
+        if !seen.contains(key) {
+            lines.push(format!("{key}{separator}{value}"));
+            changed.push(key.clone());
+        }
+    }
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    (out, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_managed_keys_only() {
+        let data = "version:3955\nrenderDistance:12\nguiScale:0\nkey_key.jump:key.keyboard.space\n";
+        let values = BTreeMap::from([
+            ("renderDistance".to_string(), "16".to_string()),
+            ("guiScale".to_string(), "0".to_string()),
+            ("fov".to_string(), "0.25".to_string()),
+        ]);
+
+        let (out, changed) = merge_options(data, ':', &values);
+        assert_eq!(
+            out,
+            "version:3955\nrenderDistance:16\nguiScale:0\nkey_key.jump:key.keyboard.space\nfov:0.25\n"
+        );
+        assert_eq!(changed, ["renderDistance", "fov"]);
+
+        let (_, changed) = merge_options(&out, ':', &values);
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn writes_pack_order_top_last() {
+        let data = "resourcePacks:[\"vanilla\",\"fabric\",\"file/old.zip\"]\nincompatibleResourcePacks:[]\n";
+        let values = pack_order_values(data, &["top.zip", "base.zip"]);
+        assert_eq!(
+            values["resourcePacks"],
+            r#"["vanilla","fabric","file/base.zip","file/top.zip"]"#
+        );
+        // Whether the game accepts a pack of another format is left to the player
+        assert!(!values.contains_key("incompatibleResourcePacks"));
+
+        let values = pack_order_values("", &["a.zip"]);
+        assert_eq!(values["resourcePacks"], r#"["vanilla","file/a.zip"]"#);
+
+        // Once the last pack is removed, its entry goes too
+        let values = pack_order_values(data, &[]);
+        assert_eq!(values["resourcePacks"], r#"["vanilla","fabric"]"#);
+        assert!(pack_order_values("resourcePacks:[\"vanilla\"]\n", &[]).is_empty());
+    }
+}
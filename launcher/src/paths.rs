@@ -1,12 +1,14 @@
 use anyhow::{Context, Result};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct Paths {
+    pub home: PathBuf,
     pub store_mods: PathBuf,
     pub store_resourcepacks: PathBuf,
     pub store_shaderpacks: PathBuf,
+    pub store_datapacks: PathBuf,
     pub store_skins: PathBuf,
     pub profiles: PathBuf,
     pub instances: PathBuf,
@@ -22,6 +24,10 @@ pub struct Paths {
     pub library_db: PathBuf,
     pub profile_organization: PathBuf,
     pub java_runtimes: PathBuf,
+    pub backups: PathBuf,
+    pub running: PathBuf,
+    pub snapshots: PathBuf,
+    pub migration_backups: PathBuf,
 }
 
 impl Paths {
@@ -36,10 +42,15 @@ impl Paths {
             let cwd = std::env::current_dir().context("failed to read current directory")?;
             base = cwd.join(base);
         }
+        Ok(Self::from_base(&base))
+    }
 
+    /// Paths for a launcher home at `base`, without consulting `SHARD_HOME`.
+    pub fn from_base(base: &Path) -> Self {
         let store_mods = base.join("store").join("mods").join("sha256");
         let store_resourcepacks = base.join("store").join("resourcepacks").join("sha256");
         let store_shaderpacks = base.join("store").join("shaderpacks").join("sha256");
+        let store_datapacks = base.join("store").join("datapacks").join("sha256");
         let store_skins = base.join("store").join("skins").join("sha256");
         let profiles = base.join("profiles");
         let instances = base.join("instances");
@@ -58,11 +69,17 @@ impl Paths {
         let library_db = base.join("library.db");
         let profile_organization = base.join("profile-organization.json");
         let java_runtimes = base.join("java");
+        let backups = base.join("backups");
+        let running = base.join("running");
+        let snapshots = base.join("snapshots");
+        let migration_backups = base.join("migrations");
 
-        Ok(Self {
+        Self {
+            home: base.to_path_buf(),
             store_mods,
             store_resourcepacks,
             store_shaderpacks,
+            store_datapacks,
             store_skins,
             profiles,
             instances,
@@ -78,7 +95,11 @@ impl Paths {
             library_db,
             profile_organization,
             java_runtimes,
-        })
+            backups,
+            running,
+            snapshots,
+            migration_backups,
+        }
     }
 
     pub fn ensure(&self) -> Result<()> {
@@ -88,6 +109,8 @@ impl Paths {
             .context("failed to create store/resourcepacks directory")?;
         std::fs::create_dir_all(&self.store_shaderpacks)
             .context("failed to create store/shaderpacks directory")?;
+        std::fs::create_dir_all(&self.store_datapacks)
+            .context("failed to create store/datapacks directory")?;
         std::fs::create_dir_all(&self.store_skins)
             .context("failed to create store/skins directory")?;
         std::fs::create_dir_all(&self.profiles).context("failed to create profiles directory")?;
@@ -118,14 +141,60 @@ impl Paths {
         self.profile_dir(id).join("profile.json")
     }
 
+    pub fn profile_local(&self, id: &str) -> PathBuf {
+        self.profile_dir(id).join("profile.local.json")
+    }
+
+    pub fn profile_lock(&self, id: &str) -> PathBuf {
+        self.profile_dir(id).join("profile.lock.json")
+    }
+
     pub fn profile_overrides(&self, id: &str) -> PathBuf {
         self.profile_dir(id).join("overrides")
     }
 
+    pub fn profile_shader_settings(&self, id: &str) -> PathBuf {
+        self.profile_dir(id).join("shaders")
+    }
+
+    pub fn profile_server_icons(&self, id: &str) -> PathBuf {
+        self.profile_dir(id).join("servers")
+    }
+
+    pub fn running_instance(&self, pid: u32) -> PathBuf {
+        self.running.join(format!("{pid}.json"))
+    }
+
+    pub fn profile_snapshots(&self, id: &str) -> PathBuf {
+        self.snapshots.join(id)
+    }
+
     pub fn instance_dir(&self, id: &str) -> PathBuf {
         self.instances.join(id)
     }
 
+    pub fn instance_saves(&self, id: &str) -> PathBuf {
+        self.instance_dir(id).join("saves")
+    }
+
+    /// Directory for shard's bookkeeping inside an instance
+    pub fn instance_state_dir(&self, id: &str) -> PathBuf {
+        self.instance_dir(id).join(".shard")
+    }
+
+    /// Backup manifests of one world
+    pub fn world_backups(&self, profile_id: &str, world: &str) -> PathBuf {
+        self.backups.join("worlds").join(profile_id).join(world)
+    }
+
+    pub fn backup_objects(&self) -> PathBuf {
+        self.backups.join("objects").join("sha256")
+    }
+
+    pub fn backup_object(&self, hash_hex: &str) -> PathBuf {
+        self.backup_objects().join(hash_hex)
+    }
+
     pub fn store_mod_path(&self, hash_hex: &str) -> PathBuf {
         self.store_mods.join(hash_hex)
     }
@@ -138,6 +207,10 @@ impl Paths {
         self.store_shaderpacks.join(hash_hex)
     }
 
+    pub fn store_datapack_path(&self, hash_hex: &str) -> PathBuf {
+        self.store_datapacks.join(hash_hex)
+    }
+
     pub fn store_skin_path(&self, hash_hex: &str) -> PathBuf {
         self.store_skins.join(hash_hex)
     }
@@ -1,13 +1,33 @@
+use crate::config::load_config;
+use crate::content_store::{ContentStore, Platform};
+use crate::inherit::{resolve_overrides, resolve_profile};
+use crate::instance::{LinkStrategy, MANAGED_DIRS};
+use crate::local::apply_local;
+use crate::migrate::{DocumentKind, PROFILE_SCHEMA_VERSION, load_document, parse_document};
+use crate::minecraft::is_known_mc_version;
 use crate::paths::Paths;
+use crate::servers::resolve_server_icon;
+use crate::snapshot::snapshot_profile;
+use crate::store::{ContentKind, content_store_path, normalize_hash};
 use crate::util::copy_dir_all;
 use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Profile {
+    /// On-disk format version (see `migrate`)
+    #[serde(rename = "schemaVersion", default)]
+    pub schema_version: u32,
     pub id: String,
-    #[serde(rename = "mcVersion")]
+    /// Base profile this one is layered on (see `inherit`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// May be left empty to inherit it from `extends`
+    #[serde(rename = "mcVersion", default)]
     pub mc_version: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub loader: Option<Loader>,
@@ -17,20 +37,91 @@ pub struct Profile {
     pub resourcepacks: Vec<ContentRef>,
     #[serde(default)]
     pub shaderpacks: Vec<ContentRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub datapacks: Vec<ContentRef>,
+    /// Worlds (folders in `saves/`) to install datapacks into. When empty they
+    /// go to the global datapack folder of a mod that provides one.
+    #[serde(
+        rename = "datapackWorlds",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub datapack_worlds: Vec<String>,
     #[serde(default)]
     pub runtime: Runtime,
+    /// Settings written into the instance's options.txt (see `options`)
+    #[serde(
+        rename = "gameOptions",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub game_options: BTreeMap<String, String>,
+    /// Shader pack (name or hash) selected in the shader loader (see `shaders`)
+    #[serde(
+        rename = "activeShader",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_shader: Option<String>,
+    /// Multiplayer servers merged into the instance's servers.dat (see `servers`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<Server>,
     #[serde(default)]
     pub files: Files,
+    /// Always launch in frozen mode (see `minecraft::LaunchOptions::frozen`);
+    /// `false` turns it off for a profile whose base enables it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<bool>,
+    /// How content is placed into the instance; falls back to the global config
+    #[serde(
+        rename = "linkStrategy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub link_strategy: Option<LinkStrategy>,
+    /// Inherited items to leave out
+    #[serde(default, skip_serializing_if = "Removals::is_empty")]
+    pub remove: Removals,
+}
+
+/// Items inherited from a base profile that a child profile drops. Content is
+/// matched by name, hash or platform project; overrides by path or directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Removals {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mods: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resourcepacks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shaderpacks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub datapacks: Vec<String>,
+    /// Servers, by address
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<String>,
+}
+
+impl Removals {
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty()
+            && self.resourcepacks.is_empty()
+            && self.shaderpacks.is_empty()
+            && self.datapacks.is_empty()
+            && self.servers.is_empty()
+            && self.overrides.is_empty()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Loader {
     #[serde(rename = "type")]
     pub loader_type: String,
     pub version: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ContentRef {
     pub name: String,
     pub hash: String,
@@ -57,6 +148,70 @@ pub struct ContentRef {
     pub pinned: bool,
 }
 
+/// An entry in the game's multiplayer server list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Server {
+    pub name: String,
+    /// Host, with an optional `:port`
+    pub address: String,
+    /// PNG file in the profile's `servers/` folder, shown next to the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(
+        rename = "resourcePack",
+        default,
+        skip_serializing_if = "ResourcePackPolicy::is_prompt"
+    )]
+    pub resource_pack: ResourcePackPolicy,
+}
+
+impl Server {
+    /// Whether `target` names this server, by address or name
+    pub fn matches(&self, target: &str) -> bool {
+        self.address.eq_ignore_ascii_case(target) || self.name == target
+    }
+}
+
+/// What the game does with a server's resource pack
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourcePackPolicy {
+    /// Ask when joining
+    #[default]
+    Prompt,
+    Enabled,
+    Disabled,
+}
+
+impl ResourcePackPolicy {
+    pub fn is_prompt(&self) -> bool {
+        *self == ResourcePackPolicy::Prompt
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResourcePackPolicy::Prompt => "prompt",
+            ResourcePackPolicy::Enabled => "enabled",
+            ResourcePackPolicy::Disabled => "disabled",
+        }
+    }
+}
+
+impl std::str::FromStr for ResourcePackPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "prompt" => Ok(ResourcePackPolicy::Prompt),
+            "enabled" => Ok(ResourcePackPolicy::Enabled),
+            "disabled" => Ok(ResourcePackPolicy::Disabled),
+            other => bail!(
+                "unknown resource pack policy: {other} (expected prompt, enabled or disabled)"
+            ),
+        }
+    }
+}
+
 fn is_false(b: &bool) -> bool {
     !*b
 }
@@ -69,7 +224,7 @@ fn is_true(b: &bool) -> bool {
     *b
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[derive(Default)]
 pub struct Runtime {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -78,10 +233,22 @@ pub struct Runtime {
     pub memory: Option<String>,
     #[serde(default)]
     pub args: Vec<String>,
+    /// Game window width; applies when height is set too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// Game window height; applies when width is set too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Start the game in fullscreen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullscreen: Option<bool>,
+    /// Launch in demo mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub demo: Option<bool>,
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Files {
     #[serde(default = "default_overrides")]
     pub config_overrides: String,
@@ -100,15 +267,22 @@ impl Default for Files {
 }
 
 pub fn load_profile(paths: &Paths, id: &str) -> Result<Profile> {
-    let path = paths.profile_json(id);
-    let data = fs::read_to_string(&path)
-        .with_context(|| format!("failed to read profile file: {}", path.display()))?;
-    let profile: Profile = serde_json::from_str(&data)
-        .with_context(|| format!("failed to parse profile JSON: {}", path.display()))?;
-    Ok(profile)
+    let strict = load_config(paths)?.strict_schema;
+    load_document(paths, DocumentKind::Profile, &paths.profile_json(id), strict)
+}
+
+/// Read a `profile.json` outside the profiles folder, such as a revision
+/// checked out by git. Older formats are migrated in memory only.
+pub fn read_profile_file(path: &Path) -> Result<Profile> {
+    let data =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse_document(DocumentKind::Profile, &data)
+        .with_context(|| format!("failed to parse {}", path.display()))
 }
 
+/// Write `profile.json`, snapshotting the previous version first.
 pub fn save_profile(paths: &Paths, profile: &Profile) -> Result<()> {
+    snapshot_profile(paths, &profile.id)?;
     let dir = paths.profile_dir(&profile.id);
     fs::create_dir_all(&dir)
         .with_context(|| format!("failed to create profile directory: {}", dir.display()))?;
@@ -152,14 +326,24 @@ pub fn create_profile(
         bail!("profile already exists: {id}");
     }
     let profile = Profile {
+        schema_version: PROFILE_SCHEMA_VERSION,
         id: id.to_string(),
+        extends: None,
         mc_version: mc_version.to_string(),
         loader,
         mods: Vec::new(),
         resourcepacks: Vec::new(),
         shaderpacks: Vec::new(),
+        datapacks: Vec::new(),
+        datapack_worlds: Vec::new(),
         runtime,
+        game_options: BTreeMap::new(),
+        active_shader: None,
+        servers: Vec::new(),
         files: Files::default(),
+        frozen: None,
+        link_strategy: None,
+        remove: Removals::default(),
     };
     save_profile(paths, &profile)?;
 
@@ -196,6 +380,14 @@ pub fn clone_profile(paths: &Paths, src: &str, dst: &str) -> Result<Profile> {
             )
         })?;
     }
+    let src_shaders = paths.profile_shader_settings(src);
+    if src_shaders.exists() {
+        copy_dir_all(&src_shaders, &paths.profile_shader_settings(dst))?;
+    }
+    let src_icons = paths.profile_server_icons(src);
+    if src_icons.exists() {
+        copy_dir_all(&src_icons, &paths.profile_server_icons(dst))?;
+    }
 
     Ok(profile)
 }
@@ -209,6 +401,12 @@ pub fn delete_profile(paths: &Paths, id: &str) -> Result<()> {
     fs::remove_dir_all(&profile_dir)
         .with_context(|| format!("failed to delete profile directory: {}", profile_dir.display()))?;
 
+    let snapshots_dir = paths.profile_snapshots(id);
+    if snapshots_dir.exists() {
+        fs::remove_dir_all(&snapshots_dir)
+            .with_context(|| format!("failed to delete snapshots: {}", snapshots_dir.display()))?;
+    }
+
     // Also remove the instance directory if it exists
     let instance_dir = paths.instances.join(id);
     if instance_dir.exists() {
@@ -242,6 +440,19 @@ pub fn rename_profile(paths: &Paths, id: &str, new_id: &str) -> Result<Profile>
     fs::rename(&old_dir, &new_dir)
         .with_context(|| format!("failed to rename profile directory: {} -> {}", old_dir.display(), new_dir.display()))?;
 
+    // Snapshots live outside the profile directory, so they move separately
+    let old_snapshots = paths.profile_snapshots(id);
+    if old_snapshots.exists() {
+        let new_snapshots = paths.profile_snapshots(new_id);
+        fs::rename(&old_snapshots, &new_snapshots).with_context(|| {
+            format!(
+                "failed to rename snapshots: {} -> {}",
+                old_snapshots.display(),
+                new_snapshots.display()
+            )
+        })?;
+    }
+
     // Save the profile with the new ID
     save_profile(paths, &profile)?;
 
@@ -281,6 +492,18 @@ fn remove_content(list: &mut Vec<ContentRef>, target: &str) -> bool {
     before != list.len()
 }
 
+fn move_content(list: &mut Vec<ContentRef>, target: &str, index: usize) -> bool {
+    let Some(from) = list
+        .iter()
+        .position(|m| m.name == target || m.hash == target)
+    else {
+        return false;
+    };
+    let item = list.remove(from);
+    list.insert(index.min(list.len()), item);
+    true
+}
+
 pub fn upsert_mod(profile: &mut Profile, new_mod: ContentRef) -> bool {
     upsert_content(&mut profile.mods, new_mod)
 }
@@ -293,6 +516,10 @@ pub fn upsert_shaderpack(profile: &mut Profile, new_pack: ContentRef) -> bool {
     upsert_content(&mut profile.shaderpacks, new_pack)
 }
 
+pub fn upsert_datapack(profile: &mut Profile, new_pack: ContentRef) -> bool {
+    upsert_content(&mut profile.datapacks, new_pack)
+}
+
 pub fn remove_mod(profile: &mut Profile, target: &str) -> bool {
     remove_content(&mut profile.mods, target)
 }
@@ -305,17 +532,36 @@ pub fn remove_shaderpack(profile: &mut Profile, target: &str) -> bool {
     remove_content(&mut profile.shaderpacks, target)
 }
 
-pub fn diff_profiles(a: &Profile, b: &Profile) -> (Vec<String>, Vec<String>, Vec<String>) {
-    use std::collections::BTreeSet;
+pub fn remove_datapack(profile: &mut Profile, target: &str) -> bool {
+    remove_content(&mut profile.datapacks, target)
+}
 
-    let set_a: BTreeSet<String> = a.mods.iter().map(|m| m.name.clone()).collect();
-    let set_b: BTreeSet<String> = b.mods.iter().map(|m| m.name.clone()).collect();
+/// Add a server, or replace the one with the same address. Returns true if it
+/// was added rather than replaced.
+pub fn upsert_server(profile: &mut Profile, server: Server) -> bool {
+    if let Some(existing) = profile
+        .servers
+        .iter_mut()
+        .find(|s| s.address.eq_ignore_ascii_case(&server.address))
+    {
+        *existing = server;
+        return false;
+    }
+    profile.servers.push(server);
+    true
+}
 
-    let only_a = set_a.difference(&set_b).cloned().collect::<Vec<_>>();
-    let only_b = set_b.difference(&set_a).cloned().collect::<Vec<_>>();
-    let both = set_a.intersection(&set_b).cloned().collect::<Vec<_>>();
+/// Remove a server by address or name
+pub fn remove_server(profile: &mut Profile, target: &str) -> bool {
+    let before = profile.servers.len();
+    profile.servers.retain(|s| !s.matches(target));
+    before != profile.servers.len()
+}
 
-    (only_a, only_b, both)
+/// Move a resource pack (by name or hash) to `index` in the load order, where
+/// 0 is the highest priority. Returns false if the pack is not in the profile.
+pub fn move_resourcepack(profile: &mut Profile, target: &str, index: usize) -> bool {
+    move_content(&mut profile.resourcepacks, target, index)
 }
 
 /// Shader loader types that can be detected in a profile
@@ -377,4 +623,484 @@ impl Profile {
     pub fn primary_shader_loader(&self) -> Option<ShaderLoader> {
         self.detect_shader_loaders().into_iter().next()
     }
+
+    /// Detect a mod that loads datapacks into every world from a global folder.
+    pub fn datapack_loader(&self) -> Option<DatapackLoader> {
+        self.mods.iter().find_map(|mod_ref| {
+            let name_lower = mod_ref.name.to_lowercase();
+            let source_lower = mod_ref.source.as_deref().unwrap_or("").to_lowercase();
+            if name_lower.contains("paxi") || source_lower.contains("/paxi") {
+                Some(DatapackLoader::Paxi)
+            } else if name_lower.contains("global packs")
+                || name_lower.contains("globalpacks")
+                || source_lower.contains("/globalpacks")
+            {
+                Some(DatapackLoader::GlobalPacks)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Mods that load datapacks from a folder outside the world saves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatapackLoader {
+    Paxi,
+    GlobalPacks,
+}
+
+impl DatapackLoader {
+    /// Folder the mod loads datapacks from, relative to the instance
+    pub fn dir(self) -> &'static str {
+        match self {
+            DatapackLoader::Paxi => "config/paxi/datapacks",
+            DatapackLoader::GlobalPacks => "global_packs/required_data",
+        }
+    }
+}
+
+/// Loader types `minecraft::prepare` knows how to install
+pub const LOADER_TYPES: [&str; 4] = ["fabric", "quilt", "forge", "neoforge"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+/// A problem found by `check_profile`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileIssue {
+    pub severity: IssueSeverity,
+    /// Stable identifier for the kind of problem, e.g. "duplicate-content"
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for ProfileIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            IssueSeverity::Error => "error",
+            IssueSeverity::Warning => "warning",
+        };
+        write!(f, "{severity}[{}]: {}", self.code, self.message)
+    }
+}
+
+/// Validate a profile as it is launched: with its `extends` chain resolved
+/// and local overlays applied. With a content store, the Minecraft version and each item's platform version are also
+/// looked up online; without one only local checks run.
+pub fn check_profile(
+    paths: &Paths,
+    profile: &Profile,
+    store: Option<&ContentStore>,
+) -> Result<Vec<ProfileIssue>> {
+    let mut issues = Vec::new();
+    let mut push = |severity, code, message: String| {
+        issues.push(ProfileIssue {
+            severity,
+            code,
+            message,
+        })
+    };
+    let mut resolved = match resolve_profile(paths, profile) {
+        Ok(resolved) => resolved,
+        // A missing base or a cycle leaves nothing to check the rest against
+        Err(err) => {
+            push(IssueSeverity::Error, "invalid-extends", format!("{err:#}"));
+            return Ok(issues);
+        }
+    };
+    apply_local(paths, &mut resolved)?;
+
+    if resolved.mc_version.is_empty() {
+        push(
+            IssueSeverity::Error,
+            "unknown-mc-version",
+            "mcVersion is not set".to_string(),
+        );
+    } else if store.is_some() && !is_known_mc_version(paths, &resolved.mc_version)? {
+        push(
+            IssueSeverity::Error,
+            "unknown-mc-version",
+            format!(
+                "mcVersion {} is not in the Mojang version manifest",
+                resolved.mc_version
+            ),
+        );
+    }
+    if let Some(loader) = &resolved.loader
+        && !LOADER_TYPES.contains(&loader.loader_type.as_str())
+    {
+        push(
+            IssueSeverity::Error,
+            "unknown-loader",
+            format!(
+                "unknown loader type {} (expected one of {})",
+                loader.loader_type,
+                LOADER_TYPES.join(", ")
+            ),
+        );
+    }
+    if let Some(memory) = &resolved.runtime.memory
+        && !is_valid_memory(memory)
+    {
+        push(
+            IssueSeverity::Error,
+            "invalid-memory",
+            format!("runtime.memory {memory:?} is not a JVM size like 4G or 4096M"),
+        );
+    }
+
+    if resolved.runtime.width.is_some() != resolved.runtime.height.is_some() {
+        push(
+            IssueSeverity::Warning,
+            "incomplete-resolution",
+            "runtime.width and runtime.height only apply when both are set".to_string(),
+        );
+    }
+    if resolved.runtime.width == Some(0) || resolved.runtime.height == Some(0) {
+        push(
+            IssueSeverity::Error,
+            "invalid-resolution",
+            "runtime.width and runtime.height must be greater than 0".to_string(),
+        );
+    }
+
+    // Layering only collapses items with the same name or project, so a base
+    // and a child can still bring in the same file under different names
+    let layered = [
+        ("mods", &resolved.mods),
+        ("resourcepacks", &resolved.resourcepacks),
+        ("shaderpacks", &resolved.shaderpacks),
+        ("datapacks", &resolved.datapacks),
+    ];
+    for (label, items) in layered {
+        for (idx, item) in items.iter().enumerate() {
+            let earlier = &items[..idx];
+            if let Some(other) = earlier
+                .iter()
+                .find(|other| normalize_hash(&other.hash) == normalize_hash(&item.hash))
+            {
+                push(
+                    IssueSeverity::Error,
+                    "duplicate-content",
+                    format!("{label}: {} has the same hash as {}", item.name, other.name),
+                );
+            } else if let Some(other) = earlier.iter().find(|other| {
+                item.project_id.is_some()
+                    && other.platform == item.platform
+                    && other.project_id == item.project_id
+            }) {
+                push(
+                    IssueSeverity::Error,
+                    "duplicate-content",
+                    format!(
+                        "{label}: {} is the same project as {}",
+                        item.name, other.name
+                    ),
+                );
+            }
+        }
+    }
+
+    let lists = [
+        (ContentKind::Mod, "mods", &resolved.mods),
+        (
+            ContentKind::ResourcePack,
+            "resourcepacks",
+            &resolved.resourcepacks,
+        ),
+        (
+            ContentKind::ShaderPack,
+            "shaderpacks",
+            &resolved.shaderpacks,
+        ),
+        (ContentKind::Datapack, "datapacks", &resolved.datapacks),
+    ];
+    for (kind, label, items) in lists {
+        for item in items {
+            if !content_store_path(paths, kind, &item.hash).exists() {
+                let fetchable = (item.platform.is_some() && item.version_id.is_some())
+                    || item
+                        .source
+                        .as_deref()
+                        .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"));
+                let (severity, hint) = if fetchable {
+                    (IssueSeverity::Warning, "run `shard profile fetch`")
+                } else {
+                    (IssueSeverity::Error, "and it has no download source")
+                };
+                push(
+                    severity,
+                    "missing-blob",
+                    format!("{label}: {} is not in the store ({hint})", item.name),
+                );
+            }
+
+            if let Some(store) = store {
+                check_platform_version(store, &resolved, kind, label, item, &mut push);
+            }
+        }
+    }
+
+    if !resolved.datapacks.is_empty()
+        && resolved.datapack_worlds.is_empty()
+        && resolved.datapack_loader().is_none()
+    {
+        push(
+            IssueSeverity::Warning,
+            "no-datapack-target",
+            "datapacks are not installed anywhere: set datapackWorlds or add a global datapack mod (Paxi, Global Packs)".to_string(),
+        );
+    }
+    for world in &resolved.datapack_worlds {
+        if !is_valid_world_name(world) {
+            push(
+                IssueSeverity::Error,
+                "invalid-world",
+                format!("datapackWorlds: {world:?} is not a world folder name"),
+            );
+        }
+    }
+
+    if let Some(active) = &resolved.active_shader {
+        if !resolved
+            .shaderpacks
+            .iter()
+            .any(|item| item.name == *active || item.hash == *active)
+        {
+            push(
+                IssueSeverity::Error,
+                "unknown-active-shader",
+                format!("activeShader {active} is not one of the profile's shader packs"),
+            );
+        } else if !matches!(
+            resolved.primary_shader_loader(),
+            Some(ShaderLoader::Iris | ShaderLoader::Optifine)
+        ) {
+            push(
+                IssueSeverity::Warning,
+                "no-shader-loader",
+                format!("activeShader {active} is set but the profile has no Iris or OptiFine mod"),
+            );
+        }
+    }
+
+    for (idx, server) in resolved.servers.iter().enumerate() {
+        if server.address.trim().is_empty() {
+            push(
+                IssueSeverity::Error,
+                "invalid-server",
+                format!("servers: {} has no address", server.name),
+            );
+        } else if resolved.servers[..idx]
+            .iter()
+            .any(|other| other.address.eq_ignore_ascii_case(&server.address))
+        {
+            push(
+                IssueSeverity::Error,
+                "duplicate-server",
+                format!("servers: {} is listed more than once", server.address),
+            );
+        }
+    }
+    for server in &resolved.servers {
+        if let Some(icon) = &server.icon
+            && resolve_server_icon(paths, profile, icon)?.is_none()
+        {
+            push(
+                IssueSeverity::Warning,
+                "missing-server-icon",
+                format!(
+                    "servers: icon {icon} for {} is not in the profile's servers/ folder",
+                    server.address
+                ),
+            );
+        }
+    }
+
+    for rel in resolve_overrides(paths, profile)?.keys() {
+        if let Some(dir) = MANAGED_DIRS
+            .iter()
+            .find(|dir| rel.starts_with(&format!("{dir}/")))
+        {
+            push(
+                IssueSeverity::Warning,
+                "override-in-managed-dir",
+                format!(
+                    "override {rel} is inside {dir}/, which is rebuilt from the profile's content"
+                ),
+            );
+        }
+    }
+
+    Ok(issues)
+}
+
+fn check_platform_version(
+    store: &ContentStore,
+    profile: &Profile,
+    kind: ContentKind,
+    label: &str,
+    item: &ContentRef,
+    push: &mut impl FnMut(IssueSeverity, &'static str, String),
+) {
+    let (Some(platform), Some(project_id), Some(version_id)) = (
+        item.platform.as_deref().and_then(Platform::parse),
+        item.project_id.as_deref(),
+        item.version_id.as_deref(),
+    ) else {
+        return;
+    };
+    let version = match store.get_version(platform, project_id, version_id) {
+        Ok(version) => version,
+        Err(err) => {
+            push(
+                IssueSeverity::Warning,
+                "lookup-failed",
+                format!("{label}: could not look up {}: {err}", item.name),
+            );
+            return;
+        }
+    };
+
+    let mc_version = &profile.mc_version;
+    if !mc_version.eq_ignore_ascii_case("latest")
+        && !version.game_versions.is_empty()
+        && !version.game_versions.contains(mc_version)
+    {
+        push(
+            IssueSeverity::Error,
+            "incompatible-content",
+            format!(
+                "{label}: {} {} does not support Minecraft {mc_version}",
+                item.name, version.version
+            ),
+        );
+    }
+    if matches!(kind, ContentKind::Mod)
+        && let Some(loader) = &profile.loader
+        && !version.loaders.is_empty()
+    {
+        let loader_type = loader.loader_type.as_str();
+        // Quilt loads Fabric mods
+        let supported = version.loaders.iter().any(|l| {
+            l.eq_ignore_ascii_case(loader_type)
+                || (loader_type == "quilt" && l.eq_ignore_ascii_case("fabric"))
+        });
+        if !supported {
+            push(
+                IssueSeverity::Error,
+                "incompatible-content",
+                format!(
+                    "{label}: {} {} does not support {loader_type}",
+                    item.name, version.version
+                ),
+            );
+        }
+    }
+}
+
+/// JVM heap sizes as accepted by -Xmx: digits with an optional k, m, g or t
+/// suffix.
+fn is_valid_memory(value: &str) -> bool {
+    let digits = value.trim_end_matches(['k', 'K', 'm', 'M', 'g', 'G', 't', 'T']);
+    value.len() - digits.len() <= 1
+        && !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && digits.bytes().any(|b| b != b'0')
+}
+
+/// A single folder name inside `saves/`
+pub fn is_valid_world_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(paths: &Paths, value: serde_json::Value) -> Vec<&'static str> {
+        let profile: Profile = serde_json::from_value(value).unwrap();
+        save_profile(paths, &profile).unwrap();
+        check_profile(paths, &profile, None)
+            .unwrap()
+            .into_iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .map(|issue| issue.code)
+            .collect()
+    }
+
+    #[test]
+    fn reports_duplicates_brought_in_through_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        check(
+            &paths,
+            json!({
+                "id": "base",
+                "mcVersion": "1.21.4",
+                "mods": [{ "name": "sodium", "hash": "sha256:aa" }]
+            }),
+        );
+        let codes = check(
+            &paths,
+            json!({
+                "id": "child",
+                "extends": "base",
+                "mods": [{
+                    "name": "Sodium", "hash": "sha256:aa",
+                    "platform": "modrinth", "project_id": "AANobbMI"
+                }]
+            }),
+        );
+        assert!(codes.contains(&"duplicate-content"), "{codes:?}");
+    }
+
+    #[test]
+    fn reports_shader_and_world_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let codes = check(
+            &paths,
+            json!({
+                "id": "a",
+                "mcVersion": "1.21.4",
+                "activeShader": "Complementary",
+                "datapackWorlds": ["../World", "New World"]
+            }),
+        );
+        let count = |code: &str| codes.iter().filter(|c| **c == code).count();
+        assert_eq!(count("unknown-active-shader"), 1);
+        assert_eq!(count("invalid-world"), 1);
+    }
+
+    #[test]
+    fn reports_inheritance_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        check(
+            &paths,
+            json!({ "id": "a", "extends": "b", "mcVersion": "1.21.4" }),
+        );
+        let codes = check(
+            &paths,
+            json!({ "id": "b", "extends": "a", "mcVersion": "1.21.4" }),
+        );
+        assert_eq!(codes, ["invalid-extends"]);
+    }
+
+    #[test]
+    fn validates_memory_sizes() {
+        for valid in ["4G", "4096M", "512m", "1t", "2048"] {
+            assert!(is_valid_memory(valid), "{valid}");
+        }
+        for invalid in ["4GB", "G", "", "0G", "-4G", "4.5G"] {
+            assert!(!is_valid_memory(invalid), "{invalid}");
+        }
+    }
 }
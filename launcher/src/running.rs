@@ -0,0 +1,326 @@
+//! Registry of running game processes.
+//!
+//! Every launched client gets a record in `running/<pid>.json` that is removed
+//! when the launcher sees the process exit. A launcher that dies first leaves
+//! its record behind, and after a reboot its pid may belong to an unrelated
+//! process. A record only counts while its pid still runs the recorded main
+//! class (the recorded Java executable on Windows); any other record is
+//! dropped whenever the registry is read, so `shard ps`, `shard kill` and the
+//! launch check never trust or signal a stale entry.
+//!
+//! Launching holds `running/<profile>.lock` from the running check until the
+//! game is registered, so two launches of one profile cannot both pass it.
+
+use crate::paths::Paths;
+use crate::util::now_epoch_secs;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// A game process started by the launcher
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningInstance {
+    pub profile: String,
+    /// Username of the account playing
+    pub account: String,
+    pub pid: u32,
+    /// Seconds since the Unix epoch
+    pub started: u64,
+    /// Java executable and main class; game arguments are left out since
+    /// they carry the access token
+    pub java: String,
+    pub main_class: String,
+}
+
+impl RunningInstance {
+    pub fn new(profile: &str, account: &str, pid: u32, java: &str, main_class: &str) -> Self {
+        Self {
+            profile: profile.to_string(),
+            account: account.to_string(),
+            pid,
+            started: now_epoch_secs(),
+            java: java.to_string(),
+            main_class: main_class.to_string(),
+        }
+    }
+}
+
+/// Record a started process.
+pub fn register(paths: &Paths, instance: &RunningInstance) -> Result<()> {
+    fs::create_dir_all(&paths.running)
+        .with_context(|| format!("failed to create dir: {}", paths.running.display()))?;
+    let path = paths.running_instance(instance.pid);
+    let data =
+        serde_json::to_string_pretty(instance).context("failed to serialize running instance")?;
+    fs::write(&path, data).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Forget a process once it has exited.
+pub fn unregister(paths: &Paths, pid: u32) -> Result<()> {
+    let path = paths.running_instance(pid);
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// All running instances, oldest first. Records of processes that are gone
+/// are removed.
+pub fn list_running(paths: &Paths) -> Result<Vec<RunningInstance>> {
+    let mut running = Vec::new();
+    if !paths.running.is_dir() {
+        return Ok(running);
+    }
+    for entry in fs::read_dir(&paths.running)
+        .with_context(|| format!("failed to read dir: {}", paths.running.display()))?
+    {
+        let path = entry.context("failed to read dir entry")?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let instance: Option<RunningInstance> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok());
+        match instance {
+            Some(instance) if is_game(&instance) => running.push(instance),
+            _ => {
+                fs::remove_file(&path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+            }
+        }
+    }
+    running.sort_by_key(|instance| (instance.started, instance.pid));
+    Ok(running)
+}
+
+/// Running instances of one profile.
+pub fn running_instances(paths: &Paths, profile_id: &str) -> Result<Vec<RunningInstance>> {
+    let mut running = list_running(paths)?;
+    running.retain(|instance| instance.profile == profile_id);
+    Ok(running)
+}
+
+/// Fail if the profile is already running.
+pub fn ensure_not_running(paths: &Paths, profile_id: &str) -> Result<()> {
+    if let Some(instance) = running_instances(paths, profile_id)?.first() {
+        bail!(
+            "profile {profile_id} is already running (pid {}); stop it with `shard kill {profile_id}` or launch with --force",
+            instance.pid
+        );
+    }
+    Ok(())
+}
+
+/// Claim on launching a profile, released when dropped
+#[derive(Debug)]
+pub struct LaunchLock {
+    path: PathBuf,
+}
+
+impl Drop for LaunchLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Fail if the profile is running or another launch of it is under way;
+/// otherwise hold the profile's launch lock until the returned guard is
+/// dropped, which should happen once the game is registered.
+pub fn claim_launch(paths: &Paths, profile_id: &str) -> Result<LaunchLock> {
+    fs::create_dir_all(&paths.running)
+        .with_context(|| format!("failed to create dir: {}", paths.running.display()))?;
+    let path = paths.running.join(format!("{profile_id}.lock"));
+    // The pid is written before the lock appears, so a reader never sees an
+    // empty lock
+    let pending = paths
+        .running
+        .join(format!("{profile_id}.lock.{}.tmp", std::process::id()));
+    fs::write(&pending, std::process::id().to_string())
+        .with_context(|| format!("failed to write {}", pending.display()))?;
+    let linked = loop {
+        match fs::hard_link(&pending, &path) {
+            Ok(()) => break Ok(()),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|data| data.trim().parse::<u32>().ok());
+                if let Some(pid) = holder.filter(|pid| is_alive(*pid)) {
+                    break Err(anyhow::anyhow!(
+                        "profile {profile_id} is already being launched (launcher pid {pid})"
+                    ));
+                }
+                // The launcher holding it died before registering its game
+                if let Err(err) = fs::remove_file(&path)
+                    && err.kind() != ErrorKind::NotFound
+                {
+                    break Err(err).with_context(|| format!("failed to remove {}", path.display()));
+                }
+            }
+            Err(err) => {
+                break Err(err).with_context(|| format!("failed to create {}", path.display()));
+            }
+        }
+    };
+    let _ = fs::remove_file(&pending);
+    linked?;
+    let lock = LaunchLock { path };
+    ensure_not_running(paths, profile_id)?;
+    Ok(lock)
+}
+
+/// Stop every running instance of a profile, killing outright with `force`
+/// instead of asking the game to close. Returns the instances signalled.
+pub fn kill_instances(
+    paths: &Paths,
+    profile_id: &str,
+    force: bool,
+) -> Result<Vec<RunningInstance>> {
+    let running = running_instances(paths, profile_id)?;
+    if running.is_empty() {
+        bail!("profile {profile_id} is not running");
+    }
+    for instance in &running {
+        terminate(instance.pid, force)?;
+    }
+    Ok(running)
+}
+
+/// Whether the record's pid still runs the game it was recorded for.
+fn is_game(instance: &RunningInstance) -> bool {
+    process_matches(instance.pid, &instance.java, &instance.main_class)
+}
+
+#[cfg(target_os = "linux")]
+fn process_matches(pid: u32, _java: &str, main_class: &str) -> bool {
+    fs::read(format!("/proc/{pid}/cmdline")).is_ok_and(|cmdline| {
+        cmdline
+            .split(|byte| *byte == 0)
+            .any(|arg| arg == main_class.as_bytes())
+    })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_matches(pid: u32, _java: &str, main_class: &str) -> bool {
+    Command::new("ps")
+        .args(["-ww", "-o", "command=", "-p", &pid.to_string()])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stdout)
+                    .split_whitespace()
+                    .any(|arg| arg == main_class)
+        })
+}
+
+#[cfg(windows)]
+fn process_matches(pid: u32, java: &str, _main_class: &str) -> bool {
+    let Some(expected) = std::path::Path::new(java).file_stem() else {
+        return false;
+    };
+    let expected = expected.to_string_lossy();
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH", "/FO", "CSV"])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut fields = stdout
+                .trim()
+                .split(',')
+                .map(|field| field.trim_matches('"'));
+            let image = fields.next().unwrap_or_default();
+            fields.next() == Some(pid.to_string().as_str())
+                && std::path::Path::new(image)
+                    .file_stem()
+                    .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(&expected))
+        })
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(windows)]
+fn is_alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH", "/FO", "CSV"])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{pid}\"")))
+}
+
+#[cfg(unix)]
+fn terminate(pid: u32, force: bool) -> Result<()> {
+    let signal = if force { "-KILL" } else { "-TERM" };
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .status()
+        .context("failed to run kill")?;
+    if !status.success() {
+        bail!("failed to stop process {pid}");
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn terminate(pid: u32, force: bool) -> Result<()> {
+    let mut command = Command::new("taskkill");
+    command.args(["/PID", &pid.to_string()]);
+    if force {
+        command.arg("/F");
+    }
+    let status = command.status().context("failed to run taskkill")?;
+    if !status.success() {
+        bail!("failed to stop process {pid}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn drops_records_of_other_processes() {
+        let dir = tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        // The test process is alive but is not a game with this main class
+        let stale = RunningInstance::new(
+            "demo",
+            "Steve",
+            std::process::id(),
+            "java",
+            "net.minecraft.client.main.Main",
+        );
+        register(&paths, &stale).unwrap();
+
+        assert!(list_running(&paths).unwrap().is_empty());
+        assert!(!paths.running_instance(stale.pid).exists());
+        assert!(kill_instances(&paths, "demo", false).is_err());
+    }
+
+    #[test]
+    fn one_launch_holds_a_profile_at_a_time() {
+        let dir = tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let lock = claim_launch(&paths, "demo").unwrap();
+        assert!(claim_launch(&paths, "demo").is_err());
+        assert!(claim_launch(&paths, "other").is_ok());
+        drop(lock);
+        drop(claim_launch(&paths, "demo").unwrap());
+
+        // A lock left by a launcher that is gone does not block launches
+        fs::write(paths.running.join("demo.lock"), "not a pid").unwrap();
+        claim_launch(&paths, "demo").unwrap();
+    }
+}
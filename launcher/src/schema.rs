@@ -0,0 +1,87 @@
+//! JSON Schemas for the hand-edited file formats.
+//!
+//! Schemas are generated from the Rust types, so they follow their serde
+//! attributes (renames, defaults, the tagged `ContentSource` enum). Objects are
+//! closed with `additionalProperties: false` so editors flag misspelled keys,
+//! which serde would otherwise drop silently.
+
+use crate::config::Config;
+use crate::profile::Profile;
+use crate::template::Template;
+use anyhow::{Result, bail};
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde_json::Value;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    Profile,
+    Template,
+    Config,
+}
+
+impl SchemaKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SchemaKind::Profile => "profile",
+            SchemaKind::Template => "template",
+            SchemaKind::Config => "config",
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for SchemaKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "profile" => Ok(SchemaKind::Profile),
+            "template" => Ok(SchemaKind::Template),
+            "config" => Ok(SchemaKind::Config),
+            _ => bail!("unknown schema kind: {value} (expected profile, template or config)"),
+        }
+    }
+}
+
+/// The JSON Schema (draft-07, which editors support best) for a file format.
+pub fn json_schema(kind: SchemaKind) -> Value {
+    let mut schema = match kind {
+        SchemaKind::Profile => schema_for::<Profile>(),
+        SchemaKind::Template => schema_for::<Template>(),
+        SchemaKind::Config => schema_for::<Config>(),
+    };
+    close_objects(&mut schema);
+    schema
+}
+
+fn schema_for<T: JsonSchema>() -> Value {
+    SchemaSettings::draft07()
+        .for_deserialize()
+        .into_generator()
+        .into_root_schema_for::<T>()
+        .to_value()
+}
+
+/// Set `additionalProperties: false` on every object schema that lists its
+/// properties and does not say otherwise.
+fn close_objects(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if map.contains_key("properties") && !map.contains_key("additionalProperties") {
+                map.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            for child in map.values_mut() {
+                close_objects(child);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(close_objects),
+        _ => {}
+    }
+}
@@ -0,0 +1,337 @@
+//! Multiplayer server list.
+//!
+//! A profile's `servers` are merged into the instance's `servers.dat`, the
+//! uncompressed NBT file behind the game's multiplayer screen. Entries the
+//! player added in-game are left alone, even when the profile declares the
+//! same address. Entries the launcher added are tracked in
+//! `.shard/servers.json`, so those dropped from the profile are removed on the
+//! next materialize while the player's own entries stay. Managed servers keep
+//! the place the player moved them to; new ones go at the top.
+
+use crate::inherit::profile_chain;
+use crate::nbt::{self, Compound, Tag};
+use crate::paths::Paths;
+use crate::profile::{Profile, ResourcePackPolicy, Server};
+use crate::util::sanitize_filename;
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Server list file, relative to the instance
+pub const SERVERS_DAT: &str = "servers.dat";
+
+/// An entry in an instance's `servers.dat`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerEntry {
+    pub name: String,
+    pub address: String,
+    /// Whether the entry comes from the profile
+    pub managed: bool,
+}
+
+/// Path of a server icon stored in the profile or, failing that, the nearest
+/// base that has it.
+pub fn resolve_server_icon(
+    paths: &Paths,
+    declared: &Profile,
+    icon: &str,
+) -> Result<Option<PathBuf>> {
+    for layer in profile_chain(paths, declared)?.iter().rev() {
+        let path = paths.profile_server_icons(&layer.id).join(icon);
+        if path.is_file() {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Copy a PNG into the profile's `servers/` folder as the icon for `address`.
+/// Returns the file name to put in the server's `icon`.
+pub fn store_server_icon(
+    paths: &Paths,
+    profile_id: &str,
+    address: &str,
+    source: &Path,
+) -> Result<String> {
+    let data = fs::read(source).with_context(|| format!("failed to read {}", source.display()))?;
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        bail!("server icon must be a PNG: {}", source.display());
+    }
+    let dir = paths.profile_server_icons(profile_id);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create dir: {}", dir.display()))?;
+    let file_name = format!(
+        "{}.png",
+        sanitize_filename(&address.to_lowercase().replace(':', "_"))
+    );
+    let target = dir.join(&file_name);
+    fs::write(&target, data).with_context(|| format!("failed to write {}", target.display()))?;
+    Ok(file_name)
+}
+
+/// Merge the servers of `profile` (the resolved form of `declared`) into the
+/// instance's `servers.dat`. Returns the addresses that were added or updated.
+pub fn apply_servers(
+    paths: &Paths,
+    declared: &Profile,
+    profile: &Profile,
+    instance_dir: &Path,
+) -> Result<Vec<String>> {
+    let state_path = paths.instance_state_dir(&profile.id).join("servers.json");
+    let previous: Vec<String> = if state_path.exists() {
+        let data = fs::read_to_string(&state_path)
+            .with_context(|| format!("failed to read {}", state_path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("failed to parse {}", state_path.display()))?
+    } else {
+        Vec::new()
+    };
+    if profile.servers.is_empty() && previous.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut icons = Vec::new();
+    for server in &profile.servers {
+        let icon = match &server.icon {
+            Some(icon) => match resolve_server_icon(paths, declared, icon)? {
+                Some(path) => {
+                    let data = fs::read(&path)
+                        .with_context(|| format!("failed to read {}", path.display()))?;
+                    Some(STANDARD.encode(data))
+                }
+                None => {
+                    eprintln!(
+                        "warning: icon {icon} for server {} not found, keeping the current one",
+                        server.address
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        icons.push(icon);
+    }
+
+    let path = instance_dir.join(SERVERS_DAT);
+    let current = if path.exists() {
+        Some(fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?)
+    } else {
+        None
+    };
+    let mut root = match &current {
+        Some(data) => {
+            nbt::parse(data).with_context(|| format!("failed to parse NBT: {}", path.display()))?
+        }
+        None => Compound::default(),
+    };
+    let entries = match root.get("servers") {
+        Some(Tag::List(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Tag::Compound(entry) => Some(entry.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let merged = merge_entries(entries, &profile.servers, &icons, &previous);
+    root.insert(
+        "servers",
+        Tag::List(merged.entries.into_iter().map(Tag::Compound).collect()),
+    );
+    if current != Some(nbt::to_bytes(&root)?) {
+        nbt::write_file(&path, &root)?;
+    }
+
+    if let Some(parent) = state_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+    }
+    let data =
+        serde_json::to_string_pretty(&merged.owned).context("failed to serialize server state")?;
+    fs::write(&state_path, data)
+        .with_context(|| format!("failed to write {}", state_path.display()))?;
+    Ok(merged.changed)
+}
+
+/// Result of `merge_entries`
+struct MergedServers {
+    entries: Vec<Compound>,
+    /// Declared addresses added or changed
+    changed: Vec<String>,
+    /// Lowercased addresses whose entry belongs to the launcher
+    owned: Vec<String>,
+}
+
+/// Apply the declared servers to the entries of `servers.dat`. `icons` holds
+/// the base64 PNG for each declared server, if it has one; `previous` the
+/// addresses whose entries the launcher owned after the last run. An entry
+/// the player added is never taken over, even if the profile declares its
+/// address.
+fn merge_entries(
+    mut entries: Vec<Compound>,
+    servers: &[Server],
+    icons: &[Option<String>],
+    previous: &[String],
+) -> MergedServers {
+    let address_of = |entry: &Compound| entry.get_str("ip").unwrap_or_default().to_lowercase();
+    let declares = |address: &str| {
+        servers
+            .iter()
+            .any(|server| server.address.eq_ignore_ascii_case(address))
+    };
+    entries.retain(|entry| {
+        let address = address_of(entry);
+        !previous.contains(&address) || declares(&address)
+    });
+
+    let mut changed = Vec::new();
+    let mut owned = Vec::new();
+    let mut added = Vec::new();
+    for (server, icon) in servers.iter().zip(icons) {
+        let address = server.address.to_lowercase();
+        let existing = entries
+            .iter_mut()
+            .find(|entry| address_of(entry) == address);
+        let (entry, is_new) = match existing {
+            Some(entry) if previous.contains(&address) => (entry, false),
+            // The player's own entry for this address
+            Some(_) => continue,
+            None => {
+                added.push(Compound::default());
+                (added.last_mut().expect("just pushed"), true)
+            }
+        };
+        owned.push(address);
+        let before = entry.clone();
+        entry.insert("name", Tag::String(server.name.clone()));
+        entry.insert("ip", Tag::String(server.address.clone()));
+        if let Some(icon) = icon {
+            entry.insert("icon", Tag::String(icon.clone()));
+        }
+        match server.resource_pack {
+            ResourcePackPolicy::Prompt => entry.remove("acceptTextures"),
+            ResourcePackPolicy::Enabled => entry.insert("acceptTextures", Tag::Byte(1)),
+            ResourcePackPolicy::Disabled => entry.insert("acceptTextures", Tag::Byte(0)),
+        }
+        if is_new || *entry != before {
+            changed.push(server.address.clone());
+        }
+    }
+
+    added.extend(entries);
+    MergedServers {
+        entries: added,
+        changed,
+        owned,
+    }
+}
+
+/// Entries of an instance's `servers.dat`, marking those from the profile.
+pub fn list_instance_servers(profile: &Profile, instance_dir: &Path) -> Result<Vec<ServerEntry>> {
+    let path = instance_dir.join(SERVERS_DAT);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let root = nbt::read_file(&path)?;
+    let Some(Tag::List(items)) = root.get("servers") else {
+        return Ok(Vec::new());
+    };
+    Ok(items
+        .iter()
+        .filter_map(|item| match item {
+            Tag::Compound(entry) => Some(entry),
+            _ => None,
+        })
+        .map(|entry| {
+            let address = entry.get_str("ip").unwrap_or_default().to_string();
+            ServerEntry {
+                name: entry.get_str("name").unwrap_or_default().to_string(),
+                managed: profile
+                    .servers
+                    .iter()
+                    .any(|server| server.address.eq_ignore_ascii_case(&address)),
+                address,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, ip: &str) -> Compound {
+        Compound(vec![
+            ("name".to_string(), Tag::String(name.to_string())),
+            ("ip".to_string(), Tag::String(ip.to_string())),
+        ])
+    }
+
+    fn server(name: &str, address: &str) -> Server {
+        Server {
+            name: name.to_string(),
+            address: address.to_string(),
+            icon: None,
+            resource_pack: ResourcePackPolicy::Prompt,
+        }
+    }
+
+    #[test]
+    fn merges_declared_servers_and_keeps_user_entries() {
+        let mut hub = entry("Old name", "hub.example.net");
+        hub.insert("acceptTextures", Tag::Byte(1));
+        let existing = vec![
+            entry("Friend", "friend.example.org"),
+            hub,
+            entry("Retired", "old.example.net"),
+        ];
+        let servers = vec![
+            server("Hub", "HUB.example.net"),
+            Server {
+                resource_pack: ResourcePackPolicy::Disabled,
+                ..server("Creative", "creative.example.net")
+            },
+        ];
+        let previous = vec!["hub.example.net".to_string(), "old.example.net".to_string()];
+
+        let merged = merge_entries(
+            existing,
+            &servers,
+            &[None, Some("aWNvbg==".to_string())],
+            &previous,
+        );
+        let entries = merged.entries;
+        let names: Vec<_> = entries.iter().map(|e| e.get_str("name").unwrap()).collect();
+        assert_eq!(names, ["Creative", "Friend", "Hub"]);
+        assert_eq!(entries[0].get_i64("acceptTextures"), Some(0));
+        assert_eq!(entries[0].get_str("icon"), Some("aWNvbg=="));
+        assert_eq!(entries[2].get("acceptTextures"), None);
+        assert_eq!(merged.changed, ["HUB.example.net", "creative.example.net"]);
+        assert_eq!(merged.owned, ["hub.example.net", "creative.example.net"]);
+
+        let again = merge_entries(entries.clone(), &servers, &[None, None], &merged.owned);
+        assert_eq!(again.entries, entries);
+        assert!(again.changed.is_empty());
+    }
+
+    #[test]
+    fn never_takes_over_player_entries() {
+        let existing = vec![entry("My hub", "hub.example.net")];
+        let merged = merge_entries(
+            existing.clone(),
+            &[server("Hub", "hub.example.net")],
+            &[None],
+            &[],
+        );
+        assert_eq!(merged.entries, existing);
+        assert!(merged.changed.is_empty() && merged.owned.is_empty());
+
+        // Dropping the address from the profile leaves the player's entry
+        let merged = merge_entries(existing.clone(), &[], &[], &merged.owned);
+        assert_eq!(merged.entries, existing);
+    }
+}
@@ -0,0 +1,149 @@
+//! Active shader pack and per-pack shader settings.
+//!
+//! `activeShader` names one of the profile's shader packs. When an instance is
+//! materialized, the shader loader found among the profile's mods is pointed
+//! at that pack: Iris through `config/iris.properties`, OptiFine through
+//! `optionsshaders.txt`. Both loaders keep a pack's settings next to it as
+//! `shaderpacks/<pack file>.txt`; a copy of that file stored in the profile as
+//! `shaders/<pack name>.txt` is written there on every materialize.
+
+use crate::inherit::profile_chain;
+use crate::instance::PlannedFile;
+use crate::options::update_properties_file;
+use crate::paths::Paths;
+use crate::profile::{Profile, ShaderLoader};
+use crate::util::sanitize_filename;
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Iris settings file, relative to the instance
+pub const IRIS_CONFIG: &str = "config/iris.properties";
+/// OptiFine shader settings file, relative to the instance
+pub const OPTIFINE_CONFIG: &str = "optionsshaders.txt";
+
+/// File name of a pack's settings inside `shaderpacks/`
+pub fn settings_file_name(pack_file_name: &str) -> String {
+    format!("{pack_file_name}.txt")
+}
+
+/// Where a profile keeps the settings for a shader pack, by pack name.
+pub fn profile_settings_path(paths: &Paths, profile_id: &str, pack_name: &str) -> PathBuf {
+    paths
+        .profile_shader_settings(profile_id)
+        .join(settings_file_name(&sanitize_filename(pack_name)))
+}
+
+/// The settings file for a pack from the profile or, failing that, the
+/// nearest base that has one.
+pub fn resolve_pack_settings(
+    paths: &Paths,
+    declared: &Profile,
+    pack_name: &str,
+) -> Result<Option<PathBuf>> {
+    for layer in profile_chain(paths, declared)?.iter().rev() {
+        let path = profile_settings_path(paths, &layer.id, pack_name);
+        if path.is_file() {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Copy stored pack settings into the instance and select the active shader in
+/// the detected loader's config. `profile` is the resolved form of `declared`.
+/// Returns the instance files that were written.
+pub fn apply_shaders(
+    paths: &Paths,
+    declared: &Profile,
+    profile: &Profile,
+    instance_dir: &Path,
+    planned: &[PlannedFile],
+) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    let packs: Vec<&PlannedFile> = planned.iter().filter(|f| f.dir == "shaderpacks").collect();
+
+    for pack in &packs {
+        let Some(source) = resolve_pack_settings(paths, declared, &pack.name)? else {
+            continue;
+        };
+        let rel = format!("shaderpacks/{}", settings_file_name(&pack.file_name));
+        let target = instance_dir.join(&rel);
+        let data =
+            fs::read(&source).with_context(|| format!("failed to read {}", source.display()))?;
+        if fs::read(&target).is_ok_and(|current| current == data) {
+            continue;
+        }
+        fs::write(&target, data)
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        written.push(rel);
+    }
+
+    let Some(active) = &profile.active_shader else {
+        return Ok(written);
+    };
+    let Some(item) = profile
+        .shaderpacks
+        .iter()
+        .find(|item| item.name == *active || item.hash == *active)
+    else {
+        bail!("active shader {active} is not one of the profile's shader packs");
+    };
+    let Some(pack) = packs.iter().find(|f| f.name == item.name) else {
+        eprintln!(
+            "warning: active shader '{active}' is disabled or not in the store, not selecting it"
+        );
+        return Ok(written);
+    };
+
+    let (rel, values) = match profile.primary_shader_loader() {
+        Some(ShaderLoader::Iris) => (
+            IRIS_CONFIG,
+            BTreeMap::from([
+                ("shaderPack".to_string(), pack.file_name.clone()),
+                ("enableShaders".to_string(), "true".to_string()),
+            ]),
+        ),
+        Some(ShaderLoader::Optifine) => (
+            OPTIFINE_CONFIG,
+            BTreeMap::from([("shaderPack".to_string(), pack.file_name.clone())]),
+        ),
+        Some(ShaderLoader::Canvas) | None => {
+            eprintln!("warning: no Iris or OptiFine mod in the profile, active shader not applied");
+            return Ok(written);
+        }
+    };
+    if !update_properties_file(&instance_dir.join(rel), &values)?.is_empty() {
+        written.push(rel.to_string());
+    }
+    Ok(written)
+}
+
+/// Store the settings the game wrote for a pack in the instance into the
+/// profile. Returns the path written.
+pub fn save_pack_settings(paths: &Paths, profile_id: &str, pack: &PlannedFile) -> Result<PathBuf> {
+    let source = paths
+        .instance_dir(profile_id)
+        .join(&pack.dir)
+        .join(settings_file_name(&pack.file_name));
+    if !source.is_file() {
+        bail!(
+            "no settings for {} in the instance yet (change them in-game first)",
+            pack.name
+        );
+    }
+    let target = profile_settings_path(paths, profile_id, &pack.name);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+    }
+    fs::copy(&source, &target).with_context(|| {
+        format!(
+            "failed to copy {} to {}",
+            source.display(),
+            target.display()
+        )
+    })?;
+    Ok(target)
+}
@@ -0,0 +1,291 @@
+//! Profile snapshots.
+//!
+//! Every time the launcher is about to overwrite `profile.json`, the current
+//! `profile.json` and a manifest of the override files are saved under
+//! `snapshots/<id>/<snapshot>/` in the launcher home. Override file contents are
+//! kept once per hash in `snapshots/<id>/.blobs`, so a rollback restores both
+//! the manifest and the configs it refers to. Snapshots stay out of the profile
+//! folder, which is often versioned in git.
+
+use crate::migrate::{DocumentKind, parse_document};
+use crate::paths::Paths;
+use crate::profile::{Profile, save_profile};
+use crate::store::{hash_dir, normalize_hash};
+use crate::util::now_epoch_secs;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+
+/// Snapshots kept per profile; older ones are pruned
+pub const MAX_SNAPSHOTS: usize = 50;
+
+const BLOBS_DIR: &str = ".blobs";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotManifest {
+    created: u64,
+    /// Override file path (relative to overrides/) -> "sha256:<hex>"
+    #[serde(default)]
+    overrides: BTreeMap<String, String>,
+}
+
+/// A saved state of a profile
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub id: String,
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    #[serde(rename = "mcVersion")]
+    pub mc_version: String,
+    pub loader: Option<String>,
+    pub mods: usize,
+    pub resourcepacks: usize,
+    pub shaderpacks: usize,
+    pub overrides: usize,
+}
+
+/// Snapshot the profile as it is on disk. Returns the new snapshot id, or
+/// `None` if the profile does not exist yet or is unchanged since the last
+/// snapshot.
+pub fn snapshot_profile(paths: &Paths, id: &str) -> Result<Option<String>> {
+    let profile_path = paths.profile_json(id);
+    if !profile_path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read(&profile_path)
+        .with_context(|| format!("failed to read profile file: {}", profile_path.display()))?;
+    let overrides_dir = paths.profile_overrides(id);
+    let overrides = hash_dir(&overrides_dir)?;
+
+    let snapshots_dir = paths.profile_snapshots(id);
+    if let Some(latest) = snapshot_ids(paths, id)?.last() {
+        let dir = snapshots_dir.join(latest);
+        let same_profile = fs::read(dir.join("profile.json")).is_ok_and(|prev| prev == data);
+        if same_profile && read_manifest(paths, id, latest)?.overrides == overrides {
+            return Ok(None);
+        }
+    }
+
+    let created = now_epoch_secs();
+    let mut snapshot_id = created.to_string();
+    let mut idx = 1;
+    while snapshots_dir.join(&snapshot_id).exists() {
+        snapshot_id = format!("{created}-{idx}");
+        idx += 1;
+    }
+    let dir = snapshots_dir.join(&snapshot_id);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create snapshot dir: {}", dir.display()))?;
+
+    let blobs_dir = snapshots_dir.join(BLOBS_DIR);
+    fs::create_dir_all(&blobs_dir)
+        .with_context(|| format!("failed to create dir: {}", blobs_dir.display()))?;
+    for (rel, hash) in &overrides {
+        let blob = blobs_dir.join(normalize_hash(hash));
+        if !blob.exists() {
+            fs::copy(overrides_dir.join(rel), &blob)
+                .with_context(|| format!("failed to snapshot override {rel}"))?;
+        }
+    }
+
+    fs::write(dir.join("profile.json"), &data).context("failed to write snapshot profile")?;
+    let manifest = SnapshotManifest { created, overrides };
+    let manifest_data =
+        serde_json::to_string_pretty(&manifest).context("failed to serialize snapshot")?;
+    fs::write(dir.join("manifest.json"), manifest_data)
+        .context("failed to write snapshot manifest")?;
+
+    prune_snapshots(paths, id)?;
+    Ok(Some(snapshot_id))
+}
+
+/// List a profile's snapshots, oldest first.
+pub fn list_snapshots(paths: &Paths, id: &str) -> Result<Vec<SnapshotInfo>> {
+    let mut snapshots = Vec::new();
+    for snapshot_id in snapshot_ids(paths, id)? {
+        let manifest = read_manifest(paths, id, &snapshot_id)?;
+        let profile = read_snapshot_profile(paths, id, &snapshot_id)?;
+        snapshots.push(SnapshotInfo {
+            id: snapshot_id,
+            created: manifest.created,
+            mc_version: profile.mc_version,
+            loader: profile
+                .loader
+                .map(|l| format!("{} {}", l.loader_type, l.version)),
+            mods: profile.mods.len(),
+            resourcepacks: profile.resourcepacks.len(),
+            shaderpacks: profile.shaderpacks.len(),
+            overrides: manifest.overrides.len(),
+        });
+    }
+    Ok(snapshots)
+}
+
+/// Restore `profile.json` and the override files from a snapshot. The current
+/// state is snapshotted first, so a rollback can itself be rolled back.
+pub fn rollback_profile(paths: &Paths, id: &str, snapshot_id: &str) -> Result<Profile> {
+    if !snapshot_ids(paths, id)?.iter().any(|s| s == snapshot_id) {
+        bail!("snapshot not found for profile {id}: {snapshot_id}");
+    }
+    let manifest = read_manifest(paths, id, snapshot_id)?;
+    let mut profile = read_snapshot_profile(paths, id, snapshot_id)?;
+    profile.id = id.to_string();
+    save_profile(paths, &profile)?;
+
+    let overrides_dir = paths.profile_overrides(id);
+    let blobs_dir = paths.profile_snapshots(id).join(BLOBS_DIR);
+    let current = hash_dir(&overrides_dir)?;
+    for rel in current
+        .keys()
+        .filter(|rel| !manifest.overrides.contains_key(*rel))
+    {
+        let path = overrides_dir.join(rel);
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    for (rel, hash) in &manifest.overrides {
+        if current.get(rel) == Some(hash) {
+            continue;
+        }
+        let target = overrides_dir.join(rel);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+        }
+        fs::copy(blobs_dir.join(normalize_hash(hash)), &target)
+            .with_context(|| format!("failed to restore override {rel}"))?;
+    }
+
+    Ok(profile)
+}
+
+fn snapshot_ids(paths: &Paths, id: &str) -> Result<Vec<String>> {
+    let dir = paths.profile_snapshots(id);
+    let mut ids = Vec::new();
+    if !dir.exists() {
+        return Ok(ids);
+    }
+    for entry in
+        fs::read_dir(&dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with('.') && entry.path().join("manifest.json").exists() {
+            ids.push(name);
+        }
+    }
+    ids.sort_by_key(|name| sort_key(name));
+    Ok(ids)
+}
+
+/// Snapshot ids are "<epoch>" or "<epoch>-<n>" for several in one second.
+fn sort_key(name: &str) -> (u64, u64) {
+    let (secs, idx) = name.split_once('-').unwrap_or((name, "0"));
+    (secs.parse().unwrap_or(0), idx.parse().unwrap_or(0))
+}
+
+fn read_manifest(paths: &Paths, id: &str, snapshot_id: &str) -> Result<SnapshotManifest> {
+    let path = paths
+        .profile_snapshots(id)
+        .join(snapshot_id)
+        .join("manifest.json");
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read snapshot: {}", path.display()))?;
+    serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse snapshot: {}", path.display()))
+}
+
+fn read_snapshot_profile(paths: &Paths, id: &str, snapshot_id: &str) -> Result<Profile> {
+    let path = paths
+        .profile_snapshots(id)
+        .join(snapshot_id)
+        .join("profile.json");
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read snapshot: {}", path.display()))?;
+    parse_document(DocumentKind::Profile, &data)
+        .with_context(|| format!("failed to parse snapshot: {}", path.display()))
+}
+
+/// Drop the oldest snapshots beyond `MAX_SNAPSHOTS` and any override blobs no
+/// remaining snapshot refers to.
+fn prune_snapshots(paths: &Paths, id: &str) -> Result<()> {
+    let ids = snapshot_ids(paths, id)?;
+    if ids.len() <= MAX_SNAPSHOTS {
+        return Ok(());
+    }
+    let snapshots_dir = paths.profile_snapshots(id);
+    let (old, kept) = ids.split_at(ids.len() - MAX_SNAPSHOTS);
+    for snapshot_id in old {
+        let dir = snapshots_dir.join(snapshot_id);
+        fs::remove_dir_all(&dir)
+            .with_context(|| format!("failed to remove snapshot: {}", dir.display()))?;
+    }
+
+    let mut referenced = HashSet::new();
+    for snapshot_id in kept {
+        let manifest = read_manifest(paths, id, snapshot_id)?;
+        referenced.extend(
+            manifest
+                .overrides
+                .values()
+                .map(|hash| normalize_hash(hash).to_string()),
+        );
+    }
+    let blobs_dir = snapshots_dir.join(BLOBS_DIR);
+    for entry in fs::read_dir(&blobs_dir)
+        .with_context(|| format!("failed to read dir: {}", blobs_dir.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !referenced.contains(&name) {
+            fs::remove_file(entry.path())
+                .with_context(|| format!("failed to remove {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::load_profile;
+    use serde_json::json;
+
+    #[test]
+    fn rolls_back_profile_and_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::from_base(dir.path());
+        let overrides = paths.profile_overrides("a");
+        fs::create_dir_all(overrides.join("config")).unwrap();
+        fs::write(overrides.join("options.txt"), "fov:70\n").unwrap();
+
+        let profile: Profile =
+            serde_json::from_value(json!({ "id": "a", "mcVersion": "1.21.4" })).unwrap();
+        save_profile(&paths, &profile).unwrap();
+        assert!(list_snapshots(&paths, "a").unwrap().is_empty());
+
+        // Saving again snapshots the first version with its overrides
+        let mut changed = profile.clone();
+        changed.mc_version = "1.21.5".to_string();
+        save_profile(&paths, &changed).unwrap();
+        fs::write(overrides.join("options.txt"), "fov:90\n").unwrap();
+        fs::write(overrides.join("config/new.toml"), "x = 1\n").unwrap();
+        let snapshots = list_snapshots(&paths, "a").unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].mc_version, "1.21.4");
+        assert_eq!(snapshots[0].overrides, 1);
+
+        let restored = rollback_profile(&paths, "a", &snapshots[0].id).unwrap();
+        assert_eq!(restored.mc_version, "1.21.4");
+        assert_eq!(load_profile(&paths, "a").unwrap().mc_version, "1.21.4");
+        assert_eq!(
+            fs::read_to_string(overrides.join("options.txt")).unwrap(),
+            "fov:70\n"
+        );
+        assert!(!overrides.join("config/new.toml").exists());
+
+        // The rollback snapshotted the state it replaced, outside the profile
+        assert_eq!(list_snapshots(&paths, "a").unwrap().len(), 2);
+        assert!(!paths.profile_dir("a").join("snapshots").exists());
+    }
+}
@@ -3,6 +3,7 @@ use crate::util::sanitize_filename;
 use anyhow::{Context, Result, bail};
 use reqwest::Url;
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -12,6 +13,7 @@ pub enum ContentKind {
     Mod,
     ResourcePack,
     ShaderPack,
+    Datapack,
     Skin,
 }
 
@@ -29,6 +31,7 @@ impl ContentKind {
             ContentKind::Mod => "mod",
             ContentKind::ResourcePack => "resourcepack",
             ContentKind::ShaderPack => "shaderpack",
+            ContentKind::Datapack => "datapack",
             ContentKind::Skin => "skin",
         }
     }
@@ -52,6 +55,35 @@ pub fn hash_file(path: &Path) -> Result<String> {
     Ok(hex::encode(digest))
 }
 
+/// Hash every file below `dir`, keyed by its `/`-separated relative path.
+pub fn hash_dir(dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    if dir.exists() {
+        hash_dir_into(dir, dir, &mut hashes)?;
+    }
+    Ok(hashes)
+}
+
+fn hash_dir_into(root: &Path, dir: &Path, hashes: &mut BTreeMap<String, String>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read dir: {}", dir.display()))? {
+        let entry = entry.context("failed to read dir entry")?;
+        let path = entry.path();
+        if entry.file_type().context("failed to read entry type")?.is_dir() {
+            hash_dir_into(root, &path, hashes)?;
+            continue;
+        }
+        let rel = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        hashes.insert(rel, format!("sha256:{}", hash_file(&path)?));
+    }
+    Ok(())
+}
+
 pub fn normalize_hash(input: &str) -> &str {
     input.strip_prefix("sha256:").unwrap_or(input)
 }
@@ -137,6 +169,7 @@ pub fn content_store_path(paths: &Paths, kind: ContentKind, hash: &str) -> PathB
         ContentKind::Mod => paths.store_mod_path(hash_hex),
         ContentKind::ResourcePack => paths.store_resourcepack_path(hash_hex),
         ContentKind::ShaderPack => paths.store_shaderpack_path(hash_hex),
+        ContentKind::Datapack => paths.store_datapack_path(hash_hex),
         ContentKind::Skin => paths.store_skin_path(hash_hex),
     }
 }
@@ -1,12 +1,18 @@
+use crate::config::load_config;
+use crate::migrate::{DocumentKind, TEMPLATE_SCHEMA_VERSION, load_document};
 use crate::paths::Paths;
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
 /// A profile template that can be used to generate new profiles
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Template {
+    /// On-disk format version (see `migrate`)
+    #[serde(rename = "schemaVersion", default)]
+    pub schema_version: u32,
     /// Unique identifier for this template
     pub id: String,
     /// Human-readable name
@@ -35,7 +41,7 @@ pub struct Template {
 }
 
 /// Loader configuration for a template
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TemplateLoader {
     /// Loader type (e.g., "fabric", "forge", "quilt", "neoforge")
     #[serde(rename = "type")]
@@ -45,7 +51,7 @@ pub struct TemplateLoader {
 }
 
 /// Content reference in a template (mod, resourcepack, or shaderpack)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TemplateContent {
     /// Human-readable name
     pub name: String,
@@ -64,7 +70,7 @@ fn default_true() -> bool {
 }
 
 /// Source for template content
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ContentSource {
     /// Modrinth project
@@ -85,7 +91,7 @@ pub enum ContentSource {
 }
 
 /// Runtime configuration for a template
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct TemplateRuntime {
     /// Java executable path (optional, uses system default)
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -117,12 +123,8 @@ impl Paths {
 
 /// Load a template by ID
 pub fn load_template(paths: &Paths, id: &str) -> Result<Template> {
-    let path = paths.template_json(id);
-    let data = fs::read_to_string(&path)
-        .with_context(|| format!("failed to read template file: {}", path.display()))?;
-    let template: Template = serde_json::from_str(&data)
-        .with_context(|| format!("failed to parse template JSON: {}", path.display()))?;
-    Ok(template)
+    let strict = load_config(paths)?.strict_schema;
+    load_document(paths, DocumentKind::Template, &paths.template_json(id), strict)
 }
 
 /// Save a template
@@ -173,6 +175,7 @@ pub fn delete_template(paths: &Paths, id: &str) -> Result<bool> {
 /// Create a built-in vanilla template
 pub fn create_vanilla_template() -> Template {
     Template {
+        schema_version: TEMPLATE_SCHEMA_VERSION,
         id: "vanilla".to_string(),
         name: "Vanilla".to_string(),
         description: "Pure Minecraft experience with no mods.".to_string(),
@@ -192,6 +195,7 @@ pub fn create_vanilla_template() -> Template {
 /// Create a built-in default template for optimized Fabric gameplay
 pub fn create_default_template() -> Template {
     Template {
+        schema_version: TEMPLATE_SCHEMA_VERSION,
         id: "default".to_string(),
         name: "Default".to_string(),
         description: "Optimized Fabric with Sodium, Iris, and performance mods.".to_string(),
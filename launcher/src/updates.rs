@@ -1,14 +1,15 @@
 //! Content update checking and storage statistics
 //!
 //! This module provides functionality for:
-//! - Checking for updates to mods, resourcepacks, and shaderpacks
+//! - Checking for updates to mods, resourcepacks, shaderpacks and datapacks
 //! - Calculating storage usage statistics
 //! - Deduplication savings tracking
 
 use crate::content_store::{ContentStore, ContentType, Platform};
 use crate::paths::Paths;
 use crate::profile::{ContentRef, Profile, load_profile, save_profile, list_profiles};
-use anyhow::{Result, Context};
+use crate::util::dir_size;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs;
@@ -24,6 +25,8 @@ pub struct StorageStats {
     pub resourcepacks_bytes: u64,
     /// Storage used by shader packs
     pub shaderpacks_bytes: u64,
+    /// Storage used by datapacks
+    pub datapacks_bytes: u64,
     /// Storage used by skins
     pub skins_bytes: u64,
     /// Storage used by Minecraft versions/libraries/assets
@@ -78,6 +81,7 @@ pub fn get_storage_stats(paths: &Paths) -> Result<StorageStats> {
     stats.mods_bytes = dir_size(&paths.store_mods)?;
     stats.resourcepacks_bytes = dir_size(&paths.store_resourcepacks)?;
     stats.shaderpacks_bytes = dir_size(&paths.store_shaderpacks)?;
+    stats.datapacks_bytes = dir_size(&paths.store_datapacks)?;
     stats.skins_bytes = dir_size(&paths.store_skins)?;
 
     // Calculate Minecraft data size
@@ -95,6 +99,7 @@ pub fn get_storage_stats(paths: &Paths) -> Result<StorageStats> {
     stats.total_bytes = stats.mods_bytes
         + stats.resourcepacks_bytes
         + stats.shaderpacks_bytes
+        + stats.datapacks_bytes
         + stats.skins_bytes
         + stats.minecraft_bytes
         + stats.database_bytes;
@@ -108,6 +113,7 @@ pub fn get_storage_stats(paths: &Paths) -> Result<StorageStats> {
         (&paths.store_mods, "mod"),
         (&paths.store_resourcepacks, "resourcepack"),
         (&paths.store_shaderpacks, "shaderpack"),
+        (&paths.store_datapacks, "datapack"),
         (&paths.store_skins, "skin"),
     ] {
         if store_path.exists() {
@@ -141,6 +147,11 @@ pub fn get_storage_stats(paths: &Paths) -> Result<StorageStats> {
                     *hash_counts.entry(hash).or_insert(0) += 1;
                     stats.total_references += 1;
                 }
+                for d in &profile.datapacks {
+                    let hash = normalize_hash(&d.hash);
+                    *hash_counts.entry(hash).or_insert(0) += 1;
+                    stats.total_references += 1;
+                }
             }
         }
     }
@@ -150,7 +161,10 @@ pub fn get_storage_stats(paths: &Paths) -> Result<StorageStats> {
     // Calculate deduplication savings
     // For each hash referenced more than once, we save (ref_count - 1) * file_size
     if stats.unique_items > 0 && stats.total_references > stats.unique_items {
-        let avg_size = (stats.mods_bytes + stats.resourcepacks_bytes + stats.shaderpacks_bytes)
+        let avg_size = (stats.mods_bytes
+            + stats.resourcepacks_bytes
+            + stats.shaderpacks_bytes
+            + stats.datapacks_bytes)
             .checked_div(stats.unique_items as u64)
             .unwrap_or(0);
         let duplicated_refs = stats.total_references.saturating_sub(stats.unique_items);
@@ -202,6 +216,15 @@ pub fn check_all_updates(paths: &Paths, curseforge_api_key: Option<&str>) -> Res
             "shaderpack",
             &mut result,
         );
+
+        // Check datapacks
+        check_content_updates(
+            &store,
+            &profile,
+            &profile.datapacks,
+            "datapack",
+            &mut result,
+        );
     }
 
     Ok(result)
@@ -239,6 +262,15 @@ pub fn check_profile_updates(
         &mut result,
     );
 
+    // Check datapacks
+    check_content_updates(
+        &store,
+        &profile,
+        &profile.datapacks,
+        "datapack",
+        &mut result,
+    );
+
     Ok(result)
 }
 
@@ -249,7 +281,12 @@ fn check_content_updates(
     content_type: &str,
     result: &mut UpdateCheckResult,
 ) {
-    let loader = profile.loader.as_ref().map(|l| l.loader_type.as_str());
+    // Modrinth publishes datapack versions under their own "datapack" loader
+    let loader = if content_type == "datapack" {
+        Some("datapack")
+    } else {
+        profile.loader.as_ref().map(|l| l.loader_type.as_str())
+    };
 
     for content in content_list {
         // Skip pinned content
@@ -332,6 +369,7 @@ pub fn apply_update(
         "mod" => &mut profile.mods,
         "resourcepack" => &mut profile.resourcepacks,
         "shaderpack" => &mut profile.shaderpacks,
+        "datapack" => &mut profile.datapacks,
         _ => return Err(anyhow::anyhow!("invalid content type: {}", content_type)),
     };
 
@@ -368,6 +406,7 @@ pub fn apply_update(
         "mod" => ContentType::Mod,
         "resourcepack" => ContentType::ResourcePack,
         "shaderpack" => ContentType::ShaderPack,
+        "datapack" => ContentType::Datapack,
         _ => ContentType::Mod,
     };
 
@@ -398,6 +437,7 @@ pub fn set_content_pinned(
         "mod" => &mut profile.mods,
         "resourcepack" => &mut profile.resourcepacks,
         "shaderpack" => &mut profile.shaderpacks,
+        "datapack" => &mut profile.datapacks,
         _ => return Err(anyhow::anyhow!("invalid content type: {}", content_type)),
     };
 
@@ -425,6 +465,7 @@ pub fn set_content_enabled(
         "mod" => &mut profile.mods,
         "resourcepack" => &mut profile.resourcepacks,
         "shaderpack" => &mut profile.shaderpacks,
+        "datapack" => &mut profile.datapacks,
         _ => return Err(anyhow::anyhow!("invalid content type: {}", content_type)),
     };
 
@@ -442,22 +483,3 @@ pub fn set_content_enabled(
 fn normalize_hash(hash: &str) -> String {
     hash.strip_prefix("sha256:").unwrap_or(hash).to_string()
 }
-
-/// Calculate the total size of a directory recursively
-fn dir_size(path: &std::path::Path) -> Result<u64> {
-    if !path.exists() {
-        return Ok(0);
-    }
-
-    let mut total = 0u64;
-    for entry in fs::read_dir(path).with_context(|| format!("failed to read dir: {}", path.display()))? {
-        let entry = entry?;
-        let metadata = entry.metadata()?;
-        if metadata.is_dir() {
-            total += dir_size(&entry.path())?;
-        } else {
-            total += metadata.len();
-        }
-    }
-    Ok(total)
-}
@@ -48,6 +48,27 @@ pub fn copy_dir_merge(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Calculate the total size of a directory recursively
+pub fn dir_size(path: &Path) -> Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let mut total = 0u64;
+    for entry in
+        fs::read_dir(path).with_context(|| format!("failed to read dir: {}", path.display()))?
+    {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
 pub fn sanitize_filename(name: &str) -> String {
     let mut out = String::with_capacity(name.len());
     for ch in name.chars() {
@@ -0,0 +1,373 @@
+//! Worlds in an instance's `saves/` folder.
+//!
+//! Each world is a folder with a gzip-compressed NBT `level.dat`. Worlds are
+//! never part of a profile: they live only in the instance, so this module
+//! works on `instances/<id>/saves/` directly.
+
+use crate::nbt::{self, Compound};
+use crate::paths::Paths;
+use crate::profile::is_valid_world_name;
+use crate::util::dir_size;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::ZipArchive;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+/// World data file
+pub const LEVEL_DAT: &str = "level.dat";
+/// Lock file the game holds while a world is open; never copied
+pub const SESSION_LOCK: &str = "session.lock";
+
+/// A world, as read from its `level.dat`
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldInfo {
+    /// Folder name in `saves/`
+    pub folder: String,
+    /// Name shown in the game's world list
+    pub name: String,
+    /// survival, creative, adventure or spectator
+    pub game_mode: String,
+    pub hardcore: bool,
+    /// Game version the world was last opened with
+    pub version: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub last_played: Option<i64>,
+    pub seed: Option<i64>,
+    /// Size of the world folder in bytes
+    pub size: u64,
+}
+
+/// Read the world in `dir`.
+pub fn read_world(dir: &Path) -> Result<WorldInfo> {
+    let folder = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let root = nbt::read_file(&dir.join(LEVEL_DAT))?;
+    let empty = Compound::default();
+    let data = root.get_compound("Data").unwrap_or(&empty);
+
+    let seed = data
+        .get_compound("WorldGenSettings")
+        .and_then(|settings| settings.get_i64("seed"))
+        .or_else(|| data.get_i64("RandomSeed"));
+    let game_mode = match data.get_i64("GameType") {
+        Some(1) => "creative",
+        Some(2) => "adventure",
+        Some(3) => "spectator",
+        _ => "survival",
+    };
+
+    Ok(WorldInfo {
+        name: data
+            .get_str("LevelName")
+            .map(str::to_string)
+            .unwrap_or_else(|| folder.clone()),
+        folder,
+        game_mode: game_mode.to_string(),
+        hardcore: data.get_i64("hardcore").is_some_and(|v| v != 0),
+        version: data
+            .get_compound("Version")
+            .and_then(|version| version.get_str("Name"))
+            .map(str::to_string),
+        last_played: data.get_i64("LastPlayed"),
+        seed,
+        size: dir_size(dir)?,
+    })
+}
+
+/// List the worlds of a profile's instance, most recently played first.
+/// Folders whose `level.dat` cannot be read are skipped with a warning.
+pub fn list_worlds(paths: &Paths, profile_id: &str) -> Result<Vec<WorldInfo>> {
+    let saves = paths.instance_saves(profile_id);
+    let mut worlds = Vec::new();
+    if !saves.is_dir() {
+        return Ok(worlds);
+    }
+    for entry in
+        fs::read_dir(&saves).with_context(|| format!("failed to read dir: {}", saves.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        let dir = entry.path();
+        if !dir.join(LEVEL_DAT).is_file() {
+            continue;
+        }
+        match read_world(&dir) {
+            Ok(world) => worlds.push(world),
+            Err(err) => eprintln!("warning: skipping world {}: {err:#}", dir.display()),
+        }
+    }
+    worlds.sort_by(|a, b| {
+        b.last_played
+            .cmp(&a.last_played)
+            .then_with(|| a.folder.cmp(&b.folder))
+    });
+    Ok(worlds)
+}
+
+/// Path of an existing world in a profile's instance.
+pub fn world_dir(paths: &Paths, profile_id: &str, folder: &str) -> Result<PathBuf> {
+    if !is_valid_world_name(folder) {
+        bail!("invalid world name: {folder}");
+    }
+    let dir = paths.instance_saves(profile_id).join(folder);
+    if !dir.join(LEVEL_DAT).is_file() {
+        bail!("world {folder} not found in profile {profile_id}");
+    }
+    Ok(dir)
+}
+
+/// Whether a game has the world open. Minecraft holds a lock on
+/// `session.lock` for as long as the world is loaded.
+pub fn is_world_open(dir: &Path) -> bool {
+    let path = dir.join(SESSION_LOCK);
+    path.is_file() && is_lock_held(&path)
+}
+
+/// Java takes a POSIX (fcntl) lock, which `flock`-based locking does not see
+/// on Linux, so the file is looked up in `/proc/locks` by inode instead.
+#[cfg(target_os = "linux")]
+fn is_lock_held(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(inode) = fs::metadata(path).map(|meta| meta.ino()) else {
+        return false;
+    };
+    let Ok(locks) = fs::read_to_string("/proc/locks") else {
+        return false;
+    };
+    // e.g. "1: POSIX  ADVISORY  WRITE 1234 08:02:131074 0 EOF"
+    locks.lines().any(|line| {
+        line.split_whitespace()
+            .find_map(|field| field.rsplit_once(':').filter(|(dev, _)| dev.contains(':')))
+            .is_some_and(|(_, ino)| ino.parse() == Ok(inode))
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_lock_held(path: &Path) -> bool {
+    fs::File::open(path)
+        .is_ok_and(|file| matches!(file.try_lock(), Err(fs::TryLockError::WouldBlock)))
+}
+
+/// Where a new world would go, failing if the folder is taken.
+fn new_world_dir(paths: &Paths, profile_id: &str, folder: &str) -> Result<PathBuf> {
+    if !is_valid_world_name(folder) {
+        bail!("invalid world name: {folder}");
+    }
+    if !paths.is_profile_present(profile_id) {
+        bail!("profile not found: {profile_id}");
+    }
+    let dir = paths.instance_saves(profile_id).join(folder);
+    if dir.exists() {
+        bail!("world {folder} already exists in profile {profile_id}");
+    }
+    Ok(dir)
+}
+
+/// Copy a world from one profile's instance to another's, as `name` if given.
+/// Returns the folder name of the copy.
+pub fn copy_world(
+    paths: &Paths,
+    from_profile: &str,
+    folder: &str,
+    to_profile: &str,
+    name: Option<&str>,
+) -> Result<String> {
+    let src = world_dir(paths, from_profile, folder)?;
+    let target = name.unwrap_or(folder);
+    let dst = new_world_dir(paths, to_profile, target)?;
+    copy_world_files(&src, &dst)?;
+    Ok(target.to_string())
+}
+
+fn copy_world_files(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst).with_context(|| format!("failed to create dir: {}", dst.display()))?;
+    for entry in
+        fs::read_dir(src).with_context(|| format!("failed to read dir: {}", src.display()))?
+    {
+        let entry = entry.context("failed to read dir entry")?;
+        if entry.file_name() == SESSION_LOCK {
+            continue;
+        }
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+        if entry
+            .file_type()
+            .context("failed to read entry type")?
+            .is_dir()
+        {
+            copy_world_files(&from, &to)?;
+        } else {
+            fs::copy(&from, &to).with_context(|| {
+                format!("failed to copy {} to {}", from.display(), to.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Import a world from a zip into a profile's instance. The world is the
+/// folder holding the archive's top-most `level.dat`; it is saved as `name`,
+/// or else under its folder name in the archive (the zip's file name if
+/// `level.dat` is at the root). Returns the folder name.
+pub fn import_world_zip(
+    paths: &Paths,
+    profile_id: &str,
+    zip_path: &Path,
+    name: Option<&str>,
+) -> Result<String> {
+    let file = fs::File::open(zip_path)
+        .with_context(|| format!("failed to open {}", zip_path.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("failed to read zip: {}", zip_path.display()))?;
+
+    let entries: Vec<PathBuf> = (0..archive.len())
+        .map(|i| {
+            let entry = archive.by_index(i).context("failed to read zip entry")?;
+            entry
+                .enclosed_name()
+                .with_context(|| format!("unsafe path in zip: {}", entry.name()))
+        })
+        .collect::<Result<_>>()?;
+    let Some(root) = world_root(&entries) else {
+        bail!("no {LEVEL_DAT} in {}", zip_path.display());
+    };
+
+    let folder = match name {
+        Some(name) => name.to_string(),
+        None => root
+            .file_name()
+            .or_else(|| zip_path.file_stem())
+            .map(|n| n.to_string_lossy().to_string())
+            .context("cannot name the world; pass a name")?,
+    };
+    let dst = new_world_dir(paths, profile_id, &folder)?;
+    fs::create_dir_all(&dst).with_context(|| format!("failed to create dir: {}", dst.display()))?;
+
+    for (i, path) in entries.iter().enumerate() {
+        let Ok(rel) = path.strip_prefix(&root) else {
+            continue;
+        };
+        if rel.as_os_str().is_empty() || rel == Path::new(SESSION_LOCK) {
+            continue;
+        }
+        let mut entry = archive.by_index(i).context("failed to read zip entry")?;
+        let target = dst.join(rel);
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .with_context(|| format!("failed to create dir: {}", target.display()))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create dir: {}", parent.display()))?;
+        }
+        let mut out = fs::File::create(&target)
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        io::copy(&mut entry, &mut out)
+            .with_context(|| format!("failed to extract {}", path.display()))?;
+    }
+    Ok(folder)
+}
+
+/// The folder containing the shallowest `level.dat`, ignoring macOS metadata.
+fn world_root(entries: &[PathBuf]) -> Option<PathBuf> {
+    entries
+        .iter()
+        .filter(|path| path.file_name().is_some_and(|n| n == LEVEL_DAT))
+        .filter(|path| !path.starts_with("__MACOSX"))
+        .min_by_key(|path| path.components().count())
+        .map(|path| path.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+/// Export a world as a zip with the world folder at its root.
+pub fn export_world_zip(paths: &Paths, profile_id: &str, folder: &str, out: &Path) -> Result<()> {
+    let src = world_dir(paths, profile_id, folder)?;
+    let file =
+        fs::File::create(out).with_context(|| format!("failed to create {}", out.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+    add_dir_to_zip(&mut zip, &src, Path::new(folder), options)?;
+    zip.finish()
+        .with_context(|| format!("failed to write {}", out.display()))?;
+    Ok(())
+}
+
+fn add_dir_to_zip<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    dir: &Path,
+    prefix: &Path,
+    options: SimpleFileOptions,
+) -> Result<()> {
+    zip.add_directory(zip_name(prefix), options)
+        .context("failed to write zip entry")?;
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read dir: {}", dir.display()))?
+        .collect::<io::Result<_>>()
+        .context("failed to read dir entry")?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if entry.file_name() == SESSION_LOCK {
+            continue;
+        }
+        let path = entry.path();
+        let name = prefix.join(entry.file_name());
+        if entry
+            .file_type()
+            .context("failed to read entry type")?
+            .is_dir()
+        {
+            add_dir_to_zip(zip, &path, &name, options)?;
+            continue;
+        }
+        zip.start_file(zip_name(&name), options)
+            .context("failed to write zip entry")?;
+        let mut file =
+            fs::File::open(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        io::copy(&mut file, zip)
+            .with_context(|| format!("failed to compress {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Zip entry name for a relative path: `/`-separated on every platform.
+fn zip_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_topmost_level_dat() {
+        let entries: Vec<PathBuf> = [
+            "__MACOSX/level.dat",
+            "My World/level.dat",
+            "My World/region/r.0.0.mca",
+            "My World/backup/level.dat",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(world_root(&entries), Some(PathBuf::from("My World")));
+
+        let entries = vec![
+            PathBuf::from("level.dat"),
+            PathBuf::from("region/r.0.0.mca"),
+        ];
+        assert_eq!(world_root(&entries), Some(PathBuf::new()));
+        assert_eq!(world_root(&[PathBuf::from("readme.txt")]), None);
+    }
+}